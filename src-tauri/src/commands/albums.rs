@@ -0,0 +1,67 @@
+use crate::error::AppError;
+use crate::models::album_types::Album;
+use crate::services::db::Database;
+use tauri::State;
+
+/// Trimmed album name, or an error for blank input.
+fn album_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Album name must not be empty".into());
+    }
+    Ok(name.to_string())
+}
+
+/// Map a UNIQUE violation on `albums.name` to a readable message.
+fn name_err(name: &str, e: rusqlite::Error) -> AppError {
+    match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("An album named \"{}\" already exists", name).into()
+        }
+        rusqlite::Error::QueryReturnedNoRows => "Album not found".into(),
+        e => e.into(),
+    }
+}
+
+#[tauri::command]
+pub fn list_albums(db: State<'_, Database>) -> Result<Vec<Album>, AppError> {
+    Ok(db.list_albums()?)
+}
+
+#[tauri::command]
+pub fn create_album(name: String, db: State<'_, Database>) -> Result<Album, AppError> {
+    let name = album_name(&name)?;
+    let id = db.create_album(&name).map_err(|e| name_err(&name, e))?;
+    Ok(db.get_album(id)?)
+}
+
+#[tauri::command]
+pub fn rename_album(album_id: i64, name: String, db: State<'_, Database>) -> Result<Album, AppError> {
+    let name = album_name(&name)?;
+    db.rename_album(album_id, &name).map_err(|e| name_err(&name, e))?;
+    Ok(db.get_album(album_id)?)
+}
+
+/// Delete an album; its photos stay on disk and in the library.
+#[tauri::command]
+pub fn delete_album(album_id: i64, db: State<'_, Database>) -> Result<(), AppError> {
+    Ok(db.delete_album(album_id)?)
+}
+
+/// Append photos to an album. Returns how many were not already in it.
+#[tauri::command]
+pub fn add_to_album(album_id: i64, paths: Vec<String>, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.add_to_album(album_id, &paths)?)
+}
+
+#[tauri::command]
+pub fn remove_from_album(album_id: i64, paths: Vec<String>, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.remove_from_album(album_id, &paths)?)
+}
+
+/// Move the photos at `paths` to the front of the album in the given order; pass every
+/// member to set the full order.
+#[tauri::command]
+pub fn reorder_album(album_id: i64, paths: Vec<String>, db: State<'_, Database>) -> Result<(), AppError> {
+    Ok(db.reorder_album(album_id, &paths)?)
+}
@@ -218,7 +218,10 @@ pub async fn classify_images(
                 // Store tags in DB for the final file location
                 {
                     let file_path_str = final_path.to_string_lossy().to_string();
-                    let tags: Vec<String> = predictions.iter().map(|p| p.class_name.clone()).collect();
+                    let tags: Vec<(String, f32)> = predictions
+                        .iter()
+                        .map(|p| (p.class_name.clone(), p.confidence))
+                        .collect();
 
                     let metadata = std::fs::metadata(&final_path);
                     let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
@@ -232,7 +235,7 @@ pub async fn classify_images(
 
                     if let Ok((id, _)) = db_state.upsert_photo(&file_path_str, size, modified, None, None) {
                         if !tags.is_empty() {
-                            if let Err(e) = db_state.add_tags(id, &tags) {
+                            if let Err(e) = db_state.replace_model_tags(id, current_model_type.tag_source(), &tags) {
                                 eprintln!("Failed to save tags for {}: {}", file_name, e);
                             }
                         }
@@ -253,6 +256,7 @@ pub async fn classify_images(
     .await
     .map_err(|e| AppError {
         message: format!("Task join failed: {}", e),
+        ..Default::default()
     })??;
 
     // Filter out empty results (cancelled items)
@@ -274,8 +278,13 @@ pub async fn classify_images(
 }
 
 #[tauri::command]
-pub async fn delete_all_tags(db: State<'_, Database>, folder: String) -> Result<(), AppError> {
-    db.delete_tags_for_folder(&folder).map_err(|e| AppError {
+pub async fn delete_all_tags(
+    db: State<'_, Database>,
+    folder: String,
+    recursive: Option<bool>,
+) -> Result<(), AppError> {
+    db.delete_tags_for_folder(&folder, recursive.unwrap_or(false)).map_err(|e| AppError {
         message: format!("Failed to delete tags: {}", e),
+        ..Default::default()
     })
 }
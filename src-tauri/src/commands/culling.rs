@@ -0,0 +1,29 @@
+use crate::error::AppError;
+use crate::models::culling_types::{ColorLabel, PhotoFlag};
+use crate::services::db::Database;
+use tauri::State;
+
+/// Set the star rating (0 clears it) of the given photos. Returns how many were updated.
+#[tauri::command]
+pub fn set_rating(paths: Vec<String>, rating: u8, db: State<'_, Database>) -> Result<usize, AppError> {
+    if rating > 5 {
+        return Err(format!("Rating must be between 0 and 5, got {}", rating).into());
+    }
+    Ok(db.set_rating(&paths, rating)?)
+}
+
+/// Set the color label of the given photos, or clear it with `None`.
+#[tauri::command]
+pub fn set_color_label(
+    paths: Vec<String>,
+    label: Option<ColorLabel>,
+    db: State<'_, Database>,
+) -> Result<usize, AppError> {
+    Ok(db.set_color_label(&paths, label)?)
+}
+
+/// Mark the given photos as picked, rejected or unflagged.
+#[tauri::command]
+pub fn set_flag(paths: Vec<String>, flag: PhotoFlag, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.set_flag(&paths, flag)?)
+}
@@ -0,0 +1,147 @@
+use crate::error::AppError;
+use crate::models::duplicate_types::{DuplicateGroup, DuplicateReport, NearDuplicateGroup};
+use crate::services::db::Database;
+use crate::services::fs_service;
+use crate::services::phash_service;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauri::{AppHandle, Emitter, State};
+
+/// Find photos with identical content in `folder`, or in the whole library when `None`.
+/// Only photos sharing a size with another photo are hashed, and hashes are kept until the
+/// file changes, so repeated runs are cheap. Emits `duplicate-progress` while hashing.
+#[tauri::command]
+pub async fn find_duplicates(
+    app: AppHandle,
+    db: State<'_, Database>,
+    folder: Option<String>,
+    recursive: Option<bool>,
+) -> Result<DuplicateReport, AppError> {
+    let db = db.inner().clone();
+    let recursive = recursive.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let mut candidates = db.get_duplicate_candidates(folder.as_deref(), recursive)?;
+
+        // Hash what earlier runs have not
+        let missing: Vec<(usize, i64, String)> = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.content_hash.is_none())
+            .map(|(i, c)| (i, c.id, c.path.clone()))
+            .collect();
+        let total = missing.len();
+        let current = AtomicUsize::new(0);
+        let hashed: Vec<(usize, i64, String)> = missing
+            .into_par_iter()
+            .filter_map(|(i, id, path)| {
+                let hash = fs_service::content_hash(Path::new(&path));
+                let count = current.fetch_add(1, Ordering::Relaxed) + 1;
+                let _ = app.emit("duplicate-progress", serde_json::json!({
+                    "current": count,
+                    "total": total,
+                }));
+                match hash {
+                    Ok(hash) => Some((i, id, hash)),
+                    Err(e) => {
+                        eprintln!("Failed to hash {}: {}", path, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        let _ = app.emit("duplicate-progress", serde_json::json!({ "done": true }));
+
+        let hashes: Vec<(i64, String)> = hashed.iter().map(|(_, id, hash)| (*id, hash.clone())).collect();
+        db.set_content_hashes(&hashes)?;
+        for (i, _, hash) in hashed {
+            candidates[i].content_hash = Some(hash);
+        }
+
+        // Candidates arrive in keep-first order, which the groups preserve
+        let mut by_content: HashMap<(u64, String), Vec<String>> = HashMap::new();
+        let mut order = Vec::new();
+        for c in candidates {
+            let Some(hash) = c.content_hash else { continue };
+            let key = (c.size, hash);
+            if !by_content.contains_key(&key) {
+                order.push(key.clone());
+            }
+            by_content.entry(key).or_default().push(c.path);
+        }
+
+        let groups: Vec<DuplicateGroup> = order
+            .into_iter()
+            .filter_map(|key| {
+                let paths = by_content.remove(&key)?;
+                (paths.len() > 1).then(|| DuplicateGroup {
+                    hash: key.1,
+                    size: key.0,
+                    paths,
+                })
+            })
+            .collect();
+        let wasted_bytes = groups
+            .iter()
+            .map(|g| g.size * (g.paths.len() as u64 - 1))
+            .sum();
+
+        Ok(DuplicateReport {
+            groups,
+            wasted_bytes,
+            hashed: total,
+        })
+    })
+    .await
+    .map_err(|e| AppError {
+        message: format!("Task join failed: {}", e),
+        ..Default::default()
+    })?
+}
+
+/// Cluster look-alike photos in `folder`, or in the whole library when `None`, by the
+/// Hamming distance between their perceptual hashes (default 8 of 64 bits). Photos are
+/// visited in keep-first order; each photo not yet grouped collects every other ungrouped
+/// photo within `threshold` of it. Photos whose hash is still being computed are skipped.
+#[tauri::command]
+pub async fn find_near_duplicates(
+    db: State<'_, Database>,
+    folder: Option<String>,
+    recursive: Option<bool>,
+    threshold: Option<u32>,
+) -> Result<Vec<NearDuplicateGroup>, AppError> {
+    let db = db.inner().clone();
+    let recursive = recursive.unwrap_or(false);
+    let threshold = threshold.unwrap_or(phash_service::DEFAULT_NEAR_DUPLICATE_DISTANCE);
+
+    tokio::task::spawn_blocking(move || {
+        let mut remaining = db.get_perceptual_hashes(folder.as_deref(), recursive)?;
+
+        let mut groups = Vec::new();
+        while !remaining.is_empty() {
+            let (first_path, first_hash) = remaining.remove(0);
+            let mut paths = vec![first_path];
+            let mut max_distance = 0;
+            remaining.retain(|(path, hash)| {
+                let distance = phash_service::hamming_distance(first_hash, *hash);
+                if distance > threshold {
+                    return true;
+                }
+                paths.push(path.clone());
+                max_distance = max_distance.max(distance);
+                false
+            });
+            if paths.len() > 1 {
+                groups.push(NearDuplicateGroup { paths, max_distance });
+            }
+        }
+        Ok(groups)
+    })
+    .await
+    .map_err(|e| AppError {
+        message: format!("Task join failed: {}", e),
+        ..Default::default()
+    })?
+}
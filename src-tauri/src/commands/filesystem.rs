@@ -1,18 +1,25 @@
 use crate::error::AppError;
 use crate::models::fs_types::{DirEntry, DriveInfo, PhotoEntry};
+use crate::models::query_types::{PageCursor, PhotoPage, PhotoQueryParams};
 use crate::services::classifier::inference;
 use crate::services::classifier::model_manager::ModelManager;
 use crate::services::fs_service;
-use crate::services::thumbnail_service;
+use crate::services::search_query::{self, SearchQuery};
+use crate::services::phash_service;
+use crate::services::thumbnail_cache::ThumbnailCache;
 use crate::services::exif_service;
 use crate::services::watcher::FolderWatcher;
 use std::path::{Path, PathBuf};
 
-use crate::services::db::Database;
+use crate::services::db::{Database, PhotoQuery, PhotoRow};
+use tauri::ipc::Channel;
 use tauri::{AppHandle, Emitter, State};
 use rayon::prelude::*;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+/// Page size for `query_photos_page` and `stream_photos` when the caller doesn't pass one.
+const DEFAULT_PAGE_SIZE: usize = 500;
+
 #[tauri::command]
 pub fn list_drives() -> Result<Vec<DriveInfo>, AppError> {
     fs_service::list_drives()
@@ -36,92 +43,73 @@ pub fn list_directory(path: String) -> Result<Vec<DirEntry>, AppError> {
 #[tauri::command]
 pub async fn list_photos(
     path: String,
+    recursive: Option<bool>,
+    max_depth: Option<usize>,
     db: State<'_, Database>,
+    thumbnails: State<'_, ThumbnailCache>,
     app: AppHandle,
     watcher: State<'_, FolderWatcher>,
 ) -> Result<(), AppError> {
     let db = db.inner().clone();
+    let thumbnails = thumbnails.inner().clone();
     let app_handle = app.clone();
     let path_for_task = path.clone();
+    let recursive = recursive.unwrap_or(false);
 
     // Run heavy filesystem I/O and DB operations on a blocking thread
     // so we don't starve the async runtime (keeps IPC responsive for thumbnails)
     tokio::task::spawn_blocking(move || -> Result<(), AppError> {
-        // Use list_image_files_with_meta — metadata comes free from DirEntry on Windows
-        let image_files = fs_service::list_image_files_with_meta(&path_for_task)?;
-        let total_files = image_files.len();
-
-        // Pre-load existing DB records for this folder (1 query)
-        let db_cache = db.get_folder_photo_cache(&path_for_task).map_err(|e| AppError {
-            message: format!("DB Error: {}", e),
-        })?;
-
-        let mut keep_paths = Vec::with_capacity(total_files);
-        let mut to_upsert: Vec<(String, u64, i64, Option<u32>, Option<u32>)> = Vec::new();
-
-        for (i, (img_path, size, modified)) in image_files.iter().enumerate() {
-            let file_path = img_path.to_string_lossy().to_string();
-            keep_paths.push(file_path.clone());
-
-            // Check if file is already in DB and unchanged
-            if let Some(&(_id, db_modified, _, _width, _height)) = db_cache.get(&file_path) {
-                if db_modified == *modified {
-                    continue;
-                }
-            }
-
-            // New or modified file — read dimensions from image header
-            let (width, height) = image::image_dimensions(img_path)
-                .map(|(w, h)| (Some(w), Some(h)))
-                .unwrap_or((None, None));
-
-            to_upsert.push((file_path, *size, *modified, width, height));
-
-            // Emit progress periodically (every 25 files) to keep UI responsive
-            if to_upsert.len() % 25 == 0 {
-                let _ = app_handle.emit("import-progress", serde_json::json!({
-                    "current": i + 1,
-                    "total": total_files,
+        // Use list_image_files_with_meta — metadata comes free from DirEntry on Windows.
+        // Recursive imports walk the whole tree and report each finished sub-folder.
+        let image_files = if recursive {
+            let depth = max_depth.unwrap_or(fs_service::DEFAULT_MAX_DEPTH);
+            let mut found = 0;
+            fs_service::list_image_files_recursive(&path_for_task, depth, |dir, count| {
+                found += count;
+                let _ = app_handle.emit("scan-progress", serde_json::json!({
+                    "folder": dir.to_string_lossy(),
+                    "count": count,
+                    "found": found,
                 }));
-            }
-        }
-
-        // Batch upsert in a single transaction (one mutex acquire, much faster)
-        let new_photo_paths = if !to_upsert.is_empty() {
-            let imported_count = to_upsert.len();
-            let results = db.batch_upsert_photos(&to_upsert).map_err(|e| AppError {
-                message: format!("DB Error: {}", e),
-            })?;
-
-            let _ = app_handle.emit("import-progress", serde_json::json!({
-                "current": imported_count,
-                "total": imported_count,
-                "done": true,
-            }));
-
-            // Collect newly inserted/changed photos for background thumbnail generation
-            results.iter()
-                .zip(to_upsert.iter())
-                .filter_map(|((id, changed), (path, _, _, _, _))| {
-                    if *changed { Some((*id, path.clone())) } else { None }
-                })
-                .collect::<Vec<_>>()
+            })?
         } else {
-            Vec::new()
+            fs_service::list_image_files_with_meta(&path_for_task)?
         };
-
-        // Only run cleanup if files may have been added/removed
-        if !to_upsert.is_empty() || keep_paths.len() != db_cache.len() {
-            db.cleanup_folder(&path_for_task, &keep_paths).map_err(|e| AppError {
-                message: format!("DB Cleanup Error: {}", e),
+        let (keep_paths, new_photo_paths) =
+            sync_folder_photos(&db, &path_for_task, recursive, &image_files, |progress| {
+                let _ = app_handle.emit("import-progress", progress);
             })?;
+
+        // Refresh EXIF for new/modified files, plus any photos imported before EXIF was stored.
+        // Read in parallel (headers only), then written in a single transaction.
+        let mut exif_pending = new_photo_paths.clone();
+        let changed_ids: std::collections::HashSet<i64> = new_photo_paths.iter().map(|(id, _)| *id).collect();
+        let missing_exif = db.get_photos_without_exif(&path_for_task, recursive)?;
+        exif_pending.extend(missing_exif.into_iter().filter(|(id, _)| !changed_ids.contains(id)));
+        if !exif_pending.is_empty() {
+            let records: Vec<_> = exif_pending
+                .par_iter()
+                .map(|(photo_id, path_str)| {
+                    let record = exif_service::read_exif_record(Path::new(path_str)).unwrap_or_default();
+                    (*photo_id, record)
+                })
+                .collect();
+            db.batch_upsert_exif(&records)?;
         }
 
+        // Show RAW+JPEG pairs as one photo; re-paired on every import so a pair whose JPEG
+        // was deleted turns its RAW back into a standalone photo
+        let pairs = fs_service::pair_companions(&keep_paths);
+        db.replace_pairs(&path_for_task, recursive, &pairs)?;
+
         // Pre-generate thumbnails for newly imported photos in a background thread.
         // This runs detached so list_photos returns immediately, but thumbnails
-        // will be cached in DB by the time the frontend batch-requests them.
+        // will be in the thumbnail cache by the time the frontend requests them.
         // Uses a dedicated 2-thread pool to avoid starving the UI for CPU time.
-        if !new_photo_paths.is_empty() {
+        // Afterwards, perceptual hashes are computed from the cached thumbnails for every
+        // photo in scope that lacks one (new, modified, or imported before hashes existed).
+        let unhashed = db.get_photos_without_perceptual_hash(&path_for_task, recursive)?;
+        if !new_photo_paths.is_empty() || !unhashed.is_empty() {
             let db_for_thumbs = db.clone();
             std::thread::spawn(move || {
                 let pool = rayon::ThreadPoolBuilder::new()
@@ -129,39 +117,152 @@ pub async fn list_photos(
                     .build()
                     .expect("Failed to build thumbnail thread pool");
                 pool.install(|| {
+                    // Photos the UI already requested are found in the cache and skipped
                     new_photo_paths.par_iter().for_each(|(photo_id, path_str)| {
-                        // Skip if the UI already generated this thumbnail via get_thumbnail
-                        if let Ok(Some(_)) = db_for_thumbs.get_thumbnail(*photo_id) {
-                            return;
-                        }
-                        if let Ok(bytes) = thumbnail_service::generate_thumbnail_bytes(
-                            Path::new(path_str),
-                        ) {
-                            let _ = db_for_thumbs.save_thumbnail(*photo_id, &bytes);
-                        }
+                        let _ = thumbnails.get_or_create(*photo_id, Path::new(path_str));
                     });
+
+                    let hashes: Vec<(i64, u64)> = unhashed
+                        .par_iter()
+                        .filter_map(|(photo_id, path_str)| {
+                            let thumbnail = thumbnails.get_or_create(*photo_id, Path::new(path_str)).ok()?;
+                            phash_service::dhash(&thumbnail).ok().map(|hash| (*photo_id, hash))
+                        })
+                        .collect();
+                    let _ = db_for_thumbs.set_perceptual_hashes(&hashes);
                 });
             });
         }
 
+        // Fingerprint photos imported before fingerprints existed, also in the background
+        let unfingerprinted = db.get_photos_without_fingerprint(&path_for_task, recursive)?;
+        if !unfingerprinted.is_empty() {
+            let db_for_fingerprints = db.clone();
+            std::thread::spawn(move || {
+                let fingerprints: Vec<(i64, String)> = unfingerprinted
+                    .iter()
+                    .filter_map(|(photo_id, path_str, size)| {
+                        fs_service::content_fingerprint(Path::new(path_str), *size)
+                            .ok()
+                            .map(|fingerprint| (*photo_id, fingerprint))
+                    })
+                    .collect();
+                let _ = db_for_fingerprints.set_fingerprints(&fingerprints);
+            });
+        }
+
         Ok(())
     })
     .await
     .map_err(|e| AppError {
         message: format!("Import task failed: {}", e),
+        ..Default::default()
     })??;
 
-    // Start watching this folder for changes
-    watcher.watch_folder(&path, app);
+    // Start watching this folder (and its subtree for recursive imports) for changes
+    watcher.watch_folder(&path, recursive, app);
 
     Ok(())
 }
 
+/// (id, path) of photos that are new or whose file changed since the last scan.
+type ChangedPhotos = Vec<(i64, String)>;
+
+/// Bring the library rows of `folder` in line with `image_files`, the (path, size, modified)
+/// of the images found on disk. Rows of vanished files are marked removed first, so that a
+/// renamed or moved file re-links to its old row by fingerprint instead of becoming a new
+/// photo. `on_progress` receives the `import-progress` payloads. Returns the paths found and
+/// the (id, path) of photos that are new or whose file changed.
+pub(crate) fn sync_folder_photos(
+    db: &Database,
+    folder: &str,
+    recursive: bool,
+    image_files: &[(PathBuf, u64, i64)],
+    mut on_progress: impl FnMut(serde_json::Value),
+) -> Result<(Vec<String>, ChangedPhotos), AppError> {
+    let total_files = image_files.len();
+
+    // Pre-load existing DB records for this folder (1 query)
+    let db_cache = db.get_folder_photo_cache(folder, recursive)?;
+
+    let mut keep_paths = Vec::with_capacity(total_files);
+    let mut to_upsert: Vec<(String, u64, i64, Option<u32>, Option<u32>, Option<String>)> = Vec::new();
+
+    for (i, (img_path, size, modified)) in image_files.iter().enumerate() {
+        let file_path = img_path.to_string_lossy().to_string();
+        keep_paths.push(file_path.clone());
+
+        // Check if file is already in DB and unchanged
+        if let Some(&(_id, db_modified, _, _width, _height)) = db_cache.get(&file_path) {
+            if db_modified == *modified {
+                continue;
+            }
+        }
+
+        // New or modified file — read dimensions from image header
+        let (width, height) = image::image_dimensions(img_path)
+            .map(|(w, h)| (Some(w), Some(h)))
+            .unwrap_or((None, None));
+
+        to_upsert.push((file_path, *size, *modified, width, height, None));
+
+        // Emit progress periodically (every 25 files) to keep UI responsive
+        if to_upsert.len() % 25 == 0 {
+            on_progress(serde_json::json!({
+                "current": i + 1,
+                "total": total_files,
+            }));
+        }
+    }
+
+    // Only run cleanup if files may have been added/removed. Runs before the upsert so
+    // the rows of renamed or moved files are already marked removed when their new
+    // paths are matched by fingerprint below.
+    if !to_upsert.is_empty() || keep_paths.len() != db_cache.len() {
+        db.cleanup_folder(folder, &keep_paths, recursive).map_err(|e| AppError {
+            message: format!("DB Cleanup Error: {}", e),
+            ..Default::default()
+        })?;
+    }
+
+    // Content fingerprints let renamed or moved files take over their old record.
+    // Reads the first and last MiB of each file, in parallel.
+    to_upsert.par_iter_mut().for_each(|(path, size, _, _, _, fingerprint)| {
+        *fingerprint = fs_service::content_fingerprint(Path::new(path), *size).ok();
+    });
+
+    // Batch upsert in a single transaction (one mutex acquire, much faster)
+    let new_photo_paths = if !to_upsert.is_empty() {
+        let imported_count = to_upsert.len();
+        let results = db.batch_upsert_photos(&to_upsert)?;
+
+        on_progress(serde_json::json!({
+            "current": imported_count,
+            "total": imported_count,
+            "done": true,
+        }));
+
+        // Collect newly inserted/changed photos for background thumbnail generation
+        results.iter()
+            .zip(to_upsert.iter())
+            .filter_map(|((id, changed), (path, _, _, _, _, _))| {
+                if *changed { Some((*id, path.clone())) } else { None }
+            })
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+
+
+    Ok((keep_paths, new_photo_paths))
+}
+
 fn run_indexing_task(
     db: State<'_, Database>,
     model_manager: State<'_, ModelManager>,
     app: AppHandle,
     folder: String,
+    recursive: bool,
 ) {
     let db_arc = db.inner().clone();
     let mm_arc = model_manager.inner().clone();
@@ -217,7 +318,7 @@ fn run_indexing_task(
         let crop_size = model_type.crop_size();
 
         // 4. Fetch photos that need indexing
-        let photos_to_index = match db_arc.get_photos_to_index(&folder) {
+        let photos_to_index = match db_arc.get_photos_to_index(&folder, recursive) {
             Ok(p) => p,
             Err(e) => {
                 eprintln!("Indexing: Failed to get photos from DB: {}", e);
@@ -303,12 +404,13 @@ fn run_indexing_task(
 #[tauri::command]
 pub async fn trigger_indexing(
     folder: String,
+    recursive: Option<bool>,
     db: State<'_, Database>,
     model_manager: State<'_, ModelManager>,
     app: AppHandle,
 ) -> Result<String, AppError> {
     // run_indexing_task will auto-download and auto-load the model if needed
-    run_indexing_task(db, model_manager, app, folder);
+    run_indexing_task(db, model_manager, app, folder, recursive.unwrap_or(false));
     Ok("Started".to_string())
 }
 
@@ -321,9 +423,10 @@ pub struct IndexingStatus {
 #[tauri::command]
 pub fn get_indexing_status(
     folder: String,
+    recursive: Option<bool>,
     db: State<'_, Database>,
 ) -> Result<IndexingStatus, AppError> {
-    let image_paths = fs_service::list_image_files(&folder)?;
+    let image_paths = fs_service::list_image_files_scoped(&folder, recursive.unwrap_or(false), None)?;
     let total = image_paths.len();
     let mut indexed = 0;
 
@@ -339,234 +442,237 @@ pub fn get_indexing_status(
     Ok(IndexingStatus { total, indexed })
 }
 
-#[tauri::command]
-pub fn query_photos(
-    folder: String,
-    search: Option<String>,
-    sort_by: String,
-    sort_order: String,
-    filter_tags: Option<Vec<String>>,
-    db: State<'_, Database>,
-) -> Result<Vec<PhotoEntry>, AppError> {
-    let rows = db
-        .query_photos(&folder, search.as_deref(), &sort_by, &sort_order, filter_tags.as_deref())
-        .map_err(|e| AppError {
-            message: format!("DB Error: {}", e),
-        })?;
+/// Parse the search box text of a query. Errors carry the offending character position.
+fn parse_search(params: &PhotoQueryParams) -> Result<Option<SearchQuery>, AppError> {
+    params
+        .search
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(search_query::parse)
+        .transpose()
+}
 
-    // Batch-load tags and embedding IDs (2 queries instead of N×2)
-    let tags_map = db.get_tags_for_folder_photos(&folder).map_err(|e| AppError {
-        message: format!("DB Error: {}", e),
-    })?;
-    let embedded_ids = db.get_all_embedded_ids().map_err(|e| AppError {
-        message: format!("DB Error: {}", e),
-    })?;
-
-    let mut photos = Vec::new();
-    for (id, path, size, modified, width, height) in rows {
-        let name = Path::new(&path)
+fn photo_query<'a>(params: &'a PhotoQueryParams, search: Option<&'a SearchQuery>) -> PhotoQuery<'a> {
+    PhotoQuery {
+        folder: &params.folder,
+        recursive: params.recursive,
+        album: params.album_id,
+        search,
+        sort_by: &params.sort_by,
+        sort_order: &params.sort_order,
+        tags: params.tag_filter.as_ref(),
+        exif: params.exif_filter.as_ref(),
+        culling: params.culling_filter.as_ref(),
+        similarity: params.similarity.as_ref(),
+        collapse_stacks: params.collapse_stacks,
+    }
+}
+
+/// Build `PhotoEntry`s for query rows, loading tags and embedding flags for just these rows.
+pub(crate) fn rows_to_entries(db: &Database, rows: Vec<PhotoRow>) -> Result<Vec<PhotoEntry>, AppError> {
+    let ids: Vec<i64> = rows.iter().map(|row| row.id).collect();
+    // Batch-load tags, companions and embedding IDs (3 queries instead of N×3)
+    let tags_map = db.get_tags_for_photos(&ids)?;
+    let mut companions_map = db.get_companions_for_photos(&ids)?;
+    let embedded_ids = db.get_embedded_ids_among(&ids)?;
+
+    let mut photos = Vec::with_capacity(rows.len());
+    for row in rows {
+        let name = Path::new(&row.path)
             .file_name()
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
-        let tags = tags_map.get(&id).cloned().unwrap_or_default();
+        let tags = tags_map.get(&row.id).cloned().unwrap_or_default();
         photos.push(PhotoEntry {
+            id: row.id,
             name,
-            path,
-            size: size as u64,
-            modified: Some(modified as u64),
+            path: row.path,
+            size: row.size as u64,
+            modified: Some(row.modified as u64),
             tags: if tags.is_empty() { None } else { Some(tags) },
-            width,
-            height,
-            has_embedding: embedded_ids.contains(&id),
+            width: row.width,
+            height: row.height,
+            has_embedding: embedded_ids.contains(&row.id),
+            rating: row.rating,
+            color_label: row.color_label,
+            flag: row.flag,
+            stack_id: row.stack_id,
+            stack_size: row.stack_size,
+            companions: companions_map.remove(&row.id).unwrap_or_default(),
         });
     }
     Ok(photos)
 }
 
+/// Evaluate a query; shared by `query_photos` and saved queries.
+pub(crate) fn run_photo_query(db: &Database, query: &PhotoQueryParams) -> Result<Vec<PhotoEntry>, AppError> {
+    let search = parse_search(query)?;
+    let rows = db.query_photos(&photo_query(query, search.as_ref()))?;
+    rows_to_entries(db, rows)
+}
+
+/// Number of photos a query matches, without loading them.
+pub(crate) fn count_photo_query(db: &Database, query: &PhotoQueryParams) -> Result<usize, AppError> {
+    let search = parse_search(query)?;
+    Ok(db.count_photos(&photo_query(query, search.as_ref()))?)
+}
+
+#[tauri::command]
+pub fn query_photos(query: PhotoQueryParams, db: State<'_, Database>) -> Result<Vec<PhotoEntry>, AppError> {
+    run_photo_query(&db, &query)
+}
+
+/// `query_photos` scoped to an album; `folder` and `recursive` are ignored. Sorting by
+/// `position` (the default) follows the album order.
+#[tauri::command]
+pub fn query_album_photos(
+    album_id: i64,
+    mut query: PhotoQueryParams,
+    db: State<'_, Database>,
+) -> Result<Vec<PhotoEntry>, AppError> {
+    query.album_id = Some(album_id);
+    if query.sort_by.is_empty() {
+        query.sort_by = "position".to_string();
+    }
+    query_photos(query, db)
+}
+
+/// One page of `query_photos`. Pass the returned `next_cursor` back as `cursor` for the next page;
+/// `total` is only counted for the first page.
+#[tauri::command]
+pub fn query_photos_page(
+    query: PhotoQueryParams,
+    cursor: Option<PageCursor>,
+    limit: Option<usize>,
+    db: State<'_, Database>,
+) -> Result<PhotoPage, AppError> {
+    let search = parse_search(&query)?;
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    let (rows, next_cursor, total) = db
+        .query_photos_page(&photo_query(&query, search.as_ref()), cursor.as_ref(), limit)?;
+    Ok(PhotoPage {
+        photos: rows_to_entries(&db, rows)?,
+        next_cursor,
+        total,
+    })
+}
+
+/// Run `query_photos` page by page and push each page through `on_page` as soon as it is
+/// ready, so the grid can render the first photos while the rest load. Returns the total.
+#[tauri::command]
+pub async fn stream_photos(
+    query: PhotoQueryParams,
+    page_size: Option<usize>,
+    on_page: Channel<PhotoPage>,
+    db: State<'_, Database>,
+) -> Result<usize, AppError> {
+    let db = db.inner().clone();
+    let search = parse_search(&query)?;
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+
+    tokio::task::spawn_blocking(move || -> Result<usize, AppError> {
+        let page_query = photo_query(&query, search.as_ref());
+        let mut cursor: Option<PageCursor> = None;
+        let mut total = 0;
+        loop {
+            // Each page takes the DB lock separately, so other commands can run in between.
+            // Only the first page counts the matches.
+            let (rows, next_cursor, page_total) = db
+                .query_photos_page(&page_query, cursor.as_ref(), page_size)?;
+            if let Some(count) = page_total {
+                total = count;
+            }
+            let page = PhotoPage {
+                photos: rows_to_entries(&db, rows)?,
+                next_cursor: next_cursor.clone(),
+                total: page_total,
+            };
+            on_page.send(page).map_err(|e| AppError {
+                message: format!("Failed to send page: {}", e),
+                ..Default::default()
+            })?;
+            match next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(total),
+            }
+        }
+    })
+    .await
+    .map_err(|e| AppError {
+        message: format!("Query task failed: {}", e),
+        ..Default::default()
+    })?
+}
+
 #[tauri::command]
 pub fn find_similar_photos(
     folder: String,
     reference_path: String,
     threshold: f32,
+    recursive: Option<bool>,
     db: State<'_, Database>,
 ) -> Result<Vec<PhotoEntry>, AppError> {
     // threshold is 0.0–1.0 (similarity). Cosine distance = 1.0 - similarity.
     let max_distance = 1.0 - threshold;
 
     let photo_id = db
-        .get_photo_id_by_path(&reference_path)
-        .map_err(|e| AppError {
-            message: format!("DB Error: {}", e),
-        })?
+        .get_photo_id_by_path(&reference_path)?
         .ok_or_else(|| {
             println!("Reference photo not found in DB: {}", reference_path);
             AppError {
                 message: "Reference photo not found in database".to_string(),
+                ..Default::default()
             }
         })?;
     
     let rows = db
-        .find_similar_by_embedding(photo_id, &folder, max_distance, 200)
-        .map_err(|e| {
-            println!("DB find_similar error: {}", e);
-            AppError {
-                message: format!("DB Error: {}", e),
-            }
-        })?;
+        .find_similar_by_embedding(photo_id, &folder, recursive.unwrap_or(false), max_distance, 200)
+        .inspect_err(|e| println!("DB find_similar error: {}", e))?;
     
-    let mut photos = Vec::new();
-    for (id, path, size, modified, width, height, _distance) in rows {
-        let name = Path::new(&path)
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let tags = db.get_tags(id).unwrap_or_default();
-        photos.push(PhotoEntry {
-            name,
-            path,
-            size: size as u64,
-            modified: Some(modified as u64),
-            tags: if tags.is_empty() { None } else { Some(tags) },
-            width,
-            height,
-            has_embedding: true,
-        });
-    }
-    Ok(photos)
+    // Rows come back closest first
+    rows_to_entries(&db, rows.into_iter().map(|(row, _distance)| row).collect())
 }
 
 #[tauri::command]
-pub fn get_all_tags(folder: String, db: State<'_, Database>) -> Result<Vec<String>, AppError> {
-    db.get_tags_for_folder(&folder).map_err(|e| AppError {
-        message: format!("DB Error: {}", e),
-    })
-}
-
-#[tauri::command]
-pub fn get_thumbnails_batch(
-    paths: Vec<String>,
+pub fn get_all_tags(
+    folder: String,
+    recursive: Option<bool>,
     db: State<'_, Database>,
-) -> Result<std::collections::HashMap<String, String>, AppError> {
-    // Fetch all cached thumbnails in a single DB query
-    let cached = db.get_cached_thumbnails_by_paths(&paths).map_err(|e| AppError {
-        message: format!("DB Error: {}", e),
-    })?;
-
-    let mut results = std::collections::HashMap::with_capacity(cached.len());
-    for (path, blob) in cached {
-        let b64 = base64::Engine::encode(
-            &base64::engine::general_purpose::STANDARD,
-            &blob,
-        );
-        results.insert(path, format!("data:image/jpeg;base64,{}", b64));
-    }
-
-    Ok(results)
+) -> Result<Vec<String>, AppError> {
+    Ok(db.get_tags_for_folder(&folder, recursive.unwrap_or(false))?)
 }
 
-#[tauri::command]
-pub fn get_thumbnail(path: String, db: State<'_, Database>) -> Result<String, AppError> {
-    let img_path = Path::new(&path);
-    if !img_path.exists() {
-        return Err("File not found".into());
-    }
-
-    // Try to serve from DB cache
-    if let Ok(Some(photo_id)) = db.get_photo_id_by_path(&path) {
-        // Photo is in DB — check for cached thumbnail
-        if let Ok(Some(blob)) = db.get_thumbnail(photo_id) {
-            let b64 = base64::Engine::encode(
-                &base64::engine::general_purpose::STANDARD,
-                &blob,
-            );
-            return Ok(format!("data:image/jpeg;base64,{}", b64));
-        }
-
-        // No cached thumbnail — generate, save, and return
-        let bytes = thumbnail_service::generate_thumbnail_bytes(img_path)?;
-        let _ = db.save_thumbnail(photo_id, &bytes);
-        let b64 = base64::Engine::encode(
-            &base64::engine::general_purpose::STANDARD,
-            &bytes,
-        );
-        return Ok(format!("data:image/jpeg;base64,{}", b64));
-    }
-
-    // Photo not in DB yet (e.g. before import completes) — generate without caching
-    thumbnail_service::generate_thumbnail(img_path)
-}
-
-#[tauri::command]
-pub fn get_image_bytes(path: String) -> Result<Vec<u8>, AppError> {
-    let img_path = Path::new(&path);
-    if !img_path.exists() {
-        return Err("File not found".into());
-    }
-    std::fs::read(img_path).map_err(|e| AppError {
-        message: format!("Failed to read file: {}", e),
-    })
-}
-
-#[tauri::command]
-pub fn get_full_image(path: String) -> Result<String, AppError> {
-    let img_path = Path::new(&path);
-    if !img_path.exists() {
-        return Err("File not found".into());
-    }
-
-    let orientation = exif_service::get_orientation(img_path);
-
-    let mut img = image::ImageReader::open(img_path)
-        .map_err(|e| AppError {
-            message: format!("Failed to open image: {}", e),
-        })?
-        .decode()
-        .map_err(|e| AppError {
-            message: format!("Failed to decode image: {}", e),
-        })?;
-
-    // Optimization: Resize BEFORE rotating.
-    // Rotating a full 24MP image (swapping w/h) is very expensive/slow.
-    // Resizing it to screen size first reduces the pixel count by ~10x-20x, making rotation instant.
-    // Since we resize to a square bounding box (1920x1920), the scale factor is the same
-    // regardless of whether we rotate before or after.
-    if img.width() > 1920 || img.height() > 1920 {
-        img = img.thumbnail(1920, 1920);
-    }
-
-    // Apply rotation after resizing
-    if orientation != 1 {
-        img = exif_service::apply_orientation(img, orientation);
-    }
-
-    let mut buffer = std::io::Cursor::new(Vec::new());
-    img.write_to(&mut buffer, image::ImageFormat::Jpeg)
-        .map_err(|e| AppError {
-            message: format!("Failed to encode image: {}", e),
-        })?;
-
-    let b64 = base64::Engine::encode(
-        &base64::engine::general_purpose::STANDARD,
-        buffer.into_inner(),
-    );
-    Ok(format!("data:image/jpeg;base64,{}", b64))
+/// `paths` plus the files that belong with them: the companions of RAW+JPEG pairs and
+/// XMP sidecars, each listed once.
+fn with_companions(db: &Database, paths: &[String]) -> Result<Vec<String>, AppError> {
+    let mut all = paths.to_vec();
+    all.extend(db.get_companion_paths(paths)?);
+    let sidecars: Vec<String> = all
+        .iter()
+        .flat_map(|path| fs_service::sidecar_files(Path::new(path)))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    all.extend(sidecars);
+
+    let mut seen = std::collections::HashSet::new();
+    all.retain(|path| seen.insert(path.clone()));
+    Ok(all)
 }
 
 #[tauri::command]
 pub fn delete_files(paths: Vec<String>, db: State<'_, Database>) -> Result<(), AppError> {
+    let paths = with_companions(&db, &paths)?;
     for path_str in &paths {
         let p = Path::new(path_str);
         if p.exists() {
             std::fs::remove_file(p).map_err(|e| AppError {
                 message: format!("Failed to delete {}: {}", path_str, e),
+                ..Default::default()
             })?;
         }
     }
-    db.delete_photos_by_paths(&paths).map_err(|e| AppError {
-        message: format!("DB Error: {}", e),
-    })?;
+    db.delete_photos_by_paths(&paths)?;
     Ok(())
 }
 
@@ -580,14 +686,17 @@ pub fn move_files(
     if !dest.is_dir() {
         return Err(AppError {
             message: format!("Destination is not a directory: {}", destination),
+            ..Default::default()
         });
     }
+    let paths = with_companions(&db, &paths)?;
     for path_str in &paths {
         let src = PathBuf::from(path_str);
         let file_name = src
             .file_name()
             .ok_or_else(|| AppError {
                 message: format!("Invalid file path: {}", path_str),
+                ..Default::default()
             })?;
         let new_path = dest.join(file_name);
         std::fs::rename(&src, &new_path).map_err(|e| {
@@ -598,17 +707,20 @@ pub fn move_files(
                         "Failed to move {} (rename: {}, copy: {})",
                         path_str, e, copy_err
                     ),
+                    ..Default::default()
                 };
             }
             if let Err(del_err) = std::fs::remove_file(&src) {
                 return AppError {
                     message: format!("Copied but failed to remove source {}: {}", path_str, del_err),
+                    ..Default::default()
                 };
             }
             // If copy+delete succeeded, this error is actually OK — swallow it
             // But we need to return *something* from the closure. We'll use a sentinel.
             AppError {
                 message: String::new(),
+                ..Default::default()
             }
         }).or_else(|e| {
             if e.message.is_empty() {
@@ -624,24 +736,110 @@ pub fn move_files(
 }
 
 #[tauri::command]
-pub fn copy_files(paths: Vec<String>, destination: String) -> Result<(), AppError> {
+pub fn copy_files(paths: Vec<String>, destination: String, db: State<'_, Database>) -> Result<(), AppError> {
     let dest = PathBuf::from(&destination);
     if !dest.is_dir() {
         return Err(AppError {
             message: format!("Destination is not a directory: {}", destination),
+            ..Default::default()
         });
     }
+    let paths = with_companions(&db, &paths)?;
     for path_str in &paths {
         let src = PathBuf::from(path_str);
         let file_name = src
             .file_name()
             .ok_or_else(|| AppError {
                 message: format!("Invalid file path: {}", path_str),
+                ..Default::default()
             })?;
         let new_path = dest.join(file_name);
         std::fs::copy(&src, &new_path).map_err(|e| AppError {
             message: format!("Failed to copy {}: {}", path_str, e),
+            ..Default::default()
         })?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory under the system temp dir, deleted again on drop.
+    struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("photolense-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            ScratchDir(dir)
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Scan `folder` the way `list_photos` does; returns the new or changed photos.
+    fn scan(db: &Database, folder: &Path) -> Vec<(i64, String)> {
+        let folder = folder.to_string_lossy();
+        let files = fs_service::list_image_files_with_meta(&folder).unwrap();
+        sync_folder_photos(db, &folder, false, &files, |_| {}).unwrap().1
+    }
+
+    #[test]
+    fn renamed_file_keeps_its_photo_and_tags() {
+        let scratch = ScratchDir::new("rename");
+        let photos = scratch.0.join("photos");
+        std::fs::create_dir(&photos).unwrap();
+        let old_path = photos.join("IMG_0001.png");
+        image::RgbImage::from_pixel(8, 8, image::Rgb([200, 40, 40])).save(&old_path).unwrap();
+        let db = Database::new(scratch.0.join("library.db")).unwrap();
+
+        let imported = scan(&db, &photos);
+        assert_eq!(imported.len(), 1);
+        let id = imported[0].0;
+        db.add_manual_tag(&[old_path.to_string_lossy().to_string()], "holiday").unwrap();
+
+        let new_path = photos.join("beach.png");
+        std::fs::rename(&old_path, &new_path).unwrap();
+        assert!(scan(&db, &photos).is_empty(), "a renamed file is not a new photo");
+
+        let new_path = new_path.to_string_lossy().to_string();
+        assert_eq!(db.get_photo_id_by_path(&new_path).unwrap(), Some(id));
+        let tags = db.get_photo_tags(id).unwrap();
+        assert!(tags.iter().any(|t| t.tag == "holiday"));
+        let live = db.get_folder_photo_cache(&photos.to_string_lossy(), false).unwrap();
+        assert_eq!(live.len(), 1);
+        assert!(live.contains_key(&new_path));
+    }
+
+    #[test]
+    fn file_moved_to_a_folder_scanned_first_keeps_its_photo() {
+        let scratch = ScratchDir::new("move");
+        let (photos, sorted) = (scratch.0.join("photos"), scratch.0.join("sorted"));
+        std::fs::create_dir(&photos).unwrap();
+        std::fs::create_dir(&sorted).unwrap();
+        let old_path = photos.join("IMG_0002.png");
+        image::RgbImage::from_pixel(8, 8, image::Rgb([40, 200, 40])).save(&old_path).unwrap();
+        let db = Database::new(scratch.0.join("library.db")).unwrap();
+
+        let id = scan(&db, &photos)[0].0;
+        db.add_manual_tag(&[old_path.to_string_lossy().to_string()], "garden").unwrap();
+
+        // The destination is scanned while the photo's row still points at the old path
+        let new_path = sorted.join("IMG_0002.png");
+        std::fs::rename(&old_path, &new_path).unwrap();
+        assert!(scan(&db, &sorted).is_empty(), "a moved file is not a new photo");
+        assert!(scan(&db, &photos).is_empty());
+
+        let new_path = new_path.to_string_lossy().to_string();
+        assert_eq!(db.get_photo_id_by_path(&new_path).unwrap(), Some(id));
+        assert!(db.get_photo_tags(id).unwrap().iter().any(|t| t.tag == "garden"));
+        assert!(db.get_folder_photo_cache(&photos.to_string_lossy(), false).unwrap().is_empty());
+    }
+}
@@ -1,14 +1,72 @@
 use crate::error::AppError;
+use crate::models::format_types::{DecodeLimits, FormatSupport};
+use crate::models::thumbnail_types::ThumbnailCacheInfo;
+use crate::services::thumbnail_cache::ThumbnailCache;
+use crate::services::{decode_service, fs_service};
 use base64::Engine;
 use image::codecs::png::{CompressionType, PngEncoder};
 use image::{ColorType, ImageEncoder};
 use std::fs::File;
 use std::io::{BufReader, Cursor, Read};
 use std::path::Path;
+use tauri::State;
 
 const WIDTH: u32 = 256;
 const HEIGHT: u32 = 100;
 
+/// Extensions the scanner imports, each with whether this build can decode it, followed by
+/// those it skips because this build cannot.
+#[tauri::command]
+pub fn get_image_formats() -> Vec<FormatSupport> {
+    let imported = fs_service::image_extensions().map(|extension| FormatSupport {
+        extension: extension.to_string(),
+        decodable: decode_service::can_decode(extension),
+        imported: true,
+    });
+    let skipped = fs_service::skipped_extensions().map(|extension| FormatSupport {
+        extension: extension.to_string(),
+        decodable: false,
+        imported: false,
+    });
+    imported.chain(skipped).collect()
+}
+
+/// Pixel and allocation caps applied to every image decode.
+#[tauri::command]
+pub fn get_decode_limits() -> DecodeLimits {
+    decode_service::limits()
+}
+
+#[tauri::command]
+pub fn set_decode_limits(limits: DecodeLimits) -> Result<(), AppError> {
+    if limits.max_pixels == 0 || limits.max_alloc == 0 {
+        return Err("Decode limits must be greater than zero".into());
+    }
+    decode_service::set_limits(limits);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_thumbnail_cache_info(thumbnails: State<'_, ThumbnailCache>) -> Result<ThumbnailCacheInfo, AppError> {
+    thumbnails.info()
+}
+
+/// Cap the thumbnail cache at `max_bytes`, evicting the least recently used thumbnails now
+/// if it is over.
+#[tauri::command]
+pub fn set_thumbnail_cache_limit(max_bytes: u64, thumbnails: State<'_, ThumbnailCache>) -> Result<(), AppError> {
+    if max_bytes == 0 {
+        return Err("Thumbnail cache limit must be greater than zero".into());
+    }
+    thumbnails.set_max_bytes(max_bytes)
+}
+
+/// Delete all cached thumbnails; they are generated again as photos are shown.
+#[tauri::command]
+pub fn clear_thumbnail_cache(thumbnails: State<'_, ThumbnailCache>) -> Result<(), AppError> {
+    thumbnails.clear()
+}
+
 #[tauri::command]
 pub fn get_histogram(path: String) -> Result<String, AppError> {
     let file_path = Path::new(&path);
@@ -17,17 +75,16 @@ pub fn get_histogram(path: String) -> Result<String, AppError> {
     // This avoids decoding the full 24MP image.
     let img = if let Ok(Some(thumb_vec)) = extract_exif_thumbnail(file_path) {
         // We found a thumbnail! Load it.
-        image::load_from_memory(&thumb_vec).ok()
+        decode_service::decode_bytes(&thumb_vec).ok()
     } else {
         None
     };
 
     // 2. SLOW FALLBACK: Load full image if thumbnail failed (~200ms+)
+    // Orientation doesn't change the histogram, so it's not applied
     let img = match img {
         Some(i) => i,
-        None => image::open(file_path).map_err(|e| AppError {
-            message: format!("Failed to open image: {}", e),
-        })?,
+        None => decode_service::decode_file(file_path)?.image,
     };
 
     // 3. CRITICAL OPTIMIZATION: Resize immediately
@@ -106,7 +163,7 @@ pub fn get_histogram(path: String) -> Result<String, AppError> {
         image::codecs::png::FilterType::NoFilter,
     )
         .write_image(&raw, WIDTH, HEIGHT, ColorType::Rgba8.into())
-        .map_err(|e| AppError { message: e.to_string() })?;
+        .map_err(|e| AppError { message: e.to_string(), ..Default::default() })?;
 
     let b64 = base64::engine::general_purpose::STANDARD.encode(&png_bytes);
     Ok(format!("data:image/png;base64,{}", b64))
@@ -114,7 +171,7 @@ pub fn get_histogram(path: String) -> Result<String, AppError> {
 
 /// Helper: Robustly extract JPEG thumbnail using kamadak-exif
 fn extract_exif_thumbnail(path: &Path) -> Result<Option<Vec<u8>>, AppError> {
-    let file = File::open(path).map_err(|_| AppError { message: "File error".into() })?;
+    let file = File::open(path).map_err(|_| AppError { message: "File error".into(), ..Default::default() })?;
 
     // Read first 128KB (Standard Exif limit is 64KB, but we add safety margin)
     let mut reader = BufReader::with_capacity(128 * 1024, file);
@@ -1,5 +1,11 @@
+pub mod albums;
 pub mod classifier;
+pub mod culling;
+pub mod duplicates;
 pub mod exif;
 pub mod filesystem;
 pub mod color;
 pub mod image;
+pub mod saved_queries;
+pub mod stacks;
+pub mod tags;
@@ -0,0 +1,124 @@
+use crate::commands::filesystem::{count_photo_query, run_photo_query};
+use crate::error::AppError;
+use crate::models::fs_types::PhotoEntry;
+use crate::models::query_types::PhotoQueryParams;
+use crate::models::saved_query_types::SavedQuery;
+use crate::services::db::Database;
+use tauri::State;
+
+/// Trimmed saved query name, or an error for blank input.
+fn query_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Saved query name must not be empty".into());
+    }
+    Ok(name.to_string())
+}
+
+fn lookup_err(e: rusqlite::Error) -> AppError {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => "Saved query not found".into(),
+        e => e.into(),
+    }
+}
+
+/// Map a UNIQUE violation on `saved_queries.name` to a readable message.
+fn name_err(name: &str, e: rusqlite::Error) -> AppError {
+    match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("A saved query named \"{}\" already exists", name).into()
+        }
+        e => lookup_err(e),
+    }
+}
+
+/// Re-count a saved query's matches and store the count.
+fn refresh_count(db: &Database, saved: &SavedQuery) -> Result<(), AppError> {
+    let count = count_photo_query(db, &saved.query)?;
+    Ok(db.set_saved_query_count(saved.id, count)?)
+}
+
+#[tauri::command]
+pub fn list_saved_queries(db: State<'_, Database>) -> Result<Vec<SavedQuery>, AppError> {
+    Ok(db.list_saved_queries()?)
+}
+
+/// Save a query under `name`. The search text is checked first, so a query with a syntax
+/// error is rejected with the error position instead of being stored.
+#[tauri::command]
+pub fn save_query(
+    name: String,
+    query: PhotoQueryParams,
+    pinned: Option<bool>,
+    db: State<'_, Database>,
+) -> Result<SavedQuery, AppError> {
+    let name = query_name(&name)?;
+    let count = count_photo_query(&db, &query)?;
+    let id = db
+        .create_saved_query(&name, &query, pinned.unwrap_or(false))
+        .map_err(|e| name_err(&name, e))?;
+    db.set_saved_query_count(id, count)?;
+    Ok(db.get_saved_query(id)?)
+}
+
+/// Rename a saved query and/or replace its query; omitted values are kept.
+#[tauri::command]
+pub fn update_saved_query(
+    saved_query_id: i64,
+    name: Option<String>,
+    query: Option<PhotoQueryParams>,
+    db: State<'_, Database>,
+) -> Result<SavedQuery, AppError> {
+    if let Some(name) = name {
+        let name = query_name(&name)?;
+        db.rename_saved_query(saved_query_id, &name).map_err(|e| name_err(&name, e))?;
+    }
+    if let Some(query) = query {
+        count_photo_query(&db, &query)?;
+        db.update_saved_query(saved_query_id, &query).map_err(lookup_err)?;
+    }
+    let saved = db.get_saved_query(saved_query_id)?;
+    if saved.cached_count.is_none() {
+        refresh_count(&db, &saved)?;
+        return Ok(db.get_saved_query(saved_query_id)?);
+    }
+    Ok(saved)
+}
+
+#[tauri::command]
+pub fn delete_saved_query(saved_query_id: i64, db: State<'_, Database>) -> Result<(), AppError> {
+    Ok(db.delete_saved_query(saved_query_id)?)
+}
+
+/// Show or hide a saved query as a smart album. Pinning refreshes its count.
+#[tauri::command]
+pub fn pin_saved_query(saved_query_id: i64, pinned: bool, db: State<'_, Database>) -> Result<SavedQuery, AppError> {
+    db.set_saved_query_pinned(saved_query_id, pinned).map_err(lookup_err)?;
+    let saved = db.get_saved_query(saved_query_id)?;
+    if pinned {
+        refresh_count(&db, &saved)?;
+        return Ok(db.get_saved_query(saved_query_id)?);
+    }
+    Ok(saved)
+}
+
+/// Evaluate a saved query against the current library, exactly like `query_photos`,
+/// and remember how many photos matched.
+#[tauri::command]
+pub fn run_saved_query(saved_query_id: i64, db: State<'_, Database>) -> Result<Vec<PhotoEntry>, AppError> {
+    let saved = db.get_saved_query(saved_query_id).map_err(lookup_err)?;
+    let photos = run_photo_query(&db, &saved.query)?;
+    db.set_saved_query_count(saved.id, photos.len())?;
+    Ok(photos)
+}
+
+/// Re-count every pinned saved query, e.g. after an import, and return all saved queries.
+#[tauri::command]
+pub fn refresh_saved_query_counts(db: State<'_, Database>) -> Result<Vec<SavedQuery>, AppError> {
+    for saved in db.list_saved_queries()? {
+        if saved.pinned {
+            refresh_count(&db, &saved)?;
+        }
+    }
+    Ok(db.list_saved_queries()?)
+}
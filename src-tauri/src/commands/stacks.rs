@@ -0,0 +1,98 @@
+use crate::commands::filesystem::rows_to_entries;
+use crate::error::AppError;
+use crate::models::fs_types::PhotoEntry;
+use crate::services::db::{BurstCandidate, Database};
+use crate::services::phash_service;
+use tauri::State;
+
+/// Largest gap between consecutive frames of one burst, in milliseconds.
+const DEFAULT_BURST_GAP_MS: i64 = 2000;
+
+/// Largest perceptual hash distance between consecutive frames. Looser than the
+/// near-duplicate default, since subjects move between frames.
+const DEFAULT_BURST_DISTANCE: u32 = 20;
+
+/// Whether `next` continues the burst that `prev` is the latest frame of.
+fn continues_burst(prev: &BurstCandidate, next: &BurstCandidate, max_gap_ms: i64, max_distance: u32) -> bool {
+    if prev.folder != next.folder || prev.camera != next.camera || next.capture_ms - prev.capture_ms > max_gap_ms {
+        return false;
+    }
+    // A cut to a different scene ends the burst; frames without a hash yet go by time alone
+    match (prev.perceptual_hash, next.perceptual_hash) {
+        (Some(a), Some(b)) => phash_service::hamming_distance(a, b) <= max_distance,
+        _ => true,
+    }
+}
+
+/// Group the photos in `folder` into stacks of bursts and series: frames from the same camera
+/// and folder taken at most `max_gap_ms` apart (default 2 s) that also look alike. Replaces
+/// the folder's previous stacks; the best-rated frame (earliest on ties) becomes the cover
+/// unless a cover was chosen before. Returns the number of stacks.
+#[tauri::command]
+pub async fn detect_stacks(
+    db: State<'_, Database>,
+    folder: String,
+    recursive: Option<bool>,
+    max_gap_ms: Option<i64>,
+    max_distance: Option<u32>,
+) -> Result<usize, AppError> {
+    let db = db.inner().clone();
+    let recursive = recursive.unwrap_or(false);
+    let max_gap_ms = max_gap_ms.unwrap_or(DEFAULT_BURST_GAP_MS);
+    let max_distance = max_distance.unwrap_or(DEFAULT_BURST_DISTANCE);
+
+    tokio::task::spawn_blocking(move || {
+        let candidates = db.get_burst_candidates(&folder, recursive)?;
+
+        let mut stacks: Vec<Vec<i64>> = Vec::new();
+        let mut run: Vec<&BurstCandidate> = Vec::new();
+        for candidate in &candidates {
+            if let Some(prev) = run.last() {
+                if !continues_burst(prev, candidate, max_gap_ms, max_distance) {
+                    stacks.push(cover_first(&run));
+                    run.clear();
+                }
+            }
+            run.push(candidate);
+        }
+        stacks.push(cover_first(&run));
+
+        Ok(db.replace_stacks(&folder, recursive, &stacks)?)
+    })
+    .await
+    .map_err(|e| AppError {
+        message: format!("Task join failed: {}", e),
+        ..Default::default()
+    })?
+}
+
+/// Ids of a burst with the suggested cover first: the best-rated frame, earliest on ties.
+fn cover_first(run: &[&BurstCandidate]) -> Vec<i64> {
+    let mut ids: Vec<i64> = run.iter().map(|c| c.id).collect();
+    if let Some(best) = run.iter().enumerate().max_by_key(|(i, c)| (c.rating, std::cmp::Reverse(*i))) {
+        ids.swap(0, best.0);
+    }
+    ids
+}
+
+/// All photos of a stack, cover first.
+#[tauri::command]
+pub fn get_stack_photos(stack_id: i64, db: State<'_, Database>) -> Result<Vec<PhotoEntry>, AppError> {
+    let rows = db.get_stack_photos(stack_id)?;
+    rows_to_entries(&db, rows)
+}
+
+/// Show the photo at `path` for its stack when stacks are collapsed.
+#[tauri::command]
+pub fn set_stack_cover(path: String, db: State<'_, Database>) -> Result<(), AppError> {
+    if !db.set_stack_cover(&path)? {
+        return Err("Photo is not in a stack".into());
+    }
+    Ok(())
+}
+
+/// Take photos out of their stacks. Returns the number of photos unstacked.
+#[tauri::command]
+pub fn unstack_photos(paths: Vec<String>, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.unstack_photos(&paths)?)
+}
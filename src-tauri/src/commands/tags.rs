@@ -0,0 +1,67 @@
+use crate::error::AppError;
+use crate::models::tag_types::{PhotoTag, TagSummary};
+use crate::services::db::Database;
+use tauri::State;
+
+/// Trimmed tag name, or an error for blank input.
+fn tag_name(tag: &str) -> Result<String, AppError> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("Tag name must not be empty".into());
+    }
+    Ok(tag.to_string())
+}
+
+/// Tag the given photos by hand. Returns how many photos gained the tag.
+#[tauri::command]
+pub fn add_manual_tag(paths: Vec<String>, tag: String, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.add_manual_tag(&paths, &tag_name(&tag)?)?)
+}
+
+/// Remove a manual tag from the given photos. Tags predicted by a model are not touched.
+#[tauri::command]
+pub fn remove_manual_tag(paths: Vec<String>, tag: String, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.remove_manual_tag(&paths, &tag_name(&tag)?)?)
+}
+
+/// All tags of one photo with their source and confidence.
+#[tauri::command]
+pub fn get_photo_tags(path: String, db: State<'_, Database>) -> Result<Vec<PhotoTag>, AppError> {
+    match db.get_photo_id_by_path(&path)? {
+        Some(id) => Ok(db.get_photo_tags(id)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Tags with photo counts and average confidence, for `folder` or the whole library when `None`.
+#[tauri::command]
+pub fn list_tags(
+    folder: Option<String>,
+    recursive: Option<bool>,
+    db: State<'_, Database>,
+) -> Result<Vec<TagSummary>, AppError> {
+    Ok(db.get_tag_summaries(folder.as_deref(), recursive.unwrap_or(false))?)
+}
+
+/// Rename a tag everywhere. Renaming onto an existing tag merges the two.
+#[tauri::command]
+pub fn rename_tag(old_name: String, new_name: String, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.rename_tag(&old_name, &tag_name(&new_name)?)?)
+}
+
+/// Merge several tags into `into` everywhere.
+#[tauri::command]
+pub fn merge_tags(tags: Vec<String>, into: String, db: State<'_, Database>) -> Result<usize, AppError> {
+    Ok(db.merge_tags(&tags, &tag_name(&into)?)?)
+}
+
+/// Delete one tag from `folder`, or from the whole library when `None`.
+#[tauri::command]
+pub fn delete_tag(
+    tag: String,
+    folder: Option<String>,
+    recursive: Option<bool>,
+    db: State<'_, Database>,
+) -> Result<usize, AppError> {
+    Ok(db.delete_tag(&tag, folder.as_deref(), recursive.unwrap_or(false))?)
+}
@@ -1,9 +1,17 @@
 use serde::Serialize;
 use std::fmt;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct AppError {
     pub message: String,
+    /// Character offset into the user input the error refers to (e.g. a search query
+    /// parse error). Omitted from the serialized error when not applicable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    /// Why an image failed to decode, so the frontend can tell unsupported, oversized and
+    /// corrupt images apart. Omitted for other errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<DecodeErrorKind>,
 }
 
 impl fmt::Display for AppError {
@@ -12,10 +20,83 @@ impl fmt::Display for AppError {
     }
 }
 
+/// Why an image could not be decoded.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Not an image format this build can decode
+    Unsupported(String),
+    /// Damaged or truncated data, or not the format its header claims
+    Corrupt(String),
+    /// Over the configured pixel or allocation limit
+    TooLarge(String),
+    /// The file could not be read
+    Io(std::io::Error),
+}
+
+/// The variant of a `DecodeError`, as sent to the frontend.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeErrorKind {
+    Unsupported,
+    Corrupt,
+    TooLarge,
+    Io,
+}
+
+impl DecodeError {
+    pub fn kind(&self) -> DecodeErrorKind {
+        match self {
+            DecodeError::Unsupported(_) => DecodeErrorKind::Unsupported,
+            DecodeError::Corrupt(_) => DecodeErrorKind::Corrupt,
+            DecodeError::TooLarge(_) => DecodeErrorKind::TooLarge,
+            DecodeError::Io(_) => DecodeErrorKind::Io,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Unsupported(msg) => write!(f, "Unsupported image: {}", msg),
+            DecodeError::Corrupt(msg) => write!(f, "Corrupt image: {}", msg),
+            DecodeError::TooLarge(msg) => write!(f, "Image too large: {}", msg),
+            DecodeError::Io(err) => write!(f, "Failed to read image: {}", err),
+        }
+    }
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(err: std::io::Error) -> Self {
+        DecodeError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for DecodeError {
+    fn from(err: image::ImageError) -> Self {
+        match err {
+            image::ImageError::Limits(e) => DecodeError::TooLarge(e.to_string()),
+            image::ImageError::Unsupported(e) => DecodeError::Unsupported(e.to_string()),
+            image::ImageError::IoError(e) => DecodeError::Io(e),
+            e => DecodeError::Corrupt(e.to_string()),
+        }
+    }
+}
+
+impl From<DecodeError> for AppError {
+    fn from(err: DecodeError) -> Self {
+        AppError {
+            message: err.to_string(),
+            position: None,
+            kind: Some(err.kind()),
+        }
+    }
+}
+
 impl From<std::io::Error> for AppError {
     fn from(err: std::io::Error) -> Self {
         AppError {
             message: err.to_string(),
+            ..Default::default()
         }
     }
 }
@@ -24,6 +105,16 @@ impl From<image::ImageError> for AppError {
     fn from(err: image::ImageError) -> Self {
         AppError {
             message: err.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        AppError {
+            message: format!("DB Error: {}", err),
+            ..Default::default()
         }
     }
 }
@@ -32,6 +123,7 @@ impl From<reqwest::Error> for AppError {
     fn from(err: reqwest::Error) -> Self {
         AppError {
             message: err.to_string(),
+            ..Default::default()
         }
     }
 }
@@ -40,13 +132,14 @@ impl From<ort::Error> for AppError {
     fn from(err: ort::Error) -> Self {
         AppError {
             message: err.to_string(),
+            ..Default::default()
         }
     }
 }
 
 impl From<String> for AppError {
     fn from(msg: String) -> Self {
-        AppError { message: msg }
+        AppError { message: msg, ..Default::default() }
     }
 }
 
@@ -54,6 +147,7 @@ impl From<&str> for AppError {
     fn from(msg: &str) -> Self {
         AppError {
             message: msg.to_string(),
+            ..Default::default()
         }
     }
 }
@@ -1,12 +1,15 @@
 mod commands;
 mod error;
 mod models;
+mod protocol;
 mod services;
 
 use services::classifier::model_manager::ModelManager;
 use services::db::Database;
+use services::thumbnail_cache::ThumbnailCache;
 use services::watcher::FolderWatcher;
 use tauri::{Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -14,6 +17,7 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_window_state::Builder::default().build())
+        .register_asynchronous_uri_scheme_protocol(protocol::SCHEME, protocol::handle)
         .setup(|app| {
             let app_data_dir = app
                 .path()
@@ -30,8 +34,31 @@ pub fn run() {
             app.manage(FolderWatcher::new());
 
             let db_path = app_data_dir.join("library.db");
-            let db = Database::new(db_path).expect("Failed to initialize database");
-            app.manage(db);
+            let db = match Database::new(db_path) {
+                Ok(db) => db,
+                Err(e) => {
+                    // E.g. a library written by a newer version: tell the user why instead of
+                    // crashing, and quit once they dismiss the message. Hide the main window
+                    // meanwhile so nothing reaches commands or images without a database.
+                    for window in app.webview_windows().values() {
+                        let _ = window.hide();
+                    }
+                    let app_handle = app.handle().clone();
+                    app.dialog()
+                        .message(e.message)
+                        .title("Failed to open the photo library")
+                        .kind(MessageDialogKind::Error)
+                        .show(move |_| app_handle.exit(1));
+                    return Ok(());
+                }
+            };
+            app.manage(db.clone());
+
+            // Thumbnails stored in the database by older versions move to the cache in the background
+            let thumbnails = ThumbnailCache::new(app_data_dir.join("thumbnails"), db)
+                .expect("Failed to initialize thumbnail cache");
+            app.manage(thumbnails.clone());
+            std::thread::spawn(move || thumbnails.migrate_legacy());
 
             // Auto-download and load MobileNetV3 model on first start
             let app_handle = app.handle().clone();
@@ -70,12 +97,11 @@ pub fn run() {
             commands::filesystem::list_directory,
             commands::filesystem::list_photos,
             commands::filesystem::query_photos,
+            commands::filesystem::query_photos_page,
+            commands::filesystem::stream_photos,
+            commands::filesystem::query_album_photos,
             commands::filesystem::find_similar_photos,
             commands::filesystem::get_all_tags,
-            commands::filesystem::get_thumbnails_batch,
-            commands::filesystem::get_thumbnail,
-            commands::filesystem::get_full_image,
-            commands::filesystem::get_image_bytes,
             commands::filesystem::delete_files,
             commands::filesystem::move_files,
             commands::filesystem::copy_files,
@@ -89,8 +115,44 @@ pub fn run() {
             commands::classifier::set_model_type,
             commands::classifier::cancel_classification,
             commands::classifier::delete_all_tags,
+            commands::tags::add_manual_tag,
+            commands::tags::remove_manual_tag,
+            commands::tags::get_photo_tags,
+            commands::tags::list_tags,
+            commands::tags::rename_tag,
+            commands::tags::merge_tags,
+            commands::tags::delete_tag,
+            commands::culling::set_rating,
+            commands::culling::set_color_label,
+            commands::culling::set_flag,
+            commands::albums::list_albums,
+            commands::albums::create_album,
+            commands::albums::rename_album,
+            commands::albums::delete_album,
+            commands::albums::add_to_album,
+            commands::albums::remove_from_album,
+            commands::albums::reorder_album,
+            commands::saved_queries::list_saved_queries,
+            commands::saved_queries::save_query,
+            commands::saved_queries::update_saved_query,
+            commands::saved_queries::delete_saved_query,
+            commands::saved_queries::pin_saved_query,
+            commands::saved_queries::run_saved_query,
+            commands::saved_queries::refresh_saved_query_counts,
+            commands::stacks::detect_stacks,
+            commands::stacks::get_stack_photos,
+            commands::stacks::set_stack_cover,
+            commands::stacks::unstack_photos,
+            commands::duplicates::find_duplicates,
+            commands::duplicates::find_near_duplicates,
             commands::color::group_by_color,
             commands::image::get_histogram,
+            commands::image::get_image_formats,
+            commands::image::get_decode_limits,
+            commands::image::set_decode_limits,
+            commands::image::get_thumbnail_cache_info,
+            commands::image::set_thumbnail_cache_limit,
+            commands::image::clear_thumbnail_cache,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
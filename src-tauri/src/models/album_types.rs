@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Album {
+    pub id: i64,
+    pub name: String,
+    /// Unix timestamp (seconds)
+    pub created: i64,
+    pub photo_count: usize,
+    /// First photo in album order, for the album thumbnail
+    pub cover_path: Option<String>,
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// Color labels as used by Lightroom and Bridge.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorLabel {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl ColorLabel {
+    /// Value stored in `photos.color_label`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorLabel::Red => "red",
+            ColorLabel::Yellow => "yellow",
+            ColorLabel::Green => "green",
+            ColorLabel::Blue => "blue",
+            ColorLabel::Purple => "purple",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "red" => Some(ColorLabel::Red),
+            "yellow" => Some(ColorLabel::Yellow),
+            "green" => Some(ColorLabel::Green),
+            "blue" => Some(ColorLabel::Blue),
+            "purple" => Some(ColorLabel::Purple),
+            _ => None,
+        }
+    }
+}
+
+/// Culling decision for a photo.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PhotoFlag {
+    #[default]
+    None,
+    Pick,
+    Reject,
+}
+
+impl PhotoFlag {
+    /// Value stored in `photos.flag`: 1 pick, 0 none, -1 reject, so sorting by it
+    /// puts picks and rejects at opposite ends.
+    pub fn to_db(self) -> i64 {
+        match self {
+            PhotoFlag::Pick => 1,
+            PhotoFlag::None => 0,
+            PhotoFlag::Reject => -1,
+        }
+    }
+
+    pub fn from_db(value: i64) -> Self {
+        match value {
+            1 => PhotoFlag::Pick,
+            -1 => PhotoFlag::Reject,
+            _ => PhotoFlag::None,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "pick" | "picked" => Some(PhotoFlag::Pick),
+            "reject" | "rejected" => Some(PhotoFlag::Reject),
+            "none" | "unflagged" => Some(PhotoFlag::None),
+            _ => None,
+        }
+    }
+}
+
+/// Culling conditions for `query_photos`, combined with AND. Empty lists don't filter.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct CullingFilter {
+    pub rating_min: Option<u8>,
+    pub rating_max: Option<u8>,
+    /// Any of these labels
+    pub color_labels: Vec<ColorLabel>,
+    /// Any of these flags
+    pub flags: Vec<PhotoFlag>,
+}
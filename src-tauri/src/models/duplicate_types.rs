@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+/// Photos with identical content.
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateGroup {
+    /// BLAKE3 hash of the file content
+    pub hash: String,
+    /// Size of each copy in bytes
+    pub size: u64,
+    /// Paths of all copies; the suggested one to keep comes first
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    /// Bytes freed by keeping one copy per group
+    pub wasted_bytes: u64,
+    /// Files that had to be hashed for this report; the rest came from earlier runs
+    pub hashed: usize,
+}
+
+/// Photos that look alike: resized, recompressed or lightly edited copies of one shot.
+#[derive(Debug, Serialize, Clone)]
+pub struct NearDuplicateGroup {
+    /// The suggested photo to keep comes first; every other photo is within the threshold of it
+    pub paths: Vec<String>,
+    /// Largest Hamming distance between the first photo's perceptual hash and another member's
+    pub max_distance: u32,
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Clone, Default)]
 pub struct ExifData {
@@ -19,3 +19,46 @@ pub struct ExifData {
     pub flash: Option<String>,
     pub white_balance: Option<String>,
 }
+
+/// Searchable EXIF fields persisted in the `exif` table at import time.
+/// Numeric values are kept as numbers so they can be range-filtered and sorted.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PhotoExif {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    /// `YYYY-MM-DD HH:MM:SS`, so lexical order is chronological order.
+    pub date_taken: Option<String>,
+    /// Milliseconds within the `date_taken` second, for telling burst frames apart.
+    pub subsec_ms: Option<u32>,
+    pub iso: Option<u32>,
+    pub f_number: Option<f64>,
+    /// Seconds.
+    pub exposure_time: Option<f64>,
+    /// Millimetres.
+    pub focal_length: Option<f64>,
+    pub gps_latitude: Option<f64>,
+    pub gps_longitude: Option<f64>,
+    pub software: Option<String>,
+    pub orientation: Option<u32>,
+}
+
+/// Optional EXIF constraints for `query_photos`. All set fields must match.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExifFilter {
+    /// Substring of make or model, case-insensitive.
+    pub camera: Option<String>,
+    /// Substring of the lens model, case-insensitive.
+    pub lens: Option<String>,
+    pub iso_min: Option<u32>,
+    pub iso_max: Option<u32>,
+    pub f_number_min: Option<f64>,
+    pub f_number_max: Option<f64>,
+    pub focal_length_min: Option<f64>,
+    pub focal_length_max: Option<f64>,
+    /// Inclusive lower bound, compared against `YYYY-MM-DD HH:MM:SS`.
+    pub date_from: Option<String>,
+    /// Inclusive upper bound; a bare date such as `2023-08-31` covers the whole day.
+    pub date_to: Option<String>,
+    pub has_gps: Option<bool>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// An image file extension, whether the scanner imports it and whether this build can decode it.
+#[derive(Debug, Serialize, Clone)]
+pub struct FormatSupport {
+    pub extension: String,
+    /// Thumbnails, previews and embeddings work; otherwise the file shows as a placeholder
+    pub decodable: bool,
+    /// False for formats left out of scans because this build has no decoder for them
+    /// (AVIF without the `avif` feature)
+    pub imported: bool,
+}
+
+/// Caps on what a single decode may use, so a crafted file (e.g. a 60000x60000 PNG that
+/// compresses to a few KB) is rejected instead of exhausting memory.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct DecodeLimits {
+    /// Largest width x height accepted
+    pub max_pixels: u64,
+    /// Largest buffer, in bytes, a decoder may allocate
+    pub max_alloc: u64,
+}
+
+impl DecodeLimits {
+    /// 200 MP covers medium-format sensors and most stitched panoramas.
+    pub const DEFAULT: DecodeLimits = DecodeLimits {
+        max_pixels: 200_000_000,
+        max_alloc: 1024 * 1024 * 1024,
+    };
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
@@ -1,3 +1,4 @@
+use crate::models::culling_types::{ColorLabel, PhotoFlag};
 use serde::Serialize;
 
 #[derive(Debug, Serialize, Clone)]
@@ -15,6 +16,8 @@ pub struct DirEntry {
 
 #[derive(Debug, Serialize, Clone)]
 pub struct PhotoEntry {
+    /// Library id, used in `photolense://` image URLs
+    pub id: i64,
     pub name: String,
     pub path: String,
     pub size: u64,
@@ -23,4 +26,14 @@ pub struct PhotoEntry {
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub has_embedding: bool,
+    /// Star rating, 0 (unrated) to 5
+    pub rating: u8,
+    pub color_label: Option<ColorLabel>,
+    pub flag: PhotoFlag,
+    /// Burst or series stack the photo belongs to
+    pub stack_id: Option<i64>,
+    /// Photos in that stack, 0 when not stacked
+    pub stack_size: usize,
+    /// Other files of a RAW+JPEG pair shown as this photo, e.g. the RAW of a JPEG
+    pub companions: Vec<String>,
 }
@@ -1,3 +1,11 @@
+pub mod album_types;
 pub mod classify_types;
+pub mod culling_types;
+pub mod duplicate_types;
 pub mod exif_types;
+pub mod format_types;
 pub mod fs_types;
+pub mod query_types;
+pub mod saved_query_types;
+pub mod tag_types;
+pub mod thumbnail_types;
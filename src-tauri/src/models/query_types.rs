@@ -0,0 +1,62 @@
+use crate::models::culling_types::CullingFilter;
+use crate::models::exif_types::ExifFilter;
+use crate::models::fs_types::PhotoEntry;
+use serde::{Deserialize, Serialize};
+
+/// Filters and ordering for `query_photos` and its paged / streaming variants.
+/// Also the stored form of a saved query.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct PhotoQueryParams {
+    pub folder: String,
+    pub search: Option<String>,
+    pub sort_by: String,
+    pub sort_order: String,
+    pub tag_filter: Option<TagFilter>,
+    pub exif_filter: Option<ExifFilter>,
+    pub culling_filter: Option<CullingFilter>,
+    pub recursive: bool,
+    /// Scope to this album instead of `folder`.
+    pub album_id: Option<i64>,
+    /// Only photos visually similar to a reference photo; sort by `similarity` for closest first.
+    pub similarity: Option<SimilarityParams>,
+    /// Return each stack as its cover only; `stack_size` tells how many photos it holds.
+    pub collapse_stacks: bool,
+}
+
+/// Embedding similarity condition, as set by "Find similar".
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimilarityParams {
+    pub reference_path: String,
+    /// Minimum cosine similarity, 0.0–1.0.
+    pub threshold: f32,
+}
+
+/// Tag conditions for `query_photos`, combined with AND:
+/// every tag in `include_all`, at least one of `include_any` (when non-empty)
+/// and none of `exclude`. "dog AND beach NOT night" is
+/// `{ include_all: [dog, beach], exclude: [night] }`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct TagFilter {
+    pub include_all: Vec<String>,
+    pub include_any: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+/// Keyset pagination cursor: the sort key values of the last row of a page and its id.
+/// Opaque to the frontend; pass `next_cursor` back unchanged to get the following page.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PageCursor {
+    pub sort_key: Vec<serde_json::Value>,
+    pub last_id: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PhotoPage {
+    pub photos: Vec<PhotoEntry>,
+    pub next_cursor: Option<PageCursor>,
+    /// Number of photos matching the query across all pages. Only set on the first page
+    /// (requested without a cursor); keep it for the pages that follow.
+    pub total: Option<usize>,
+}
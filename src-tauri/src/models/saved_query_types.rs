@@ -0,0 +1,16 @@
+use crate::models::query_types::PhotoQueryParams;
+use serde::Serialize;
+
+/// A named `query_photos` request, re-run against the current library whenever it is opened.
+#[derive(Debug, Serialize, Clone)]
+pub struct SavedQuery {
+    pub id: i64,
+    pub name: String,
+    pub query: PhotoQueryParams,
+    /// Shown as a smart album in the sidebar
+    pub pinned: bool,
+    /// Number of matching photos when the query last ran; `None` if it never ran
+    pub cached_count: Option<usize>,
+    /// Unix timestamp (seconds)
+    pub created: i64,
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+/// Source of tags added by hand. Model tags use the model's name instead.
+pub const MANUAL_TAG_SOURCE: &str = "manual";
+
+/// Source of model tags stored before provenance was tracked. The next
+/// classification of the photo replaces them, whichever model runs.
+pub const LEGACY_TAG_SOURCE: &str = "model";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PhotoTag {
+    pub tag: String,
+    /// `manual`, or the name of the model that predicted the tag
+    pub source: String,
+    /// Prediction confidence (0..1); `None` for manual tags
+    pub confidence: Option<f32>,
+}
+
+/// One tag across a folder or the whole library.
+#[derive(Debug, Serialize, Clone)]
+pub struct TagSummary {
+    pub tag: String,
+    /// Photos carrying the tag from any source
+    pub photo_count: usize,
+    /// Average model confidence; `None` when the tag was only added manually
+    pub avg_confidence: Option<f32>,
+    /// Photos carrying the tag as a manual tag
+    pub manual_count: usize,
+}
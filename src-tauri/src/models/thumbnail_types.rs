@@ -0,0 +1,10 @@
+use serde::Serialize;
+
+/// Size of the on-disk thumbnail cache and its cap.
+#[derive(Debug, Serialize, Clone)]
+pub struct ThumbnailCacheInfo {
+    pub files: u64,
+    pub size_bytes: u64,
+    /// Least recently used thumbnails are deleted beyond this
+    pub max_bytes: u64,
+}
@@ -0,0 +1,308 @@
+use crate::error::{AppError, DecodeError};
+use crate::services::db::Database;
+use crate::services::thumbnail_cache::ThumbnailCache;
+use crate::services::{decode_service, exif_service, fs_service, raw_service};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{Manager, Runtime, UriSchemeContext, UriSchemeResponder};
+
+/// Scheme the webview loads photos from, so image bytes skip JSON IPC and base64:
+/// - `thumb/{id}`: the cached thumbnail, generated on first request
+/// - `full/{id}`: the original file, with Range support; formats the webview can't show
+///   (RAW, SVG, JPEG XL) and files too large to send whole are sent as a rendered JPEG instead
+/// - `full/{id}?max=N`: a JPEG scaled down to fit N x N, upright and in sRGB
+///
+/// The frontend builds URLs with `convertFileSrc`, which is `photolense://localhost/...` on
+/// macOS and Linux and `http://photolense.localhost/...` on Windows.
+pub const SCHEME: &str = "photolense";
+
+type Reply = Response<Vec<u8>>;
+
+/// Largest part of an original read into one response. Range requests get at most this
+/// much; whole files over it are sent rendered at `FALLBACK_SIZE` instead.
+const MAX_ORIGINAL_BYTES: u64 = 32 * 1024 * 1024;
+/// Longer side of the JPEG sent in place of an original too large to send whole.
+const FALLBACK_SIZE: u32 = 4096;
+
+pub fn handle<R: Runtime>(ctx: UriSchemeContext<'_, R>, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    // Neither is managed when the library failed to open and the app is about to exit
+    let app = ctx.app_handle();
+    let (Some(db), Some(thumbnails)) = (app.try_state::<Database>(), app.try_state::<ThumbnailCache>()) else {
+        responder.respond(status(StatusCode::SERVICE_UNAVAILABLE, "Photo library is not open"));
+        return;
+    };
+    let db = db.inner().clone();
+    let thumbnails = thumbnails.inner().clone();
+    // Decoding and file reads block, so keep them off the webview's thread
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut reply = route(&db, &thumbnails, &request).unwrap_or_else(|e| e);
+        if *request.method() == Method::HEAD {
+            reply.body_mut().clear();
+        }
+        responder.respond(reply);
+    });
+}
+
+fn route(db: &Database, thumbnails: &ThumbnailCache, request: &Request<Vec<u8>>) -> Result<Reply, Reply> {
+    // convertFileSrc percent-encodes the slash between kind and id
+    let path = percent_decode(request.uri().path());
+    let (kind, id) = path
+        .trim_start_matches('/')
+        .split_once('/')
+        .ok_or_else(|| status(StatusCode::NOT_FOUND, "Unknown image URL"))?;
+    let id: i64 = id.parse().map_err(|_| status(StatusCode::BAD_REQUEST, "Invalid photo id"))?;
+    let (file_path, size, modified) = db
+        .get_photo_file(id)
+        .map_err(|e| status(StatusCode::INTERNAL_SERVER_ERROR, &format!("DB Error: {}", e)))?
+        .ok_or_else(|| status(StatusCode::NOT_FOUND, "Photo not found"))?;
+    let file_path = Path::new(&file_path);
+
+    match kind {
+        "thumb" => {
+            let etag = format!("\"thumb-{}-{}-{}\"", id, size, modified);
+            if is_fresh(request, &etag) {
+                return Ok(not_modified(&etag));
+            }
+            let bytes = thumbnails.get_or_create(id, file_path).map_err(server_error)?;
+            Ok(image(bytes, "image/jpeg", &etag))
+        }
+        "full" => match query_param(request, "max") {
+            Some(max) => {
+                let max: u32 = max
+                    .parse()
+                    .ok()
+                    .filter(|&max| max > 0)
+                    .ok_or_else(|| status(StatusCode::BAD_REQUEST, "Invalid max size"))?;
+                let etag = format!("\"full-{}-{}-{}-{}\"", id, size, modified, max);
+                if is_fresh(request, &etag) {
+                    return Ok(not_modified(&etag));
+                }
+                let img = decode_service::decode_file(file_path).map_err(decode_error)?.fit(max).display();
+                Ok(image(encode_jpeg(&img).map_err(server_error)?, "image/jpeg", &etag))
+            }
+            None => original(request, file_path),
+        },
+        _ => Err(status(StatusCode::NOT_FOUND, "Unknown image URL")),
+    }
+}
+
+/// The file itself, or a byte range of it. Validated against the file on disk rather than
+/// the library, which may not have caught up with a change yet.
+fn original(request: &Request<Vec<u8>>, path: &Path) -> Result<Reply, Reply> {
+    let metadata = std::fs::metadata(path).map_err(|_| status(StatusCode::NOT_FOUND, "File not found"))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    let etag = format!("\"file-{}-{}\"", metadata.len(), modified);
+    if is_fresh(request, &etag) {
+        return Ok(not_modified(&etag));
+    }
+
+    if fs_service::is_raw_file(path) || decode_service::needs_rendering(path) {
+        return Ok(image(rendered(path).map_err(server_error)?, "image/jpeg", &etag));
+    }
+
+    let len = metadata.len();
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(ByteRange::Full, |v| parse_range(v, len));
+    // `<img>` asks for the whole file and never follows up on a partial reply, so a file too
+    // large to buffer is sent scaled down instead
+    if matches!(range, ByteRange::Full) && len > MAX_ORIGINAL_BYTES {
+        let img = decode_service::decode_file(path).map_err(decode_error)?.fit(FALLBACK_SIZE).display();
+        return Ok(image(encode_jpeg(&img).map_err(server_error)?, "image/jpeg", &etag));
+    }
+    let mut file = File::open(path).map_err(server_error)?;
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type(path))
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ETAG, &etag)
+        .header(header::ACCEPT_RANGES, "bytes");
+    let reply = match range {
+        ByteRange::Full => {
+            let mut bytes = Vec::with_capacity(len as usize);
+            file.read_to_end(&mut bytes).map_err(server_error)?;
+            builder.status(StatusCode::OK).body(bytes)
+        }
+        ByteRange::Partial(start, end) => {
+            // Clients ask for the rest with a further range request
+            let end = end.min(start + MAX_ORIGINAL_BYTES - 1);
+            let mut bytes = Vec::with_capacity((end - start + 1) as usize);
+            file.seek(SeekFrom::Start(start)).map_err(server_error)?;
+            file.take(end - start + 1).read_to_end(&mut bytes).map_err(server_error)?;
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len))
+                .body(bytes)
+        }
+        ByteRange::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Vec::new()),
+    };
+    reply.map_err(server_error)
+}
+
+/// A JPEG of a file the webview can't display: the embedded preview of a RAW file (as is
+/// when it needs no rotation), or SVG and JPEG XL rendered.
+fn rendered(path: &Path) -> Result<Vec<u8>, AppError> {
+    if fs_service::is_raw_file(path) {
+        let preview = raw_service::extract_preview(path)?;
+        if preview.orientation == 1 {
+            return Ok(preview.jpeg);
+        }
+        let img = decode_service::decode_bytes(&preview.jpeg)?;
+        return encode_jpeg(&exif_service::apply_orientation(img, preview.orientation));
+    }
+    encode_jpeg(&decode_service::decode_file(path)?.display())
+}
+
+fn encode_jpeg(img: &image::DynamicImage) -> Result<Vec<u8>, AppError> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut buffer, image::ImageFormat::Jpeg).map_err(|e| AppError {
+        message: format!("Failed to encode image: {}", e),
+        ..Default::default()
+    })?;
+    Ok(buffer.into_inner())
+}
+
+enum ByteRange {
+    Full,
+    /// First and last byte, inclusive
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// A single `bytes=` range of a `len`-byte file. Multiple ranges aren't supported, so the
+/// whole file is sent for those, as HTTP allows.
+fn parse_range(value: &str, len: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Suffix range: the last `n` bytes
+        ("", n) => match n.parse::<u64>() {
+            Ok(n) if n > 0 && len > 0 => (len.saturating_sub(n), len - 1),
+            _ => return ByteRange::Unsatisfiable,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, len.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end)
+}
+
+/// Whether the webview's cached copy, sent as `If-None-Match`, is still current.
+fn is_fresh(request: &Request<Vec<u8>>, etag: &str) -> bool {
+    request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"))
+}
+
+fn query_param<'a>(request: &'a Request<Vec<u8>>, name: &str) -> Option<&'a str> {
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match fs_service::lowercase_extension(path).as_deref() {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        Some("tif" | "tiff") => "image/tiff",
+        Some("ico") => "image/x-icon",
+        Some("avif") => "image/avif",
+        _ => "application/octet-stream",
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// A generated image; revalidated by ETag on every use, since the photo may change on disk.
+fn image(bytes: Vec<u8>, content_type: &str, etag: &str) -> Reply {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ETAG, etag)
+        .body(bytes)
+        .unwrap_or_else(server_error)
+}
+
+fn not_modified(etag: &str) -> Reply {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag)
+        .body(Vec::new())
+        .unwrap_or_else(server_error)
+}
+
+fn status(code: StatusCode, message: &str) -> Reply {
+    let mut reply = Response::new(message.as_bytes().to_vec());
+    *reply.status_mut() = code;
+    reply
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("text/plain; charset=utf-8"));
+    reply
+}
+
+fn server_error(e: impl std::fmt::Display) -> Reply {
+    status(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+}
+
+fn decode_error(e: DecodeError) -> Reply {
+    let code = match e {
+        DecodeError::Unsupported(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        DecodeError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        DecodeError::Corrupt(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        DecodeError::Io(_) => StatusCode::NOT_FOUND,
+    };
+    status(code, &e.to_string())
+}
@@ -1,7 +1,7 @@
 use crate::error::AppError;
 use crate::models::classify_types::Prediction;
 use crate::services::classifier::model_manager::TractModel;
-use image::ImageReader;
+use crate::services::decode_service;
 use ndarray::Array4;
 use ort::value::Value;
 use std::path::Path;
@@ -13,14 +13,7 @@ const MEAN: [f32; 3] = [0.485, 0.456, 0.406];
 const STD: [f32; 3] = [0.229, 0.224, 0.225];
 
 pub fn preprocess_image(path: &Path, crop_size: u32) -> Result<Array4<f32>, AppError> {
-    let img = ImageReader::open(path)
-        .map_err(|e| AppError {
-            message: format!("Failed to open image {}: {}", path.display(), e),
-        })?
-        .decode()
-        .map_err(|e| AppError {
-            message: format!("Failed to decode image {}: {}", path.display(), e),
-        })?;
+    let img = decode_service::decode_file(path)?.display();
 
     // Preprocessing: resize shortest edge to ceil(crop_size / crop_pct), then center crop
     let resize_size = (crop_size as f32 / CROP_PCT).ceil() as u32;
@@ -71,6 +64,7 @@ pub fn preprocess_image(path: &Path, crop_size: u32) -> Result<Array4<f32>, AppE
     )
     .map_err(|e| AppError {
         message: format!("Failed to create tensor: {}", e),
+        ..Default::default()
     })?;
 
     Ok(tensor)
@@ -88,12 +82,13 @@ pub fn run_inference_with_model(
 
     // Create tensor Value
     let input_tensor = Value::from_array(input)
-        .map_err(|e| AppError { message: format!("Failed to create tensor value: {}", e) })?;
+        .map_err(|e| AppError { message: format!("Failed to create tensor value: {}", e), ..Default::default() })?;
 
     // Run inference
     let outputs = model
         .run(ort::inputs![input_name.as_str() => input_tensor])
         .map_err(|e| AppError {            message: format!("Inference failed: {}", e),
+            ..Default::default()
         })?;
 
     // Get the first output tensor
@@ -102,12 +97,14 @@ pub fn run_inference_with_model(
         .next()
         .ok_or_else(|| AppError {
             message: "Model produced no outputs".to_string(),
+            ..Default::default()
         })?;
 
     let (_, data) = output_value
         .try_extract_tensor::<f32>()
         .map_err(|e| AppError {
             message: format!("Failed to extract output tensor: {}", e),
+            ..Default::default()
         })?;
 
     // Compute L2-normalized embedding from raw logits (before softmax)
@@ -54,6 +54,12 @@ impl ModelType {
         }
     }
 
+    /// Name recorded as the source of the tags this model predicts, e.g. `convnextv2-base-22k-384`.
+    pub fn tag_source(&self) -> &'static str {
+        let (_, _, model_file, _) = self.config();
+        model_file.trim_end_matches(".onnx")
+    }
+
     pub fn crop_size(&self) -> u32 {
         match self {
             ModelType::Base | ModelType::Large => 384,
@@ -149,6 +155,7 @@ impl ModelManager {
 
         std::fs::create_dir_all(&self.model_dir).map_err(|e| AppError {
             message: format!("Failed to create model directory: {}", e),
+            ..Default::default()
         })?;
 
         let type_lock = self.current_type.lock().await;
@@ -168,10 +175,10 @@ impl ModelManager {
         if current_type_enum == ModelType::MobileNetV3Large {
             let client = reqwest::Client::new();
             let resp = client.get(UPDATE_API_URL).send().await
-                .map_err(|e| AppError { message: format!("Failed to fetch update info: {}", e) })?;
+                .map_err(|e| AppError { message: format!("Failed to fetch update info: {}", e), ..Default::default() })?;
             
             let update_data: UpdateResponse = resp.json().await
-                .map_err(|e| AppError { message: format!("Failed to parse update info: {}", e) })?;
+                .map_err(|e| AppError { message: format!("Failed to parse update info: {}", e), ..Default::default() })?;
 
             for file in update_data.files {
                 if file.file_name == "mobilenetv3_large.onnx" {
@@ -245,16 +252,19 @@ impl ModelManager {
             .await
             .map_err(|e| AppError {
                 message: format!("Failed to read config file {}: {}", config_path.display(), e),
+                ..Default::default()
             })?;
 
         let config: serde_json::Value = serde_json::from_str(&config_content).map_err(|e| AppError {
             message: format!("Failed to parse config JSON: {}", e),
+            ..Default::default()
         })?;
 
         let id2label = config["id2label"]
             .as_object()
             .ok_or_else(|| AppError {
                 message: "Config missing id2label field".to_string(),
+                ..Default::default()
             })?;
 
         let mut labels: Vec<(usize, String)> = id2label
@@ -279,11 +289,11 @@ impl ModelManager {
                 .commit();
 
             let mut builder = Session::builder()
-                .map_err(|e| AppError { message: format!("Failed to create session builder: {}", e) })?
+                .map_err(|e| AppError { message: format!("Failed to create session builder: {}", e), ..Default::default() })?
                 .with_optimization_level(ort::session::builder::GraphOptimizationLevel::Level3)
-                .map_err(|e| AppError { message: format!("Failed to set optimization level: {}", e) })?
+                .map_err(|e| AppError { message: format!("Failed to set optimization level: {}", e), ..Default::default() })?
                 .with_intra_threads(4)
-                .map_err(|e| AppError { message: format!("Failed to set intra threads: {}", e) })?;
+                .map_err(|e| AppError { message: format!("Failed to set intra threads: {}", e), ..Default::default() })?;
 
             if use_gpu {
                 builder = builder.with_execution_providers([
@@ -291,16 +301,17 @@ impl ModelManager {
                     ort::execution_providers::CoreMLExecutionProvider::default().build(),
                     ort::execution_providers::CUDAExecutionProvider::default().build(),
                     ort::execution_providers::CPUExecutionProvider::default().build(),
-                ]).map_err(|e| AppError { message: format!("Failed to register GPU execution providers: {}", e) })?;
+                ]).map_err(|e| AppError { message: format!("Failed to register GPU execution providers: {}", e), ..Default::default() })?;
             } else {
                 builder = builder.with_execution_providers([
                     ort::execution_providers::CPUExecutionProvider::default().build(),
-                ]).map_err(|e| AppError { message: format!("Failed to register CPU execution provider: {}", e) })?;
+                ]).map_err(|e| AppError { message: format!("Failed to register CPU execution provider: {}", e), ..Default::default() })?;
             }
 
             let session = builder.commit_from_file(model_path)
                 .map_err(|e| AppError {
                     message: format!("Failed to load ONNX model: {}", e),
+                    ..Default::default()
                 })?;
                 
             Ok(session)
@@ -308,6 +319,7 @@ impl ModelManager {
         .await
         .map_err(|e| AppError {
             message: format!("Failed to spawn model loading task: {}", e),
+            ..Default::default()
         })??;
 
         *self.model.lock().unwrap() = Some(model);
@@ -326,6 +338,7 @@ impl ModelManager {
             .clone()
             .ok_or_else(|| AppError {
                 message: "Labels not loaded".to_string(),
+                ..Default::default()
             })
     }
 }
@@ -348,6 +361,7 @@ async fn download_file(url: &str, dest: &PathBuf, app: &AppHandle, cancel_flag:
 
     let mut file = tokio::fs::File::create(dest).await.map_err(|e| AppError {
         message: format!("Failed to create file {}: {}", dest.display(), e),
+        ..Default::default()
     })?;
 
     let mut stream = response.bytes_stream();
@@ -367,6 +381,7 @@ async fn download_file(url: &str, dest: &PathBuf, app: &AppHandle, cancel_flag:
             .await
             .map_err(|e| AppError {
                 message: format!("Failed to write to file: {}", e),
+                ..Default::default()
             })?;
         
         if total_size > 0 {
@@ -3,9 +3,7 @@ use lab::Lab;
 use std::collections::HashMap;
 use std::path::Path;
 use crate::error::AppError;
-use crate::services::thumbnail_service;
-use std::io::Cursor;
-use image::ImageReader;
+use crate::services::{decode_service, thumbnail_service};
 
 pub fn kmeans_clustering(items: Vec<(String, Lab)>, k: usize) -> HashMap<String, Vec<String>> {
     if items.is_empty() {
@@ -140,15 +138,7 @@ pub fn get_image_lab(path: &Path) -> Result<Lab, AppError> {
     let thumb_bytes = thumbnail_service::generate_thumbnail_bytes(path)?;
 
     // 2. Decode the small thumbnail
-    let img = ImageReader::new(Cursor::new(thumb_bytes))
-        .with_guessed_format()
-        .map_err(|e| AppError {
-            message: format!("Failed to read thumbnail format: {}", e),
-        })?
-        .decode()
-        .map_err(|e| AppError {
-            message: format!("Failed to decode thumbnail: {}", e),
-        })?;
+    let img = decode_service::decode_bytes(&thumb_bytes)?;
 
     // 3. Calculate average RGB
     // The thumbnail is already small (~200px), so we can iterate directly.
@@ -159,7 +149,7 @@ pub fn get_image_lab(path: &Path) -> Result<Lab, AppError> {
     let count = (width * height) as u64;
 
     if count == 0 {
-         return Err(AppError { message: "Image has no pixels".to_string() });
+         return Err(AppError { message: "Image has no pixels".to_string(), ..Default::default() });
     }
 
     for pixel in img.pixels() {
@@ -1,4 +1,14 @@
-use rusqlite::{params, Connection, Result};
+use crate::error::AppError;
+use crate::models::album_types::Album;
+use crate::models::culling_types::{ColorLabel, CullingFilter, PhotoFlag};
+use crate::models::exif_types::{ExifFilter, PhotoExif};
+use crate::models::query_types::{PageCursor, PhotoQueryParams, SimilarityParams, TagFilter};
+use crate::models::saved_query_types::SavedQuery;
+use crate::models::tag_types::{PhotoTag, TagSummary, LEGACY_TAG_SOURCE, MANUAL_TAG_SOURCE};
+use crate::services::migrations;
+use crate::services::search_query::{Condition, Presence, SearchQuery, Term};
+use rusqlite::types::{ToSql, Value};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use sqlite_vec::sqlite3_vec_init;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
@@ -9,8 +19,710 @@ pub struct Database {
     conn: Arc<Mutex<Connection>>,
 }
 
+/// Order for tag names grouped per photo: manual tags (`?2` = manual source) first, then
+/// by the best confidence any model gave the tag.
+const TAG_ORDER: &str = "MAX(source = ?2) DESC, MAX(confidence) DESC, tag ASC";
+
+/// How long rows of removed files are kept for re-linking before they are purged.
+const REMOVED_RETENTION_SECS: i64 = 30 * 24 * 60 * 60;
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Folder key stored in `photos.folder`: the parent directory without trailing separators,
+/// so `/photos/2023/` and `/photos/2023` scope the same rows.
+fn normalize_folder(folder: &str) -> String {
+    folder.trim_end_matches(['/', '\\']).to_string()
+}
+
+/// Folder key for a file path (its parent directory).
+pub(crate) fn parent_folder(path: &str) -> String {
+    Path::new(path)
+        .parent()
+        .map(|p| normalize_folder(&p.to_string_lossy()))
+        .unwrap_or_default()
+}
+
+/// Restricts `photos.folder` either to exactly one folder or to the folder and its subtree.
+/// Matching is done with `=` and a half-open range instead of `LIKE`, so sibling folders
+/// sharing a prefix (`2023` vs `2023-old`) never match and `_`/`%` in paths are literal.
+struct FolderScope {
+    key: String,
+    recursive: bool,
+}
+
+impl FolderScope {
+    fn new(folder: &str, recursive: bool) -> Self {
+        Self {
+            key: normalize_folder(folder),
+            recursive,
+        }
+    }
+
+    /// Number of placeholders used by `sql`.
+    fn param_count(&self) -> usize {
+        if self.recursive { 3 } else { 1 }
+    }
+
+    /// WHERE fragment against `col`, with placeholders numbered from `?{first}`.
+    fn sql(&self, col: &str, first: usize) -> String {
+        if self.recursive {
+            format!(
+                "({col} = ?{a} OR ({col} >= ?{b} AND {col} < ?{c}))",
+                col = col,
+                a = first,
+                b = first + 1,
+                c = first + 2
+            )
+        } else {
+            format!("{} = ?{}", col, first)
+        }
+    }
+
+    /// Values bound to the placeholders of `sql`, in order.
+    fn params(&self) -> Vec<String> {
+        if self.recursive {
+            // Every descendant folder starts with "<key><sep>"; the character after the
+            // separator in code-point order bounds the range from above.
+            let sep = std::path::MAIN_SEPARATOR;
+            let next = char::from_u32(sep as u32 + 1).unwrap_or(sep);
+            vec![
+                self.key.clone(),
+                format!("{}{}", self.key, sep),
+                format!("{}{}", self.key, next),
+            ]
+        } else {
+            vec![self.key.clone()]
+        }
+    }
+
+    /// Bind this scope into `filter` as a condition against `col`.
+    fn apply(&self, col: &str, filter: &mut SqlFilter) {
+        let first = filter.values.len() + 1;
+        for value in self.params() {
+            filter.values.push(Box::new(value));
+        }
+        filter.conditions.push(self.sql(col, first));
+    }
+}
+
+/// WHERE conditions and their bound values for dynamically built queries.
+#[derive(Default)]
+struct SqlFilter {
+    conditions: Vec<String>,
+    values: Vec<Box<dyn ToSql>>,
+}
+
+impl SqlFilter {
+    /// Bind a value and return its numbered placeholder.
+    fn bind<T: ToSql + 'static>(&mut self, value: T) -> String {
+        self.values.push(Box::new(value));
+        format!("?{}", self.values.len())
+    }
+
+    /// Bind every value and return the comma-separated placeholders, for `IN (...)`.
+    fn bind_list(&mut self, values: &[String]) -> String {
+        let placeholders: Vec<String> = values.iter().map(|v| self.bind(v.clone())).collect();
+        placeholders.join(", ")
+    }
+
+    fn push(&mut self, condition: String) {
+        self.conditions.push(condition);
+    }
+
+    /// `WHERE a AND b ...`, or an empty string when there are no conditions.
+    fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", self.conditions.join(" AND "))
+        }
+    }
+
+    fn params(&self) -> Vec<&dyn ToSql> {
+        self.values.iter().map(|v| v.as_ref()).collect()
+    }
+}
+
+/// Turn free search words into an FTS5 query: every word must match, as a prefix.
+/// Words are quoted so FTS syntax characters in user input are taken literally;
+/// words without any letters or digits are dropped since they index to nothing.
+fn fts_match_query<'a>(words: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let terms: Vec<String> = words
+        .into_iter()
+        .flat_map(str::split_whitespace)
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Photos found by the embedding KNN search for `similar:` (same limit and default
+/// threshold as the "Find similar" button).
+const SIMILAR_LIMIT: i64 = 200;
+const SIMILAR_MAX_DISTANCE: f64 = 0.5;
+
+/// Numeric comparison or inclusive range against `col`.
+fn condition_sql<T: ToSql + Clone + 'static>(filter: &mut SqlFilter, col: &str, condition: &Condition<T>) -> String {
+    match condition {
+        Condition::Cmp(op, value) => format!("{} {} {}", col, op.sql(), filter.bind(value.clone())),
+        Condition::Range(from, to) => {
+            let from = filter.bind(from.clone());
+            let to = filter.bind(to.clone());
+            format!("{} BETWEEN {} AND {}", col, from, to)
+        }
+    }
+}
+
+/// Date comparison on the `YYYY-MM-DD HH:MM:SS` text, truncated to the length of the
+/// given value so `2023-06` compares against the month.
+fn date_condition_sql(filter: &mut SqlFilter, condition: &Condition<String>) -> String {
+    let mut prefix_cmp = |op: &str, value: &String| {
+        let ph = filter.bind(value.clone());
+        format!("substr(e.date_taken, 1, length({ph})) {op} {ph}", ph = ph, op = op)
+    };
+    match condition {
+        Condition::Cmp(op, value) => prefix_cmp(op.sql(), value),
+        Condition::Range(from, to) => format!("({} AND {})", prefix_cmp(">=", from), prefix_cmp("<=", to)),
+    }
+}
+
+/// Compile a parsed search into conditions on `filter`. Positive free-text words are
+/// returned as an FTS5 query so the caller can join `photos_fts` and rank by it.
+fn push_search_conditions(conn: &Connection, filter: &mut SqlFilter, query: &SearchQuery) -> Result<Option<String>> {
+    let has_vec_table = vec_table_exists(conn);
+    let mut text = Vec::new();
+    for clause in &query.clauses {
+        let condition = match &clause.term {
+            Term::Text(word) if !clause.negated => {
+                text.push(word.as_str());
+                continue;
+            }
+            Term::Text(word) => match fts_match_query([word.as_str()]) {
+                // Negated below: photos whose index row matches the word
+                Some(fts) => format!(
+                    "p.id IN (SELECT rowid FROM photos_fts WHERE photos_fts MATCH {})",
+                    filter.bind(fts)
+                ),
+                None => continue,
+            },
+            Term::Tag(tag) => format!(
+                "EXISTS (SELECT 1 FROM tags t WHERE t.photo_id = p.id AND t.tag = {} COLLATE NOCASE)",
+                filter.bind(tag.clone())
+            ),
+            Term::Camera(camera) => {
+                let ph = filter.bind(camera.to_lowercase());
+                format!(
+                    "(instr(lower(e.camera_make), {ph}) > 0 OR instr(lower(e.camera_model), {ph}) > 0)",
+                    ph = ph
+                )
+            }
+            Term::Lens(lens) => format!("instr(lower(e.lens_model), {}) > 0", filter.bind(lens.to_lowercase())),
+            Term::Iso(c) => condition_sql(filter, "e.iso", c),
+            Term::FNumber(c) => condition_sql(filter, "e.f_number", c),
+            Term::FocalLength(c) => condition_sql(filter, "e.focal_length", c),
+            Term::Width(c) => condition_sql(filter, "p.width", c),
+            Term::Height(c) => condition_sql(filter, "p.height", c),
+            Term::Size(c) => condition_sql(filter, "p.size", c),
+            Term::Date(c) => date_condition_sql(filter, c),
+            Term::Rating(c) => condition_sql(filter, "p.rating", c),
+            Term::Label(label) => format!("p.color_label = {}", filter.bind(label.as_str())),
+            Term::Flag(flag) => format!("p.flag = {}", filter.bind(flag.to_db())),
+            Term::Has(Presence::Gps) => "e.gps_latitude IS NOT NULL".to_string(),
+            Term::Has(Presence::Tags) => "EXISTS (SELECT 1 FROM tags t WHERE t.photo_id = p.id)".to_string(),
+            // Files without EXIF still get an all-NULL row, so check the common fields
+            Term::Has(Presence::Exif) => {
+                "coalesce(e.camera_make, e.camera_model, e.date_taken) IS NOT NULL".to_string()
+            }
+            // Nothing has been indexed yet
+            Term::Has(Presence::Embedding) | Term::Similar(_) if !has_vec_table => "0".to_string(),
+            Term::Has(Presence::Embedding) => "p.id IN (SELECT photo_id FROM vec_photos)".to_string(),
+            Term::Similar(reference) => match similar_reference_id(conn, reference)? {
+                Some(reference_id) => {
+                    let knn = knn_sql(filter, reference_id);
+                    let max_distance = filter.bind(SIMILAR_MAX_DISTANCE);
+                    format!(
+                        "p.id IN (SELECT knn.photo_id FROM ({}) knn WHERE knn.distance <= {})",
+                        knn, max_distance
+                    )
+                }
+                // Unknown or not yet indexed reference photo
+                None => "0".to_string(),
+            },
+        };
+        // Missing values (no EXIF row, unknown size) count as "not matching", so they
+        // are included by a negated clause
+        filter.push(if clause.negated {
+            format!("NOT coalesce(({}), 0)", condition)
+        } else {
+            condition
+        });
+    }
+    Ok(fts_match_query(text))
+}
+
+/// Nearest neighbours of the reference photo's embedding as `(photo_id, distance)` rows.
+/// sqlite-vec takes the neighbour count as `k = ?` in the WHERE clause instead of a LIMIT.
+fn knn_sql(filter: &mut SqlFilter, reference_id: i64) -> String {
+    let reference_id = filter.bind(reference_id);
+    let limit = filter.bind(SIMILAR_LIMIT);
+    format!(
+        "SELECT v.photo_id, v.distance FROM vec_photos v
+         WHERE v.embedding MATCH (SELECT embedding FROM vec_photos WHERE photo_id = {})
+         AND k = {}",
+        reference_id, limit
+    )
+}
+
+/// JOIN exposing `sim.distance` for the photos within the threshold of the reference photo,
+/// which itself is left out like in "Find similar". Joins nothing while the reference has
+/// no embedding yet.
+fn similarity_join_sql(conn: &Connection, filter: &mut SqlFilter, similarity: &SimilarityParams) -> Result<String> {
+    let reference_id = if vec_table_exists(conn) {
+        similar_reference_id(conn, &similarity.reference_path)?
+    } else {
+        None
+    };
+    let Some(reference_id) = reference_id else {
+        return Ok(" JOIN (SELECT NULL AS photo_id, NULL AS distance) sim ON 0".to_string());
+    };
+
+    let join = format!(" JOIN ({}) sim ON sim.photo_id = p.id", knn_sql(filter, reference_id));
+    // Cosine distance = 1 - similarity
+    let max_distance = filter.bind(1.0 - similarity.threshold as f64);
+    filter.push(format!("sim.distance <= {}", max_distance));
+    let reference_id = filter.bind(reference_id);
+    filter.push(format!("p.id != {}", reference_id));
+    Ok(join)
+}
+
+/// Photo referenced by `similar:`, by full path or else by file name, if it has an embedding.
+fn similar_reference_id(conn: &Connection, reference: &str) -> Result<Option<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT r.id FROM photos r
+         JOIN vec_photos v ON v.photo_id = r.id
+         WHERE (r.path = ?1 OR substr(r.path, length(r.folder) + 2) = ?1) AND r.removed_at IS NULL
+         ORDER BY r.path = ?1 DESC, r.id
+         LIMIT 1",
+    )?;
+    let mut rows = stmt.query(params![reference])?;
+    rows.next()?.map(|row| row.get(0)).transpose()
+}
+
+/// Filters and ordering shared by the photo listing queries.
+pub struct PhotoQuery<'a> {
+    pub folder: &'a str,
+    pub recursive: bool,
+    /// Scope to this album instead of `folder`; sorting by `position` uses the album order.
+    pub album: Option<i64>,
+    pub search: Option<&'a SearchQuery>,
+    pub sort_by: &'a str,
+    pub sort_order: &'a str,
+    pub tags: Option<&'a TagFilter>,
+    pub exif: Option<&'a ExifFilter>,
+    pub culling: Option<&'a CullingFilter>,
+    pub similarity: Option<&'a SimilarityParams>,
+    /// Show each stack as its cover photo only.
+    pub collapse_stacks: bool,
+}
+
+/// A photo as returned by the listing queries.
+pub struct PhotoRow {
+    pub id: i64,
+    pub path: String,
+    pub size: i64,
+    pub modified: i64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub rating: u8,
+    pub color_label: Option<ColorLabel>,
+    pub flag: PhotoFlag,
+    pub stack_id: Option<i64>,
+    /// Live photos in the photo's stack, 0 when it is not stacked
+    pub stack_size: usize,
+}
+
+/// A photo that shares its size with another one, so it may be a duplicate.
+pub struct DuplicateCandidate {
+    pub id: i64,
+    pub path: String,
+    pub size: u64,
+    /// Full-file hash, `None` until the duplicate finder hashed the current file
+    pub content_hash: Option<String>,
+}
+
+/// A dated photo considered by burst detection.
+pub struct BurstCandidate {
+    pub id: i64,
+    pub folder: String,
+    /// Make and model; frames from different cameras never share a stack
+    pub camera: String,
+    /// Capture time in milliseconds since the epoch, from `date_taken` and `subsec_ms`
+    pub capture_ms: i64,
+    pub perceptual_hash: Option<u64>,
+    pub rating: u8,
+}
+
+/// Columns read by `photo_row`, in order.
+const PHOTO_COLUMNS: &str = "p.id, p.path, p.size, p.modified, p.width, p.height, p.rating, p.color_label, p.flag,
+    p.stack_id, (SELECT COUNT(*) FROM photos m WHERE m.stack_id = p.stack_id AND m.removed_at IS NULL)";
+const PHOTO_COLUMN_COUNT: usize = 11;
+
+/// Id of the photo standing for the stack of `p`: the chosen cover while it is live,
+/// otherwise the first imported live member.
+const STACK_COVER_SQL: &str = "(SELECT COALESCE(
+        (SELECT c.id FROM photos c WHERE c.id = s.cover_id AND c.removed_at IS NULL),
+        (SELECT MIN(m.id) FROM photos m WHERE m.stack_id = s.id AND m.removed_at IS NULL))
+     FROM stacks s WHERE s.id = p.stack_id)";
+
+fn photo_row(row: &rusqlite::Row) -> Result<PhotoRow> {
+    Ok(PhotoRow {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        size: row.get(2)?,
+        modified: row.get(3)?,
+        width: row.get(4)?,
+        height: row.get(5)?,
+        rating: row.get(6)?,
+        color_label: row.get::<_, Option<String>>(7)?.as_deref().and_then(ColorLabel::parse),
+        flag: PhotoFlag::from_db(row.get(8)?),
+        stack_id: row.get(9)?,
+        stack_size: row.get::<_, i64>(10)? as usize,
+    })
+}
+
+/// A `PhotoQuery` turned into SQL: FROM clause with joins, WHERE conditions and the
+/// ORDER BY keys as (expression, descending). `p.id` is always the last key, so the
+/// order is total and can be used for keyset pagination.
+struct CompiledQuery {
+    from: String,
+    filter: SqlFilter,
+    order_keys: Vec<(String, bool)>,
+}
+
+impl CompiledQuery {
+    /// Missing values (e.g. photos without EXIF) sort last in both directions.
+    fn order_by(&self) -> String {
+        self.order_keys
+            .iter()
+            .map(|(expr, desc)| format!("{} {} NULLS LAST", expr, if *desc { "DESC" } else { "ASC" }))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Restrict to rows after `cursor` in `order_by` order: for some key, all earlier keys
+    /// equal the cursor and that key comes after it (NULLs counting as largest).
+    fn push_keyset_condition(&mut self, cursor: &PageCursor) -> Result<()> {
+        let (last_id_key, value_keys) = self.order_keys.split_last().expect("order keys end with p.id");
+        if cursor.sort_key.len() != value_keys.len() {
+            return Err(rusqlite::Error::InvalidParameterCount(cursor.sort_key.len(), value_keys.len()));
+        }
+
+        let mut alternatives = Vec::new();
+        let mut equal_so_far: Vec<String> = Vec::new();
+        for ((expr, desc), value) in value_keys.iter().zip(&cursor.sort_key) {
+            if value.is_null() {
+                // Only other NULLs can follow a NULL, and those are ordered by the later keys
+                equal_so_far.push(format!("{} IS NULL", expr));
+                continue;
+            }
+            let ph = self.filter.bind(json_to_sql(value));
+            let after = format!("({} {} {} OR {} IS NULL)", expr, if *desc { "<" } else { ">" }, ph, expr);
+            alternatives.push(and_all(&equal_so_far, after));
+            equal_so_far.push(format!("{} = {}", expr, ph));
+        }
+        let id = self.filter.bind(cursor.last_id);
+        let (id_expr, id_desc) = last_id_key;
+        alternatives.push(and_all(&equal_so_far, format!("{} {} {}", id_expr, if *id_desc { "<" } else { ">" }, id)));
+
+        self.filter.push(format!("({})", alternatives.join(" OR ")));
+        Ok(())
+    }
+}
+
+/// Dissolve stacks with fewer than two members and delete stacks without any.
+fn prune_stacks(tx: &rusqlite::Transaction) -> Result<()> {
+    tx.execute_batch(
+        "UPDATE photos SET stack_id = NULL WHERE stack_id IN (
+            SELECT stack_id FROM photos WHERE stack_id IS NOT NULL GROUP BY stack_id HAVING COUNT(*) < 2
+        );
+        DELETE FROM stacks WHERE id NOT IN (SELECT stack_id FROM photos WHERE stack_id IS NOT NULL);",
+    )
+}
+
+fn and_all(conditions: &[String], last: String) -> String {
+    let mut all = conditions.to_vec();
+    all.push(last);
+    format!("({})", all.join(" AND "))
+}
+
+fn compile_photo_query(conn: &Connection, query: &PhotoQuery) -> Result<CompiledQuery> {
+    let mut filter = SqlFilter::default();
+    // Structured search; sorting by `relevance` ranks the free-text matches
+    let fts_query = match query.search {
+        Some(search) => push_search_conditions(conn, &mut filter, search)?,
+        None => None,
+    };
+
+    let order_col = match query.sort_by {
+        "size" => "p.size",
+        "date" => "p.modified",
+        "date_taken" => "e.date_taken",
+        "camera" => "e.camera_model",
+        "iso" => "e.iso",
+        "rating" => "p.rating",
+        "label" => "p.color_label",
+        "flag" => "p.flag",
+        "position" if query.album.is_some() => "ap.position",
+        "similarity" if query.similarity.is_some() => "sim.distance",
+        // Column weights: name, tags, metadata, folder
+        "relevance" if fts_query.is_some() => "bm25(photos_fts, 10.0, 5.0, 2.0, 1.0)",
+        _ => "p.path",
+    };
+    let desc = query.sort_order == "desc";
+
+    // Companions of a RAW+JPEG pair are represented by their primary
+    filter.push("p.removed_at IS NULL AND p.primary_id IS NULL".to_string());
+    match query.album {
+        Some(album_id) => {
+            let ph = filter.bind(album_id);
+            filter.push(format!("ap.album_id = {}", ph));
+        }
+        None => FolderScope::new(query.folder, query.recursive).apply("p.folder", &mut filter),
+    }
+    let similar_join = match query.similarity {
+        Some(similarity) => Some(similarity_join_sql(conn, &mut filter, similarity)?),
+        None => None,
+    };
+
+    if let Some(fts) = &fts_query {
+        let ph = filter.bind(fts.clone());
+        filter.push(format!("photos_fts MATCH {}", ph));
+    }
+
+    if let Some(tags) = query.tags {
+        push_tag_conditions(&mut filter, tags);
+    }
+
+    if let Some(exif) = query.exif {
+        push_exif_conditions(&mut filter, exif);
+    }
+
+    if let Some(culling) = query.culling {
+        push_culling_conditions(&mut filter, culling);
+    }
+
+    if query.collapse_stacks {
+        filter.push(format!("(p.stack_id IS NULL OR p.id = {})", STACK_COVER_SQL));
+    }
+
+    let mut from = "FROM photos p".to_string();
+    if query.album.is_some() {
+        from.push_str(" JOIN album_photos ap ON ap.photo_id = p.id");
+    }
+    if let Some(join) = &similar_join {
+        from.push_str(join);
+    }
+    if fts_query.is_some() {
+        from.push_str(" JOIN photos_fts ON photos_fts.rowid = p.id");
+    }
+    from.push_str(" LEFT JOIN exif e ON e.photo_id = p.id");
+    let order_keys = vec![(order_col.to_string(), desc), ("p.id".to_string(), desc)];
+
+    Ok(CompiledQuery { from, filter, order_keys })
+}
+
+/// Album columns read by `album_row`; append a WHERE / ORDER BY clause.
+const ALBUM_SELECT: &str = "SELECT a.id, a.name, a.created,
+        (SELECT COUNT(*) FROM album_photos ap JOIN photos p ON p.id = ap.photo_id
+          WHERE ap.album_id = a.id AND p.removed_at IS NULL),
+        (SELECT p.path FROM album_photos ap JOIN photos p ON p.id = ap.photo_id
+          WHERE ap.album_id = a.id AND p.removed_at IS NULL ORDER BY ap.position LIMIT 1)
+     FROM albums a";
+
+const SAVED_QUERY_SELECT: &str = "SELECT id, name, query, pinned, cached_count, created FROM saved_queries";
+
+fn saved_query_row(row: &rusqlite::Row) -> Result<SavedQuery> {
+    let json: String = row.get(2)?;
+    let query = serde_json::from_str(&json)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+    Ok(SavedQuery {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        query,
+        pinned: row.get(3)?,
+        cached_count: row.get::<_, Option<i64>>(4)?.map(|n| n as usize),
+        created: row.get(5)?,
+    })
+}
+
+fn query_json(query: &PhotoQueryParams) -> Result<String> {
+    serde_json::to_string(query).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn album_row(row: &rusqlite::Row) -> Result<Album> {
+    Ok(Album {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created: row.get(2)?,
+        photo_count: row.get::<_, i64>(3)? as usize,
+        cover_path: row.get(4)?,
+    })
+}
+
+/// JSON array of ids, for `IN (SELECT value FROM json_each(?))` without a placeholder per id.
+fn id_list_json(ids: &[i64]) -> String {
+    serde_json::Value::from(ids.to_vec()).to_string()
+}
+
+/// JSON array of strings (paths, tag names), the `id_list_json` counterpart for text lookups.
+fn text_list_json(values: &[String]) -> String {
+    serde_json::Value::from(values.to_vec()).to_string()
+}
+
+fn sql_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Integer(i) => i.into(),
+        Value::Real(f) => serde_json::Number::from_f64(f).map_or(serde_json::Value::Null, Into::into),
+        Value::Text(s) => s.into(),
+        Value::Null | Value::Blob(_) => serde_json::Value::Null,
+    }
+}
+
+fn json_to_sql(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => n.as_f64().map_or(Value::Null, Value::Real),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        _ => Value::Null,
+    }
+}
+
+fn vec_table_exists(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='vec_photos'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|c| c > 0)
+    .unwrap_or(false)
+}
+
+/// Add include-all / include-any / exclude tag conditions to `filter`.
+fn push_tag_conditions(filter: &mut SqlFilter, tags: &TagFilter) {
+    if !tags.include_all.is_empty() {
+        // Deduplicated the way NOCASE compares, so the count matches COUNT(DISTINCT ...)
+        let mut wanted = tags.include_all.clone();
+        wanted.sort_by_key(|tag| tag.to_ascii_lowercase());
+        wanted.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        let list = filter.bind_list(&wanted);
+        let count = filter.bind(wanted.len() as i64);
+        filter.push(format!(
+            "p.id IN (SELECT t.photo_id FROM tags t WHERE t.tag COLLATE NOCASE IN ({}) \
+             GROUP BY t.photo_id HAVING COUNT(DISTINCT t.tag COLLATE NOCASE) = {})",
+            list, count
+        ));
+    }
+    if !tags.include_any.is_empty() {
+        let list = filter.bind_list(&tags.include_any);
+        filter.push(format!(
+            "EXISTS (SELECT 1 FROM tags t WHERE t.photo_id = p.id AND t.tag COLLATE NOCASE IN ({}))",
+            list
+        ));
+    }
+    if !tags.exclude.is_empty() {
+        let list = filter.bind_list(&tags.exclude);
+        filter.push(format!(
+            "NOT EXISTS (SELECT 1 FROM tags t WHERE t.photo_id = p.id AND t.tag COLLATE NOCASE IN ({}))",
+            list
+        ));
+    }
+}
+
+/// Add the conditions of a culling filter (rating range, color labels and flags, all columns
+/// of `photos p`) to `filter`. An empty label or flag list doesn't filter.
+fn push_culling_conditions(filter: &mut SqlFilter, culling: &CullingFilter) {
+    if let Some(min) = culling.rating_min {
+        let ph = filter.bind(min);
+        filter.push(format!("p.rating >= {}", ph));
+    }
+    if let Some(max) = culling.rating_max {
+        let ph = filter.bind(max);
+        filter.push(format!("p.rating <= {}", ph));
+    }
+    if !culling.color_labels.is_empty() {
+        let labels: Vec<String> = culling.color_labels.iter().map(|l| l.as_str().to_string()).collect();
+        let ph = filter.bind(text_list_json(&labels));
+        filter.push(format!("p.color_label IN (SELECT value FROM json_each({}))", ph));
+    }
+    if !culling.flags.is_empty() {
+        let flags: Vec<i64> = culling.flags.iter().map(|f| f.to_db()).collect();
+        let ph = filter.bind(id_list_json(&flags));
+        filter.push(format!("p.flag IN (SELECT value FROM json_each({}))", ph));
+    }
+}
+
+/// Add the conditions of an EXIF filter (against the `exif e` join) to `filter`.
+fn push_exif_conditions(filter: &mut SqlFilter, exif: &ExifFilter) {
+    if let Some(camera) = exif.camera.as_deref().filter(|c| !c.is_empty()) {
+        let ph = filter.bind(camera.to_lowercase());
+        filter.push(format!(
+            "(instr(lower(e.camera_make), {ph}) > 0 OR instr(lower(e.camera_model), {ph}) > 0)",
+            ph = ph
+        ));
+    }
+    if let Some(lens) = exif.lens.as_deref().filter(|l| !l.is_empty()) {
+        let ph = filter.bind(lens.to_lowercase());
+        filter.push(format!("instr(lower(e.lens_model), {}) > 0", ph));
+    }
+
+    let mut range = |col: &str, min: Option<f64>, max: Option<f64>| {
+        if let Some(min) = min {
+            let ph = filter.bind(min);
+            filter.push(format!("{} >= {}", col, ph));
+        }
+        if let Some(max) = max {
+            let ph = filter.bind(max);
+            filter.push(format!("{} <= {}", col, ph));
+        }
+    };
+    range("e.iso", exif.iso_min.map(f64::from), exif.iso_max.map(f64::from));
+    range("e.f_number", exif.f_number_min, exif.f_number_max);
+    range("e.focal_length", exif.focal_length_min, exif.focal_length_max);
+
+    // Dates are stored as `YYYY-MM-DD HH:MM:SS`, so a partial date works as a prefix:
+    // `date_to = 2023-08` still includes everything taken in August.
+    if let Some(from) = exif.date_from.as_deref().filter(|d| !d.is_empty()) {
+        let ph = filter.bind(from.to_string());
+        filter.push(format!("e.date_taken >= {}", ph));
+    }
+    if let Some(to) = exif.date_to.as_deref().filter(|d| !d.is_empty()) {
+        let ph = filter.bind(to.to_string());
+        filter.push(format!("substr(e.date_taken, 1, length({ph})) <= {ph}", ph = ph));
+    }
+
+    match exif.has_gps {
+        Some(true) => filter.push("e.gps_latitude IS NOT NULL".to_string()),
+        Some(false) => filter.push("e.gps_latitude IS NULL".to_string()),
+        None => {}
+    }
+}
+
 impl Database {
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+    pub fn new<P: AsRef<Path>>(path: P) -> std::result::Result<Self, AppError> {
+        let path = path.as_ref();
+
         // Register sqlite-vec as an auto-extension before opening any connection
         unsafe {
             rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute(
@@ -18,59 +730,18 @@ impl Database {
             )));
         }
 
-        let conn = Connection::open(path)?;
+        let mut conn = Connection::open(path).map_err(|e| AppError {
+            message: format!("Failed to open database {}: {}", path.display(), e),
+            ..Default::default()
+        })?;
 
         // Enable WAL mode for better concurrency and performance
-        conn.execute_batch("PRAGMA journal_mode = WAL;")?;
-        conn.execute_batch("PRAGMA synchronous = NORMAL;")?;
-
-        // Create tables
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS photos (
-                id INTEGER PRIMARY KEY,
-                path TEXT NOT NULL UNIQUE,
-                size INTEGER,
-                modified INTEGER,
-                width INTEGER,
-                height INTEGER
-            )",
-            [],
-        )?;
-
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS tags (
-                id INTEGER PRIMARY KEY,
-                photo_id INTEGER NOT NULL,
-                tag TEXT NOT NULL,
-                confidence REAL,
-                FOREIGN KEY(photo_id) REFERENCES photos(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
-
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS thumbnails (
-                photo_id INTEGER PRIMARY KEY,
-                data BLOB NOT NULL,
-                FOREIGN KEY(photo_id) REFERENCES photos(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
-
-        // Index for faster path lookups
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_photos_path ON photos(path)",
-            [],
-        )?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
 
-        // Vec metadata table to track current model type and embedding dimension
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS vec_meta (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )",
-            [],
-        )?;
+        // Create or upgrade the schema to the version this build expects
+        migrations::run(&mut conn, path)?;
+        // Migrations run without foreign key enforcement, as SQLite advises for schema changes
+        migrations::enable_foreign_keys(&conn)?;
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
@@ -140,9 +811,9 @@ impl Database {
         Ok(())
     }
 
-    pub fn get_photos_to_index(&self, folder: &str) -> Result<Vec<(i64, String)>> {
+    pub fn get_photos_to_index(&self, folder: &str, recursive: bool) -> Result<Vec<(i64, String)>> {
         let conn = self.conn.lock().unwrap();
-        let folder_pattern = format!("{}%", folder);
+        let scope = FolderScope::new(folder, recursive);
 
         // Check if vec_photos exists
         let vec_table_exists: bool = conn
@@ -154,14 +825,19 @@ impl Database {
             .map(|c| c > 0)
             .unwrap_or(false);
 
+        let scope_sql = scope.sql("folder", 1);
         let sql = if vec_table_exists {
-            "SELECT id, path FROM photos WHERE path LIKE ?1 AND id NOT IN (SELECT photo_id FROM vec_photos)"
+            format!(
+                "SELECT id, path FROM photos
+                 WHERE {} AND removed_at IS NULL AND id NOT IN (SELECT photo_id FROM vec_photos)",
+                scope_sql
+            )
         } else {
-            "SELECT id, path FROM photos WHERE path LIKE ?1"
+            format!("SELECT id, path FROM photos WHERE {} AND removed_at IS NULL", scope_sql)
         };
 
-        let mut stmt = conn.prepare(sql)?;
-        let rows = stmt.query_map([folder_pattern], |row| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(scope.params()), |row| {
             Ok((row.get(0)?, row.get(1)?))
         })?;
 
@@ -189,11 +865,12 @@ impl Database {
         &self,
         photo_id: i64,
         folder: &str,
+        recursive: bool,
         max_distance: f32,
         limit: usize,
-    ) -> Result<Vec<(i64, String, i64, i64, Option<u32>, Option<u32>, f32)>> {
+    ) -> Result<Vec<(PhotoRow, f32)>> {
         let conn = self.conn.lock().unwrap();
-        let folder_pattern = format!("{}%", folder);
+        let scope = FolderScope::new(folder, recursive);
 
         // 1. Fetch reference embedding
         let ref_embedding: Vec<u8> = conn.query_row(
@@ -205,7 +882,8 @@ impl Database {
         // 2. KNN query using vec0's required `k = ?` constraint.
         //    sqlite-vec doesn't support LIMIT — it needs `k` in the WHERE clause.
         //    Additional filters (folder, distance) are applied in an outer query.
-        let sql = "SELECT sub.photo_id, sub.distance, p.path, p.size, p.modified, p.width, p.height
+        let sql = format!(
+            "SELECT {}, sub.distance
              FROM (
                SELECT v.photo_id, v.distance
                FROM vec_photos v
@@ -214,32 +892,31 @@ impl Database {
              ) sub
              JOIN photos p ON p.id = sub.photo_id
              WHERE sub.photo_id != ?3
-               AND p.path LIKE ?4
-               AND sub.distance <= ?5
-             ORDER BY sub.distance";
-
-        let mut stmt = conn.prepare(sql)?;
-        let rows = stmt.query_map(
-            params![ref_embedding, limit as i64, photo_id, folder_pattern, max_distance],
-            |row| {
-                Ok((
-                    row.get::<_, i64>(0)?,    // photo_id
-                    row.get::<_, f32>(1)?,    // distance
-                    row.get::<_, String>(2)?, // path
-                    row.get::<_, i64>(3)?,    // size
-                    row.get::<_, i64>(4)?,    // modified
-                    row.get::<_, Option<u32>>(5)?, // width
-                    row.get::<_, Option<u32>>(6)?, // height
-                ))
-            },
-        )?;
+               AND sub.distance <= ?4
+               AND p.removed_at IS NULL
+               AND {}
+             ORDER BY sub.distance",
+            PHOTO_COLUMNS,
+            scope.sql("p.folder", 5)
+        );
 
-        let mut results = Vec::new();
-        for row in rows {
-            let r = row?;
-            results.push((r.0, r.2, r.3, r.4, r.5, r.6, r.1));
+        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = vec![
+            Box::new(ref_embedding),
+            Box::new(limit as i64),
+            Box::new(photo_id),
+            Box::new(max_distance),
+        ];
+        for value in scope.params() {
+            param_values.push(Box::new(value));
         }
-        Ok(results)
+        let param_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|p| p.as_ref()).collect();
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(param_refs.as_slice(), |row| {
+            Ok((photo_row(row)?, row.get::<_, f32>(PHOTO_COLUMN_COUNT)?))
+        })?;
+        rows.collect()
     }
 
     /// Get the photo_id for a given path.
@@ -255,77 +932,177 @@ impl Database {
         }
     }
 
-    /// Batch-fetch cached thumbnails by file paths in a single query.
-    /// Returns a map of path → JPEG bytes for all paths that have cached thumbnails.
-    pub fn get_cached_thumbnails_by_paths(
-        &self,
-        paths: &[String],
-    ) -> Result<std::collections::HashMap<String, Vec<u8>>> {
+    /// Path, size and modified time of a photo that hasn't been removed.
+    pub fn get_photo_file(&self, photo_id: i64) -> Result<Option<(String, u64, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT path, size, modified FROM photos WHERE id = ?1 AND removed_at IS NULL",
+            params![photo_id],
+            |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64, row.get(2)?)),
+        )
+        .optional()
+    }
+
+    /// Hash of the cached thumbnail file of a photo, marking the file as used for LRU eviction.
+    pub fn use_thumbnail(&self, photo_id: i64) -> Result<Option<String>> {
         let conn = self.conn.lock().unwrap();
-        if paths.is_empty() {
-            return Ok(std::collections::HashMap::new());
+        let hash: Option<String> = conn
+            .query_row(
+                "SELECT hash FROM photo_thumbnails WHERE photo_id = ?1",
+                params![photo_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(hash) = &hash {
+            // Hour granularity is plenty for LRU and saves a write per thumbnail shown
+            conn.execute(
+                "UPDATE thumbnail_cache SET accessed = ?2 WHERE hash = ?1 AND accessed < ?2 - 3600",
+                params![hash, unix_now()],
+            )?;
         }
+        Ok(hash)
+    }
 
-        let placeholders: String = (1..=paths.len())
-            .map(|i| format!("?{}", i))
-            .collect::<Vec<_>>()
-            .join(", ");
+    /// Record that a photo's thumbnail is the cached file `hash` of `size` bytes.
+    /// Returns whether the file is new to the cache rather than shared with another photo.
+    pub fn add_thumbnail(&self, photo_id: i64, hash: &str, size: u64) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = unix_now();
+        let added = tx.execute(
+            "INSERT OR IGNORE INTO thumbnail_cache (hash, size, accessed) VALUES (?1, ?2, ?3)",
+            params![hash, size as i64, now],
+        )? > 0;
+        if !added {
+            tx.execute("UPDATE thumbnail_cache SET accessed = ?2 WHERE hash = ?1", params![hash, now])?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO photo_thumbnails (photo_id, hash) VALUES (?1, ?2)",
+            params![photo_id, hash],
+        )?;
+        tx.commit()?;
+        Ok(added)
+    }
 
-        let sql = format!(
-            "SELECT p.path, t.data FROM photos p \
-             JOIN thumbnails t ON t.photo_id = p.id \
-             WHERE p.path IN ({})",
-            placeholders
-        );
+    /// Forget a photo's thumbnail, e.g. when its cached file has gone missing.
+    pub fn remove_photo_thumbnail(&self, photo_id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM photo_thumbnails WHERE photo_id = ?1", params![photo_id])?;
+        Ok(())
+    }
 
-        let mut stmt = conn.prepare(&sql)?;
-        let param_refs: Vec<&dyn rusqlite::types::ToSql> =
-            paths.iter().map(|p| p as &dyn rusqlite::types::ToSql).collect();
+    /// Number of cached thumbnail files and their total size in bytes.
+    pub fn thumbnail_cache_size(&self) -> Result<(u64, u64)> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM thumbnail_cache",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+        )
+    }
 
-        let rows = stmt.query_map(param_refs.as_slice(), |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+    /// Drop the least recently used cache entries, and the photos' references to them, until
+    /// at most `max_bytes` remain. Returns the hashes and sizes of the files to delete.
+    pub fn evict_thumbnails(&self, max_bytes: u64) -> Result<Vec<(String, u64)>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut total: u64 = tx.query_row("SELECT COALESCE(SUM(size), 0) FROM thumbnail_cache", [], |row| {
+            row.get::<_, i64>(0).map(|v| v as u64)
         })?;
-
-        let mut map = std::collections::HashMap::new();
-        for row in rows {
-            let (path, data) = row?;
-            map.insert(path, data);
+        let mut evicted = Vec::new();
+        {
+            // Ties (same second) go oldest insert first, so a file just added isn't evicted
+            let mut stmt = tx.prepare("SELECT hash, size FROM thumbnail_cache ORDER BY accessed, rowid")?;
+            let mut rows = stmt.query([])?;
+            while total > max_bytes {
+                let Some(row) = rows.next()? else { break };
+                let size = row.get::<_, i64>(1)? as u64;
+                evicted.push((row.get::<_, String>(0)?, size));
+                total = total.saturating_sub(size);
+            }
         }
-        Ok(map)
+        let hashes: Vec<String> = evicted.iter().map(|(hash, _)| hash.clone()).collect();
+        let hashes = text_list_json(&hashes);
+        tx.execute(
+            "DELETE FROM photo_thumbnails WHERE hash IN (SELECT value FROM json_each(?1))",
+            params![hashes],
+        )?;
+        tx.execute(
+            "DELETE FROM thumbnail_cache WHERE hash IN (SELECT value FROM json_each(?1))",
+            params![hashes],
+        )?;
+        tx.commit()?;
+        Ok(evicted)
     }
 
-    /// Fetch cached thumbnail JPEG bytes for a photo.
-    pub fn get_thumbnail(&self, photo_id: i64) -> Result<Option<Vec<u8>>> {
+    /// Empty the thumbnail cache index; the caller deletes the files.
+    pub fn clear_thumbnail_cache(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT data FROM thumbnails WHERE photo_id = ?1")?;
-        let mut rows = stmt.query(params![photo_id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(row.get(0)?))
-        } else {
-            Ok(None)
-        }
+        conn.execute_batch("DELETE FROM photo_thumbnails; DELETE FROM thumbnail_cache;")
+    }
+
+    /// Whether thumbnails stored as BLOBs by older versions still wait to be moved to disk.
+    pub fn has_legacy_thumbnails(&self) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'legacy_thumbnails'")?;
+        stmt.exists([])
+    }
+
+    /// Up to `limit` thumbnails stored as BLOBs by older versions.
+    pub fn get_legacy_thumbnails(&self, limit: usize) -> Result<Vec<(i64, Vec<u8>)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT photo_id, data FROM legacy_thumbnails LIMIT ?1")?;
+        let rows = stmt.query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// The BLOB thumbnail of one photo, if an older version stored one.
+    pub fn get_legacy_thumbnail(&self, photo_id: i64) -> Result<Option<Vec<u8>>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT data FROM legacy_thumbnails WHERE photo_id = ?1",
+            params![photo_id],
+            |row| row.get(0),
+        )
+        .optional()
     }
 
-    /// Store (or replace) cached thumbnail JPEG bytes for a photo.
-    pub fn save_thumbnail(&self, photo_id: i64, data: &[u8]) -> Result<()> {
+    pub fn delete_legacy_thumbnails(&self, photo_ids: &[i64]) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO thumbnails (photo_id, data) VALUES (?1, ?2)",
-            params![photo_id, data],
+            "DELETE FROM legacy_thumbnails WHERE photo_id IN (SELECT value FROM json_each(?1))",
+            params![id_list_json(photo_ids)],
         )?;
         Ok(())
     }
 
-    /// Delete cached thumbnail for a photo (e.g. when the source file changes).
-    #[allow(dead_code)]
-    pub fn delete_thumbnail(&self, photo_id: i64) -> Result<()> {
+    /// Drop the emptied BLOB table and give its pages back to the filesystem.
+    pub fn drop_legacy_thumbnails(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM thumbnails WHERE photo_id = ?1", params![photo_id])?;
-        Ok(())
+        conn.execute_batch(
+            "DROP TRIGGER IF EXISTS legacy_thumbnails_stale;
+            DROP TABLE IF EXISTS legacy_thumbnails;
+            VACUUM;",
+        )
     }
 
-    /// Returns (id, changed) where changed=true means new insert or modified-time update.
-    pub fn upsert_photo(
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Returns (id, changed) where changed=true means new insert or modified-time update.
+    pub fn upsert_photo(
         &self,
         path: &str,
         size: u64,
@@ -340,10 +1117,14 @@ impl Database {
         if let Some(row) = rows.next()? {
             let id: i64 = row.get(0)?;
             let db_modified: i64 = row.get(1)?;
+            // The file is on disk again
+            conn.execute("UPDATE photos SET removed_at = NULL WHERE id = ?1", params![id])?;
 
             if db_modified != modified {
                 conn.execute(
-                    "UPDATE photos SET size = ?1, modified = ?2, width = ?3, height = ?4 WHERE id = ?5",
+                    "UPDATE photos SET size = ?1, modified = ?2, width = ?3, height = ?4, content_hash = NULL,
+                        perceptual_hash = NULL
+                     WHERE id = ?5",
                     params![size as i64, modified, width, height, id],
                 )?;
                 Ok((id, true))
@@ -352,52 +1133,102 @@ impl Database {
             }
         } else {
             conn.execute(
-                "INSERT INTO photos (path, size, modified, width, height) VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![path, size as i64, modified, width, height],
+                "INSERT INTO photos (path, folder, size, modified, width, height) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![path, parent_folder(path), size as i64, modified, width, height],
             )?;
             Ok((conn.last_insert_rowid(), true))
         }
     }
 
     /// Batch upsert photos in a single transaction for much better performance.
-    /// Returns Vec<(id, changed)> in the same order as input.
+    /// Input is (path, size, modified, width, height, fingerprint). A new path whose
+    /// fingerprint matches a removed photo, or a photo whose file is no longer on disk (moved
+    /// to a folder scanned before its old one), takes over that photo's row, keeping its tags,
+    /// thumbnail and embedding. Returns Vec<(id, changed)> in the same order as input.
     pub fn batch_upsert_photos(
         &self,
-        photos: &[(String, u64, i64, Option<u32>, Option<u32>)],
+        photos: &[(String, u64, i64, Option<u32>, Option<u32>, Option<String>)],
     ) -> Result<Vec<(i64, bool)>> {
         let mut conn = self.conn.lock().unwrap();
         let tx = conn.transaction()?;
         let mut results = Vec::with_capacity(photos.len());
 
         {
-            let mut select_stmt = tx.prepare("SELECT id, modified FROM photos WHERE path = ?1")?;
+            let mut select_stmt =
+                tx.prepare("SELECT id, modified, removed_at IS NOT NULL FROM photos WHERE path = ?1")?;
             let mut update_stmt = tx.prepare(
-                "UPDATE photos SET size = ?1, modified = ?2, width = ?3, height = ?4 WHERE id = ?5",
+                "UPDATE photos SET size = ?1, modified = ?2, width = ?3, height = ?4, fingerprint = ?5,
+                    content_hash = NULL, perceptual_hash = NULL, removed_at = NULL
+                 WHERE id = ?6",
+            )?;
+            let mut restore_stmt = tx.prepare("UPDATE photos SET removed_at = NULL WHERE id = ?1")?;
+            // Removed photos first, most recent first; live ones only qualify once their file is gone
+            let mut moved_stmt = tx.prepare(
+                "SELECT id, path, removed_at IS NOT NULL FROM photos
+                 WHERE fingerprint = ?1 AND size = ?2
+                 ORDER BY removed_at IS NULL, removed_at DESC",
+            )?;
+            let mut relink_stmt = tx.prepare(
+                "UPDATE photos SET path = ?1, folder = ?2, modified = ?3, width = ?4, height = ?5,
+                    removed_at = NULL
+                 WHERE id = ?6",
             )?;
             let mut insert_stmt = tx.prepare(
-                "INSERT INTO photos (path, size, modified, width, height) VALUES (?1, ?2, ?3, ?4, ?5)",
+                "INSERT INTO photos (path, folder, size, modified, width, height, fingerprint)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             )?;
             let mut del_thumb_stmt =
-                tx.prepare("DELETE FROM thumbnails WHERE photo_id = ?1")?;
+                tx.prepare("DELETE FROM photo_thumbnails WHERE photo_id = ?1")?;
 
-            for (path, size, modified, width, height) in photos {
+            for (path, size, modified, width, height, fingerprint) in photos {
                 let mut rows = select_stmt.query([path])?;
 
                 if let Some(row) = rows.next()? {
                     let id: i64 = row.get(0)?;
                     let db_modified: i64 = row.get(1)?;
+                    let removed: bool = row.get(2)?;
                     drop(rows);
 
                     if db_modified != *modified {
-                        update_stmt.execute(params![*size as i64, *modified, *width, *height, id])?;
+                        update_stmt.execute(params![*size as i64, *modified, *width, *height, fingerprint, id])?;
                         del_thumb_stmt.execute(params![id])?;
                         results.push((id, true));
                     } else {
+                        if removed {
+                            restore_stmt.execute(params![id])?;
+                        }
                         results.push((id, false));
                     }
+                    continue;
+                }
+                drop(rows);
+
+                // Same content as a removed or vanished photo: the file was renamed or moved
+                let mut moved_id: Option<i64> = None;
+                if let Some(fingerprint) = fingerprint {
+                    let mut rows = moved_stmt.query(params![fingerprint, *size as i64])?;
+                    while let Some(row) = rows.next()? {
+                        let candidate_path: String = row.get(1)?;
+                        let removed: bool = row.get(2)?;
+                        if removed || !Path::new(&candidate_path).exists() {
+                            moved_id = Some(row.get(0)?);
+                            break;
+                        }
+                    }
+                }
+                if let Some(id) = moved_id {
+                    relink_stmt.execute(params![path, parent_folder(path), *modified, *width, *height, id])?;
+                    results.push((id, false));
                 } else {
-                    drop(rows);
-                    insert_stmt.execute(params![path, *size as i64, *modified, *width, *height])?;
+                    insert_stmt.execute(params![
+                        path,
+                        parent_folder(path),
+                        *size as i64,
+                        *modified,
+                        *width,
+                        *height,
+                        fingerprint
+                    ])?;
                     results.push((tx.last_insert_rowid(), true));
                 }
             }
@@ -407,17 +1238,23 @@ impl Database {
         Ok(results)
     }
 
-    pub fn add_tags(&self, photo_id: i64, tags: &[String]) -> Result<()> {
+    /// Replace the tags `source` (a model name) predicted for a photo. Tags from other
+    /// models and manual tags are kept; legacy tags of unknown origin are replaced too.
+    pub fn replace_model_tags(&self, photo_id: i64, source: &str, tags: &[(String, f32)]) -> Result<()> {
         let mut conn = self.conn.lock().unwrap();
         let tx = conn.transaction()?;
 
-        // Clear existing tags for this photo to avoid duplicates/stale tags
-        tx.execute("DELETE FROM tags WHERE photo_id = ?1", params![photo_id])?;
+        tx.execute(
+            "DELETE FROM tags WHERE photo_id = ?1 AND source IN (?2, ?3)",
+            params![photo_id, source, LEGACY_TAG_SOURCE],
+        )?;
 
         {
-            let mut stmt = tx.prepare("INSERT INTO tags (photo_id, tag) VALUES (?1, ?2)")?;
-            for tag in tags {
-                stmt.execute(params![photo_id, tag])?;
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO tags (photo_id, tag, source, confidence) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (tag, confidence) in tags {
+                stmt.execute(params![photo_id, tag, source, confidence])?;
             }
         }
 
@@ -425,154 +1262,869 @@ impl Database {
         Ok(())
     }
 
-    pub fn get_tags(&self, photo_id: i64) -> Result<Vec<String>> {
+    /// Add a manual tag to every photo in `paths`. Returns how many photos gained it.
+    pub fn add_manual_tag(&self, paths: &[String], tag: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT tag FROM tags WHERE photo_id = ?1")?;
-        let rows = stmt.query_map([photo_id], |row| row.get(0))?;
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (photo_id, tag, source)
+             SELECT id, ?2, ?3 FROM photos WHERE path IN (SELECT value FROM json_each(?1))",
+            params![text_list_json(paths), tag, MANUAL_TAG_SOURCE],
+        )
+    }
 
-        let mut tags = Vec::new();
-        for tag in rows {
-            tags.push(tag?);
-        }
-        Ok(tags)
+    /// Remove a manual tag from every photo in `paths`. Model tags with the same name stay.
+    pub fn remove_manual_tag(&self, paths: &[String], tag: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM tags
+             WHERE tag = ?2 AND source = ?3
+               AND photo_id IN (SELECT id FROM photos WHERE path IN (SELECT value FROM json_each(?1)))",
+            params![text_list_json(paths), tag, MANUAL_TAG_SOURCE],
+        )
     }
 
-    pub fn get_tags_for_folder(&self, folder: &str) -> Result<Vec<String>> {
+    /// Every tag row of a photo with its source, manual tags first, then by confidence.
+    pub fn get_photo_tags(&self, photo_id: i64) -> Result<Vec<PhotoTag>> {
         let conn = self.conn.lock().unwrap();
-        // Get all unique tags for photos in this folder
-        let folder_pattern = format!("{}%", folder);
         let mut stmt = conn.prepare(
+            "SELECT tag, source, confidence FROM tags
+             WHERE photo_id = ?1
+             ORDER BY source = ?2 DESC, confidence DESC, tag ASC",
+        )?;
+        let tags = stmt
+            .query_map(params![photo_id, MANUAL_TAG_SOURCE], |row| {
+                Ok(PhotoTag {
+                    tag: row.get(0)?,
+                    source: row.get(1)?,
+                    confidence: row.get::<_, Option<f64>>(2)?.map(|c| c as f32),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(tags)
+    }
+
+    pub fn get_tags_for_folder(&self, folder: &str, recursive: bool) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        // Get all unique tags for photos in this folder (or its subtree)
+        let scope = FolderScope::new(folder, recursive);
+        let mut stmt = conn.prepare(&format!(
             "SELECT DISTINCT t.tag
              FROM tags t
              JOIN photos p ON t.photo_id = p.id
-             WHERE p.path LIKE ?1
+             WHERE {} AND p.removed_at IS NULL
              ORDER BY t.tag ASC",
-        )?;
+            scope.sql("p.folder", 1)
+        ))?;
 
         let tags = stmt
-            .query_map([folder_pattern], |row| row.get(0))?
+            .query_map(rusqlite::params_from_iter(scope.params()), |row| row.get(0))?
             .collect::<Result<Vec<String>, _>>()?;
         Ok(tags)
     }
 
-    pub fn delete_tags_for_folder(&self, folder: &str) -> Result<()> {
+    pub fn delete_tags_for_folder(&self, folder: &str, recursive: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let scope = FolderScope::new(folder, recursive);
+
+        conn.execute(
+            &format!(
+                "DELETE FROM tags
+                 WHERE photo_id IN (
+                     SELECT id FROM photos WHERE {} AND removed_at IS NULL
+                 )",
+                scope.sql("folder", 1)
+            ),
+            rusqlite::params_from_iter(scope.params()),
+        )?;
+        Ok(())
+    }
+
+    /// Every tag in `folder` (or the whole library when `None`) with the number of photos
+    /// carrying it, the average model confidence and how many photos have it as a manual tag.
+    pub fn get_tag_summaries(&self, folder: Option<&str>, recursive: bool) -> Result<Vec<TagSummary>> {
+        let conn = self.conn.lock().unwrap();
+        let mut filter = SqlFilter::default();
+        filter.push("p.removed_at IS NULL".to_string());
+        if let Some(folder) = folder {
+            FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
+        }
+        let manual = filter.bind(MANUAL_TAG_SOURCE);
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.tag,
+                    COUNT(DISTINCT t.photo_id),
+                    AVG(t.confidence),
+                    COUNT(DISTINCT CASE WHEN t.source = {} THEN t.photo_id END)
+             FROM tags t
+             JOIN photos p ON p.id = t.photo_id
+             {}
+             GROUP BY t.tag
+             ORDER BY COUNT(DISTINCT t.photo_id) DESC, t.tag ASC",
+            manual,
+            filter.where_clause()
+        ))?;
+        let tags = stmt
+            .query_map(filter.params().as_slice(), |row| {
+                Ok(TagSummary {
+                    tag: row.get(0)?,
+                    photo_count: row.get::<_, i64>(1)? as usize,
+                    avg_confidence: row.get::<_, Option<f64>>(2)?.map(|c| c as f32),
+                    manual_count: row.get::<_, i64>(3)? as usize,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(tags)
+    }
+
+    /// Rename `old` to `new` across the library. Returns the number of photos affected.
+    pub fn rename_tag(&self, old: &str, new: &str) -> Result<usize> {
+        self.merge_tags(&[old.to_string()], new)
+    }
+
+    /// Replace every tag in `tags` with `into` across the library, keeping provenance.
+    /// Where a photo ends up with `into` twice from the same source, the rows collapse
+    /// into one with the higher confidence. Returns the number of photos affected.
+    pub fn merge_tags(&self, tags: &[String], into: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let sources: Vec<String> = tags.iter().filter(|t| t.as_str() != into).cloned().collect();
+        let affected: i64 = tx.query_row(
+            "SELECT COUNT(DISTINCT photo_id) FROM tags WHERE tag IN (SELECT value FROM json_each(?1))",
+            params![text_list_json(&sources)],
+            |row| row.get(0),
+        )?;
+
+        for tag in &sources {
+            tx.execute(
+                "UPDATE tags AS t
+                 SET confidence = (
+                     SELECT MAX(o.confidence) FROM tags o
+                     WHERE o.photo_id = t.photo_id AND o.source = t.source AND o.tag IN (?1, ?2)
+                 )
+                 WHERE t.tag = ?2 AND EXISTS (
+                     SELECT 1 FROM tags o
+                     WHERE o.photo_id = t.photo_id AND o.source = t.source AND o.tag = ?1
+                 )",
+                params![tag, into],
+            )?;
+            // Rows that would collide with an existing `into` row were folded in above
+            tx.execute("UPDATE OR IGNORE tags SET tag = ?2 WHERE tag = ?1", params![tag, into])?;
+            tx.execute("DELETE FROM tags WHERE tag = ?1", params![tag])?;
+        }
+
+        tx.commit()?;
+        Ok(affected as usize)
+    }
+
+    /// Remove `tag`, whatever its source, from every photo in `folder` (or the whole
+    /// library when `None`). Returns the number of photos affected.
+    pub fn delete_tag(&self, tag: &str, folder: Option<&str>, recursive: bool) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut filter = SqlFilter::default();
+        let tag_param = filter.bind(tag.to_string());
+        filter.push(format!("t.tag = {}", tag_param));
+        if let Some(folder) = folder {
+            FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
+        }
+        let scoped = format!(
+            "SELECT DISTINCT t.photo_id FROM tags t JOIN photos p ON p.id = t.photo_id {}",
+            filter.where_clause()
+        );
+        let photo_ids: Vec<i64> = tx
+            .prepare(&scoped)?
+            .query_map(filter.params().as_slice(), |row| row.get(0))?
+            .collect::<Result<_>>()?;
+
+        tx.execute(
+            "DELETE FROM tags WHERE tag = ?1 AND photo_id IN (SELECT value FROM json_each(?2))",
+            params![tag, id_list_json(&photo_ids)],
+        )?;
+
+        tx.commit()?;
+        Ok(photo_ids.len())
+    }
+
+    /// Set the star rating (0-5) of every photo in `paths`. Returns the number of photos updated.
+    pub fn set_rating(&self, paths: &[String], rating: u8) -> Result<usize> {
+        self.set_photo_column(paths, "rating", &rating)
+    }
+
+    /// Set or clear (`None`) the color label of every photo in `paths`.
+    pub fn set_color_label(&self, paths: &[String], label: Option<ColorLabel>) -> Result<usize> {
+        self.set_photo_column(paths, "color_label", &label.map(ColorLabel::as_str))
+    }
+
+    /// Set the pick/reject flag of every photo in `paths`.
+    pub fn set_flag(&self, paths: &[String], flag: PhotoFlag) -> Result<usize> {
+        self.set_photo_column(paths, "flag", &flag.to_db())
+    }
+
+    /// `UPDATE photos SET <column> = value` for `paths`, as one statement.
+    fn set_photo_column(&self, paths: &[String], column: &str, value: &dyn ToSql) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!(
+                "UPDATE photos SET {} = ?1 WHERE path IN (SELECT value FROM json_each(?2))",
+                column
+            ),
+            params![value, text_list_json(paths)],
+        )
+    }
+
+    /// All albums by name, with photo count and cover.
+    pub fn list_albums(&self) -> Result<Vec<Album>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("{} ORDER BY a.name", ALBUM_SELECT))?;
+        let albums = stmt.query_map([], album_row)?.collect::<Result<Vec<_>>>()?;
+        Ok(albums)
+    }
+
+    pub fn get_album(&self, album_id: i64) -> Result<Album> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(&format!("{} WHERE a.id = ?1", ALBUM_SELECT), params![album_id], album_row)
+    }
+
+    /// Create an empty album. Names are unique, ignoring case.
+    pub fn create_album(&self, name: &str) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO albums (name, created) VALUES (?1, ?2)",
+            params![name, unix_now()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn rename_album(&self, album_id: i64, name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        match conn.execute("UPDATE albums SET name = ?1 WHERE id = ?2", params![name, album_id])? {
+            0 => Err(rusqlite::Error::QueryReturnedNoRows),
+            _ => Ok(()),
+        }
+    }
+
+    /// Delete an album. The photos themselves are not touched.
+    pub fn delete_album(&self, album_id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM albums WHERE id = ?1", params![album_id])?;
+        Ok(())
+    }
+
+    /// Append the photos at `paths` to the end of an album, in the given order. Photos
+    /// already in the album keep their place. Returns the number of photos added.
+    pub fn add_to_album(&self, album_id: i64, paths: &[String]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut position: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM album_photos WHERE album_id = ?1",
+            params![album_id],
+            |row| row.get(0),
+        )?;
+        let mut added = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO album_photos (album_id, photo_id, position)
+                 SELECT ?1, id, ?3 FROM photos WHERE path = ?2 AND removed_at IS NULL",
+            )?;
+            for path in paths {
+                if stmt.execute(params![album_id, path, position])? > 0 {
+                    position += 1;
+                    added += 1;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(added)
+    }
+
+    /// Remove the photos at `paths` from an album. Returns the number removed.
+    pub fn remove_from_album(&self, album_id: i64, paths: &[String]) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM album_photos
+             WHERE album_id = ?1
+               AND photo_id IN (SELECT id FROM photos WHERE path IN (SELECT value FROM json_each(?2)))",
+            params![album_id, text_list_json(paths)],
+        )
+    }
+
+    /// Put the album members at `paths` first, in that order, followed by the remaining
+    /// members in their current order. Passing every member sets the complete order.
+    pub fn reorder_album(&self, album_id: i64, paths: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let members: Vec<(i64, String)> = tx
+            .prepare(
+                "SELECT p.id, p.path FROM album_photos ap
+                 JOIN photos p ON p.id = ap.photo_id
+                 WHERE ap.album_id = ?1
+                 ORDER BY ap.position",
+            )?
+            .query_map(params![album_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+
+        let ids_by_path: std::collections::HashMap<&str, i64> =
+            members.iter().map(|(id, path)| (path.as_str(), *id)).collect();
+        let mut order: Vec<i64> = Vec::with_capacity(members.len());
+        let mut placed = std::collections::HashSet::new();
+        for path in paths {
+            if let Some(&id) = ids_by_path.get(path.as_str()) {
+                if placed.insert(id) {
+                    order.push(id);
+                }
+            }
+        }
+        order.extend(members.iter().map(|(id, _)| *id).filter(|id| !placed.contains(id)));
+
+        {
+            let mut stmt =
+                tx.prepare("UPDATE album_photos SET position = ?1 WHERE album_id = ?2 AND photo_id = ?3")?;
+            for (position, id) in order.iter().enumerate() {
+                stmt.execute(params![position as i64, album_id, id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// All saved queries by name.
+    pub fn list_saved_queries(&self) -> Result<Vec<SavedQuery>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("{} ORDER BY name", SAVED_QUERY_SELECT))?;
+        let queries = stmt.query_map([], saved_query_row)?.collect::<Result<Vec<_>>>()?;
+        Ok(queries)
+    }
+
+    pub fn get_saved_query(&self, id: i64) -> Result<SavedQuery> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(&format!("{} WHERE id = ?1", SAVED_QUERY_SELECT), params![id], saved_query_row)
+    }
+
+    /// Store a query under `name`. Names are unique, ignoring case.
+    pub fn create_saved_query(&self, name: &str, query: &PhotoQueryParams, pinned: bool) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
-        let folder_pattern = format!("{}%", folder);
-        
         conn.execute(
-            "DELETE FROM tags 
-             WHERE photo_id IN (
-                 SELECT id FROM photos WHERE path LIKE ?1
-             )",
-            params![folder_pattern],
+            "INSERT INTO saved_queries (name, query, pinned, created) VALUES (?1, ?2, ?3, ?4)",
+            params![name, query_json(query)?, pinned, unix_now()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn rename_saved_query(&self, id: i64, name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        match conn.execute("UPDATE saved_queries SET name = ?1 WHERE id = ?2", params![name, id])? {
+            0 => Err(rusqlite::Error::QueryReturnedNoRows),
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace the stored query. The cached count is dropped until it runs again.
+    pub fn update_saved_query(&self, id: i64, query: &PhotoQueryParams) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        match conn.execute(
+            "UPDATE saved_queries SET query = ?1, cached_count = NULL WHERE id = ?2",
+            params![query_json(query)?, id],
+        )? {
+            0 => Err(rusqlite::Error::QueryReturnedNoRows),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_saved_query_pinned(&self, id: i64, pinned: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        match conn.execute("UPDATE saved_queries SET pinned = ?1 WHERE id = ?2", params![pinned, id])? {
+            0 => Err(rusqlite::Error::QueryReturnedNoRows),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_saved_query_count(&self, id: i64, count: usize) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE saved_queries SET cached_count = ?1 WHERE id = ?2",
+            params![count as i64, id],
         )?;
         Ok(())
     }
 
-    pub fn query_photos(
+    pub fn delete_saved_query(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM saved_queries WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Dated photos in scope for burst detection, ordered by folder, camera and capture time.
+    pub fn get_burst_candidates(&self, folder: &str, recursive: bool) -> Result<Vec<BurstCandidate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut filter = SqlFilter::default();
+        FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
+        filter.push("p.removed_at IS NULL AND p.primary_id IS NULL AND e.date_taken IS NOT NULL".to_string());
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT p.id, p.folder,
+                    TRIM(COALESCE(e.camera_make, '') || ' ' || COALESCE(e.camera_model, '')) AS camera,
+                    CAST(strftime('%s', e.date_taken) AS INTEGER) * 1000 + COALESCE(e.subsec_ms, 0) AS capture_ms,
+                    p.perceptual_hash, p.rating
+             FROM photos p JOIN exif e ON e.photo_id = p.id
+             {}
+             ORDER BY p.folder, camera, capture_ms, p.path",
+            filter.where_clause()
+        ))?;
+        let rows = stmt.query_map(filter.params().as_slice(), |row| {
+            Ok(BurstCandidate {
+                id: row.get(0)?,
+                folder: row.get(1)?,
+                camera: row.get(2)?,
+                capture_ms: row.get(3)?,
+                perceptual_hash: row.get::<_, Option<i64>>(4)?.map(|h| h as u64),
+                rating: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Replace the stacks of photos in scope with `stacks`, each a list of photo ids whose
+    /// first id is the suggested cover. A photo that was the cover of its old stack stays
+    /// the cover, so re-running detection keeps covers chosen by hand. Returns the number
+    /// of stacks created.
+    pub fn replace_stacks(&self, folder: &str, recursive: bool, stacks: &[Vec<i64>]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let old_covers: std::collections::HashSet<i64> = tx
+            .prepare("SELECT cover_id FROM stacks WHERE cover_id IS NOT NULL")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+
+        let mut filter = SqlFilter::default();
+        FolderScope::new(folder, recursive).apply("folder", &mut filter);
+        tx.execute(
+            &format!("UPDATE photos SET stack_id = NULL {}", filter.where_clause()),
+            filter.params().as_slice(),
+        )?;
+
+        let now = unix_now();
+        let mut created = 0;
+        for members in stacks.iter().filter(|members| members.len() > 1) {
+            let cover = members
+                .iter()
+                .find(|id| old_covers.contains(id))
+                .unwrap_or(&members[0]);
+            tx.execute("INSERT INTO stacks (cover_id, created) VALUES (?1, ?2)", params![cover, now])?;
+            let stack_id = tx.last_insert_rowid();
+            tx.execute(
+                "UPDATE photos SET stack_id = ?1 WHERE id IN (SELECT value FROM json_each(?2))",
+                params![stack_id, id_list_json(members)],
+            )?;
+            created += 1;
+        }
+        prune_stacks(&tx)?;
+
+        tx.commit()?;
+        Ok(created)
+    }
+
+    /// Make the photo at `path` the cover of its stack. Returns false if it is not stacked.
+    pub fn set_stack_cover(&self, path: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE stacks SET cover_id = (SELECT id FROM photos WHERE path = ?1)
+             WHERE id = (SELECT stack_id FROM photos WHERE path = ?1)",
+            params![path],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Take the photos at `paths` out of their stacks; stacks left with one photo dissolve.
+    /// Returns the number of photos unstacked.
+    pub fn unstack_photos(&self, paths: &[String]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let unstacked = tx.execute(
+            "UPDATE photos SET stack_id = NULL
+             WHERE stack_id IS NOT NULL AND path IN (SELECT value FROM json_each(?1))",
+            params![text_list_json(paths)],
+        )?;
+        prune_stacks(&tx)?;
+        tx.commit()?;
+        Ok(unstacked)
+    }
+
+    /// Live members of a stack, cover first, then by capture time.
+    pub fn get_stack_photos(&self, stack_id: i64) -> Result<Vec<PhotoRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM photos p LEFT JOIN exif e ON e.photo_id = p.id
+             WHERE p.stack_id = ?1 AND p.removed_at IS NULL
+             ORDER BY p.id = {} DESC, e.date_taken, e.subsec_ms, p.path",
+            PHOTO_COLUMNS, STACK_COVER_SQL
+        ))?;
+        let rows = stmt.query_map(params![stack_id], photo_row)?;
+        rows.collect()
+    }
+
+    /// Replace the RAW+JPEG pairs of photos in scope with `pairs` of (primary path, companion
+    /// paths). Companions leave their stacks, since only the primary is listed.
+    pub fn replace_pairs(&self, folder: &str, recursive: bool, pairs: &[(String, Vec<String>)]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut filter = SqlFilter::default();
+        FolderScope::new(folder, recursive).apply("folder", &mut filter);
+        filter.push("primary_id IS NOT NULL".to_string());
+        tx.execute(
+            &format!("UPDATE photos SET primary_id = NULL {}", filter.where_clause()),
+            filter.params().as_slice(),
+        )?;
+
+        {
+            let mut stmt = tx.prepare(
+                "UPDATE photos SET primary_id = (SELECT id FROM photos WHERE path = ?1), stack_id = NULL
+                 WHERE path IN (SELECT value FROM json_each(?2))",
+            )?;
+            for (primary, companions) in pairs {
+                stmt.execute(params![primary, text_list_json(companions)])?;
+            }
+        }
+        prune_stacks(&tx)?;
+
+        tx.commit()
+    }
+
+    /// Paths of the companions paired with the photos at `paths`.
+    pub fn get_companion_paths(&self, paths: &[String]) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT c.path FROM photos c JOIN photos p ON p.id = c.primary_id
+             WHERE p.path IN (SELECT value FROM json_each(?1))
+             ORDER BY c.path",
+        )?;
+        let rows = stmt.query_map(params![text_list_json(paths)], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Live companion paths of the given photos, keyed by primary photo id.
+    pub fn get_companions_for_photos(&self, photo_ids: &[i64]) -> Result<std::collections::HashMap<i64, Vec<String>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT primary_id, path FROM photos
+             WHERE primary_id IN (SELECT value FROM json_each(?1)) AND removed_at IS NULL
+             ORDER BY primary_id, path",
+        )?;
+        let rows = stmt.query_map(params![id_list_json(photo_ids)], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut map: std::collections::HashMap<i64, Vec<String>> = std::collections::HashMap::new();
+        for row in rows {
+            let (photo_id, path) = row?;
+            map.entry(photo_id).or_default().push(path);
+        }
+        Ok(map)
+    }
+
+    /// Number of photos matching `query`.
+    pub fn count_photos(&self, query: &PhotoQuery) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let compiled = compile_photo_query(&conn, query)?;
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) {} {}", compiled.from, compiled.filter.where_clause()),
+            compiled.filter.params().as_slice(),
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// All photos matching `query`, in sort order.
+    pub fn query_photos(&self, query: &PhotoQuery) -> Result<Vec<PhotoRow>> {
+        let conn = self.conn.lock().unwrap();
+        let compiled = compile_photo_query(&conn, query)?;
+
+        let sql = format!(
+            "SELECT {} {} {} ORDER BY {}",
+            PHOTO_COLUMNS,
+            compiled.from,
+            compiled.filter.where_clause(),
+            compiled.order_by()
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(compiled.filter.params().as_slice(), photo_row)?;
+        rows.collect()
+    }
+
+    /// One page of `query` using keyset pagination: rows strictly after `after` in sort
+    /// order, at most `limit` of them. Returns the rows, the cursor for the next page
+    /// (`None` on the last page) and, for the first page only, the total number of matching
+    /// photos. Later pages skip the count, which scans the whole filtered set.
+    pub fn query_photos_page(
         &self,
-        folder: &str,
-        search: Option<&str>,
-        sort_by: &str,
-        sort_order: &str,
-        filter_tags: Option<&[String]>,
-    ) -> Result<Vec<(i64, String, i64, i64, Option<u32>, Option<u32>)>> {
+        query: &PhotoQuery,
+        after: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<(Vec<PhotoRow>, Option<PageCursor>, Option<usize>)> {
         let conn = self.conn.lock().unwrap();
+        let mut compiled = compile_photo_query(&conn, query)?;
 
-        let order_col = match sort_by {
-            "size" => "p.size",
-            "date" => "p.modified",
-            _ => "p.path",
-        };
-        let order_dir = if sort_order == "desc" { "DESC" } else { "ASC" };
-        let folder_pattern = format!("{}%", folder);
-        let search_pattern: Option<String> = search
-            .filter(|s| !s.is_empty())
-            .map(|s| format!("%{}%", s));
-
-        // Build tag filter clause
-        let tag_filter = if let Some(tags) = filter_tags {
-            if !tags.is_empty() {
-                let placeholders: Vec<String> =
-                    tags.iter().enumerate().map(|(i, _)| format!("?{}", i + 3)).collect();
-                format!(
-                    " AND EXISTS (SELECT 1 FROM tags t WHERE t.photo_id = p.id AND t.tag IN ({}))",
-                    placeholders.join(", ")
-                )
-            } else {
-                String::new()
+        let total = match after {
+            Some(cursor) => {
+                compiled.push_keyset_condition(cursor)?;
+                None
+            }
+            None => {
+                let count: i64 = conn.query_row(
+                    &format!("SELECT COUNT(*) {} {}", compiled.from, compiled.filter.where_clause()),
+                    compiled.filter.params().as_slice(),
+                    |row| row.get(0),
+                )?;
+                Some(count as usize)
             }
-        } else {
-            String::new()
         };
 
+        // The sort key values (all keys but the trailing p.id) are selected too, so the last
+        // row can become the next cursor. One extra row tells whether another page follows.
+        let key_exprs: Vec<&str> = compiled.order_keys[..compiled.order_keys.len() - 1]
+            .iter()
+            .map(|(expr, _)| expr.as_str())
+            .collect();
+        let key_count = key_exprs.len();
         let sql = format!(
-            "SELECT p.id, p.path, p.size, p.modified, p.width, p.height \
-             FROM photos p \
-             WHERE p.path LIKE ?1 \
-               AND (?2 IS NULL \
-                    OR p.path LIKE ?2 \
-                    OR EXISTS (SELECT 1 FROM tags t WHERE t.photo_id = p.id AND t.tag LIKE ?2)){} \
-             ORDER BY {} {}",
-            tag_filter, order_col, order_dir
+            "SELECT {}{} {} {} ORDER BY {} LIMIT {}",
+            PHOTO_COLUMNS,
+            key_exprs.iter().map(|expr| format!(", {}", expr)).collect::<String>(),
+            compiled.from,
+            compiled.filter.where_clause(),
+            compiled.order_by(),
+            limit + 1
         );
-
         let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(compiled.filter.params().as_slice(), |row| {
+            let keys = (0..key_count)
+                .map(|i| row.get::<_, Value>(PHOTO_COLUMN_COUNT + i).map(sql_to_json))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((photo_row(row)?, keys))
+        })?;
+        let mut rows = rows.collect::<Result<Vec<_>>>()?;
+
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|(row, keys)| PageCursor {
+                sort_key: keys.clone(),
+                last_id: row.id,
+            })
+        } else {
+            None
+        };
+        Ok((rows.into_iter().map(|(row, _)| row).collect(), next_cursor, total))
+    }
 
-        // Build params dynamically
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-        param_values.push(Box::new(folder_pattern));
-        param_values.push(Box::new(search_pattern));
-        if let Some(tags) = filter_tags {
-            for tag in tags {
-                param_values.push(Box::new(tag.clone()));
+    /// Distinct tag names of the given photos, keyed by photo id.
+    pub fn get_tags_for_photos(&self, photo_ids: &[i64]) -> Result<std::collections::HashMap<i64, Vec<String>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT photo_id, tag FROM tags
+             WHERE photo_id IN (SELECT value FROM json_each(?1))
+             GROUP BY photo_id, tag
+             ORDER BY photo_id, {}",
+            TAG_ORDER
+        ))?;
+        let rows = stmt.query_map(params![id_list_json(photo_ids), MANUAL_TAG_SOURCE], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut map: std::collections::HashMap<i64, Vec<String>> = std::collections::HashMap::new();
+        for row in rows {
+            let (photo_id, tag) = row?;
+            map.entry(photo_id).or_default().push(tag);
+        }
+        Ok(map)
+    }
+
+    /// Which of the given photos have an embedding.
+    pub fn get_embedded_ids_among(&self, photo_ids: &[i64]) -> Result<std::collections::HashSet<i64>> {
+        let conn = self.conn.lock().unwrap();
+        if !vec_table_exists(&conn) {
+            return Ok(std::collections::HashSet::new());
+        }
+        let mut stmt = conn.prepare(
+            "SELECT photo_id FROM vec_photos WHERE photo_id IN (SELECT value FROM json_each(?1))",
+        )?;
+        let rows = stmt.query_map(params![id_list_json(photo_ids)], |row| row.get::<_, i64>(0))?;
+        rows.collect()
+    }
+
+    /// Photos in scope that have never had their EXIF read (imported before the `exif` table existed).
+    pub fn get_photos_without_exif(&self, folder: &str, recursive: bool) -> Result<Vec<(i64, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut filter = SqlFilter::default();
+        FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
+        filter.push("NOT EXISTS (SELECT 1 FROM exif e WHERE e.photo_id = p.id)".to_string());
+        filter.push("p.removed_at IS NULL".to_string());
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT p.id, p.path FROM photos p {}",
+            filter.where_clause()
+        ))?;
+        let rows = stmt.query_map(filter.params().as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Photos in scope without a content fingerprint (imported before fingerprints existed),
+    /// as (id, path, size).
+    pub fn get_photos_without_fingerprint(&self, folder: &str, recursive: bool) -> Result<Vec<(i64, String, u64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut filter = SqlFilter::default();
+        FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
+        filter.push("p.fingerprint IS NULL AND p.removed_at IS NULL".to_string());
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT p.id, p.path, p.size FROM photos p {}",
+            filter.where_clause()
+        ))?;
+        let rows = stmt.query_map(filter.params().as_slice(), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)? as u64))
+        })?;
+        rows.collect()
+    }
+
+    pub fn set_fingerprints(&self, fingerprints: &[(i64, String)]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE photos SET fingerprint = ?1 WHERE id = ?2")?;
+            for (id, fingerprint) in fingerprints {
+                stmt.execute(params![fingerprint, id])?;
             }
         }
-        let param_refs: Vec<&dyn rusqlite::types::ToSql> =
-            param_values.iter().map(|p| p.as_ref()).collect();
+        tx.commit()
+    }
 
-        let rows = stmt.query_map(param_refs.as_slice(), |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, i64>(2)?,
-                row.get::<_, i64>(3)?,
-                row.get::<_, Option<u32>>(4)?,
-                row.get::<_, Option<u32>>(5)?,
-            ))
+    /// Duplicate candidates in `folder` (or the whole library when `None`): live photos whose
+    /// size is shared by at least one other photo in scope. Each group is ordered so the
+    /// photo to keep comes first — highest rating, then oldest, then by path.
+    pub fn get_duplicate_candidates(&self, folder: Option<&str>, recursive: bool) -> Result<Vec<DuplicateCandidate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut filter = SqlFilter::default();
+        filter.push("p.removed_at IS NULL".to_string());
+        if let Some(folder) = folder {
+            FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
+        }
+        let scope = filter.where_clause();
+
+        let mut stmt = conn.prepare(&format!(
+            "WITH scoped AS (SELECT p.* FROM photos p {})
+             SELECT id, path, size, content_hash FROM scoped
+             WHERE size IN (SELECT size FROM scoped GROUP BY size HAVING COUNT(*) > 1)
+             ORDER BY size DESC, rating DESC, modified ASC, path ASC",
+            scope
+        ))?;
+        let rows = stmt.query_map(filter.params().as_slice(), |row| {
+            Ok(DuplicateCandidate {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                size: row.get::<_, i64>(2)? as u64,
+                content_hash: row.get(3)?,
+            })
         })?;
+        rows.collect()
+    }
 
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
+    pub fn set_content_hashes(&self, hashes: &[(i64, String)]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE photos SET content_hash = ?1 WHERE id = ?2")?;
+            for (id, hash) in hashes {
+                stmt.execute(params![hash, id])?;
+            }
         }
-        Ok(results)
+        tx.commit()
     }
 
-    /// Get all photo_ids that have embeddings (single scan of vec_photos).
-    pub fn get_all_embedded_ids(&self) -> Result<std::collections::HashSet<i64>> {
+    /// Photos in scope without a perceptual hash yet, as (id, path).
+    pub fn get_photos_without_perceptual_hash(&self, folder: &str, recursive: bool) -> Result<Vec<(i64, String)>> {
         let conn = self.conn.lock().unwrap();
+        let mut filter = SqlFilter::default();
+        FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
+        filter.push("p.perceptual_hash IS NULL AND p.removed_at IS NULL".to_string());
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT p.id, p.path FROM photos p {}",
+            filter.where_clause()
+        ))?;
+        let rows = stmt.query_map(filter.params().as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
 
-        let vec_table_exists: bool = conn
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='vec_photos'",
-                [],
-                |row| row.get::<_, i64>(0),
-            )
-            .map(|c| c > 0)
-            .unwrap_or(false);
+    pub fn set_perceptual_hashes(&self, hashes: &[(i64, u64)]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE photos SET perceptual_hash = ?1 WHERE id = ?2")?;
+            for (id, hash) in hashes {
+                stmt.execute(params![*hash as i64, id])?;
+            }
+        }
+        tx.commit()
+    }
 
-        if !vec_table_exists {
-            return Ok(std::collections::HashSet::new());
+    /// (path, perceptual hash) of hashed photos in `folder`, or the whole library when `None`,
+    /// ordered like `get_duplicate_candidates` so the photo to keep comes first.
+    pub fn get_perceptual_hashes(&self, folder: Option<&str>, recursive: bool) -> Result<Vec<(String, u64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut filter = SqlFilter::default();
+        filter.push("p.perceptual_hash IS NOT NULL AND p.removed_at IS NULL AND p.primary_id IS NULL".to_string());
+        if let Some(folder) = folder {
+            FolderScope::new(folder, recursive).apply("p.folder", &mut filter);
         }
 
-        let mut stmt = conn.prepare("SELECT photo_id FROM vec_photos")?;
-        let rows = stmt.query_map([], |row| row.get::<_, i64>(0))?;
-        let mut set = std::collections::HashSet::new();
-        for row in rows {
-            set.insert(row?);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT p.path, p.perceptual_hash FROM photos p {}
+             ORDER BY p.rating DESC, p.modified ASC, p.path ASC",
+            filter.where_clause()
+        ))?;
+        let rows = stmt.query_map(filter.params().as_slice(), |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        rows.collect()
+    }
+
+    /// Store (or replace) EXIF records in a single transaction. Photos without EXIF get an
+    /// all-NULL row so they are not re-read on every import.
+    pub fn batch_upsert_exif(&self, records: &[(i64, PhotoExif)]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO exif (
+                    photo_id, camera_make, camera_model, lens_model, date_taken, iso, f_number,
+                    exposure_time, focal_length, gps_latitude, gps_longitude, software, orientation,
+                    subsec_ms
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )?;
+            for (photo_id, e) in records {
+                stmt.execute(params![
+                    photo_id,
+                    e.camera_make,
+                    e.camera_model,
+                    e.lens_model,
+                    e.date_taken,
+                    e.iso,
+                    e.f_number,
+                    e.exposure_time,
+                    e.focal_length,
+                    e.gps_latitude,
+                    e.gps_longitude,
+                    e.software,
+                    e.orientation,
+                    e.subsec_ms
+                ])?;
+            }
         }
-        Ok(set)
+        tx.commit()
     }
 
     #[allow(dead_code)]
@@ -590,12 +2142,23 @@ impl Database {
         Ok(())
     }
 
+    /// Point the record of `old_path` at `new_path`, keeping its id and everything attached
+    /// to it (tags, ratings, albums). A record already at `new_path` — e.g. created by the
+    /// folder watcher importing the destination mid-move — is replaced.
     pub fn update_photo_path(&self, old_path: &str, new_path: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE photos SET path = ?1 WHERE path = ?2",
-            params![new_path, old_path],
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let moved = tx
+            .prepare("SELECT 1 FROM photos WHERE path = ?1")?
+            .exists(params![old_path])?;
+        if moved && old_path != new_path {
+            tx.execute("DELETE FROM photos WHERE path = ?1", params![new_path])?;
+        }
+        tx.execute(
+            "UPDATE photos SET path = ?1, folder = ?2 WHERE path = ?3",
+            params![new_path, parent_folder(new_path), old_path],
         )?;
+        tx.commit()?;
         Ok(())
     }
 
@@ -604,12 +2167,15 @@ impl Database {
     pub fn get_folder_photo_cache(
         &self,
         folder: &str,
+        recursive: bool,
     ) -> Result<std::collections::HashMap<String, (i64, i64, u64, Option<u32>, Option<u32>)>> {
         let conn = self.conn.lock().unwrap();
-        let pattern = format!("{}%", folder);
-        let mut stmt =
-            conn.prepare("SELECT id, path, modified, size, width, height FROM photos WHERE path LIKE ?1")?;
-        let rows = stmt.query_map([pattern], |row| {
+        let scope = FolderScope::new(folder, recursive);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, path, modified, size, width, height FROM photos WHERE {} AND removed_at IS NULL",
+            scope.sql("folder", 1)
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(scope.params()), |row| {
             Ok((
                 row.get::<_, i64>(0)?,
                 row.get::<_, String>(1)?,
@@ -627,49 +2193,45 @@ impl Database {
         Ok(map)
     }
 
-    /// Pre-load all tags for photos in a folder, grouped by photo_id.
-    pub fn get_tags_for_folder_photos(
-        &self,
-        folder: &str,
-    ) -> Result<std::collections::HashMap<i64, Vec<String>>> {
+    /// Mark DB rows for files in the scanned scope that are no longer on disk as removed.
+    /// They stay around for `REMOVED_RETENTION_SECS` so a renamed or moved file can be
+    /// re-linked by fingerprint; rows without a fingerprint are deleted right away.
+    /// A non-recursive scan only owns the folder's direct children, so rows from
+    /// sub-folders imported by an earlier recursive scan are left alone.
+    pub fn cleanup_folder(&self, folder_path: &str, keep_paths: &[String], recursive: bool) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        let folder_pattern = format!("{}%", folder);
-        let mut stmt = conn.prepare(
-            "SELECT t.photo_id, t.tag FROM tags t JOIN photos p ON t.photo_id = p.id WHERE p.path LIKE ?1",
+        let now = unix_now();
+        conn.execute(
+            "DELETE FROM photos WHERE removed_at < ?1",
+            params![now - REMOVED_RETENTION_SECS],
         )?;
-        let rows = stmt.query_map([folder_pattern], |row| {
-            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
-        })?;
-        let mut map: std::collections::HashMap<i64, Vec<String>> =
-            std::collections::HashMap::new();
-        for row in rows {
-            let (photo_id, tag) = row?;
-            map.entry(photo_id).or_default().push(tag);
-        }
-        Ok(map)
-    }
 
-    pub fn cleanup_folder(&self, folder_path: &str, keep_paths: &[String]) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        let pattern = format!("{}%", folder_path);
-        let mut stmt = conn.prepare("SELECT id, path FROM photos WHERE path LIKE ?1")?;
+        let scope = FolderScope::new(folder_path, recursive);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, path, fingerprint IS NOT NULL FROM photos WHERE {} AND removed_at IS NULL",
+            scope.sql("folder", 1)
+        ))?;
 
-        let rows = stmt.query_map([pattern], |row| {
-            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        let rows = stmt.query_map(rusqlite::params_from_iter(scope.params()), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?))
         })?;
 
         let keep_set: std::collections::HashSet<&String> = keep_paths.iter().collect();
-        let mut to_delete = Vec::new();
+        let mut to_remove = Vec::new();
 
         for row in rows {
-            let (id, path) = row?;
+            let (id, path, has_fingerprint) = row?;
             if !keep_set.contains(&path) {
-                to_delete.push(id);
+                to_remove.push((id, has_fingerprint));
             }
         }
 
-        for id in to_delete {
-            conn.execute("DELETE FROM photos WHERE id = ?1", params![id])?;
+        for (id, has_fingerprint) in to_remove {
+            if has_fingerprint {
+                conn.execute("UPDATE photos SET removed_at = ?1 WHERE id = ?2", params![now, id])?;
+            } else {
+                conn.execute("DELETE FROM photos WHERE id = ?1", params![id])?;
+            }
         }
 
         Ok(())
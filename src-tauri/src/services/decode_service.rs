@@ -0,0 +1,279 @@
+use crate::error::DecodeError;
+use crate::models::format_types::DecodeLimits;
+use crate::services::{exif_service, fs_service, icc_service, raw_service};
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, RgbImage};
+use jxl_oxide::integration::JxlDecoder;
+use resvg::{tiny_skia, usvg};
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Length of the longer side SVGs are rendered at. Vector images have no pixel size of their
+/// own, so this is the resolution thumbnails, the viewer and the classifier all get.
+const SVG_RENDER_SIZE: f32 = 2048.0;
+
+/// Bytes read from the start of a file to detect its format. SVGs may open with an XML
+/// prolog, comments and a doctype before the `<svg` element.
+const SNIFF_LEN: u64 = 4096;
+
+static LIMITS: RwLock<DecodeLimits> = RwLock::new(DecodeLimits::DEFAULT);
+
+/// Limits every decode through this service is held to.
+pub fn limits() -> DecodeLimits {
+    *LIMITS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn set_limits(limits: DecodeLimits) {
+    *LIMITS.write().unwrap_or_else(|e| e.into_inner()) = limits;
+}
+
+/// Whether files with this (lowercase) extension can be decoded by this build. HEIC is
+/// only imported as the companion of a same-named JPEG or RAW file; AVIF needs the `avif`
+/// feature (dav1d), and without it `.avif` files are not imported at all.
+pub fn can_decode(extension: &str) -> bool {
+    match extension {
+        "heic" | "heif" => false,
+        "avif" => cfg!(feature = "avif"),
+        _ => true,
+    }
+}
+
+/// Formats the webview cannot display itself, so the viewer gets them decoded.
+pub fn needs_rendering(path: &Path) -> bool {
+    matches!(fs_service::lowercase_extension(path).as_deref(), Some("svg" | "jxl"))
+}
+
+/// A decoded image with the EXIF orientation and color profile it is stored in.
+pub struct DecodedImage {
+    pub image: DynamicImage,
+    pub orientation: u32,
+    /// Embedded ICC profile; none means sRGB
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+impl DecodedImage {
+    /// Scale down to fit a `max` x `max` box. Do this before `display`: rotating and
+    /// color converting a full-size image is far slower, and a square box scales both
+    /// orientations alike.
+    pub fn fit(mut self, max: u32) -> Self {
+        if self.image.width() > max || self.image.height() > max {
+            self.image = self.image.thumbnail(max, max);
+        }
+        self
+    }
+
+    /// The image as it should be displayed: turned upright and converted to sRGB.
+    pub fn display(self) -> DynamicImage {
+        let img = match &self.icc_profile {
+            Some(icc) => icc_service::to_srgb(self.image, icc),
+            None => self.image,
+        };
+        exif_service::apply_orientation(img, self.orientation)
+    }
+}
+
+/// What a file contains, going by its first bytes.
+enum SourceFormat {
+    Raster(ImageFormat),
+    Jxl,
+    Svg,
+    /// Camera RAW, shown through its embedded JPEG preview
+    Raw,
+}
+
+/// Decode the image file at `path`, whatever its extension says: raster formats through the
+/// `image` crate, JPEG XL with jxl-oxide, SVG by rendering it onto a white background, RAW
+/// through its largest embedded preview. The orientation and color profile to apply are
+/// returned alongside.
+pub fn decode_file(path: &Path) -> Result<DecodedImage, DecodeError> {
+    let mut file = File::open(path)?;
+    let mut header = Vec::with_capacity(SNIFF_LEN as usize);
+    file.by_ref().take(SNIFF_LEN).read_to_end(&mut header)?;
+    file.rewind()?;
+
+    let with_path = |err: DecodeError| match err {
+        DecodeError::Unsupported(msg) => DecodeError::Unsupported(format!("{}: {}", path.display(), msg)),
+        DecodeError::Corrupt(msg) => DecodeError::Corrupt(format!("{}: {}", path.display(), msg)),
+        DecodeError::TooLarge(msg) => DecodeError::TooLarge(format!("{}: {}", path.display(), msg)),
+        err => err,
+    };
+
+    match detect_format(path, &header).map_err(with_path)? {
+        SourceFormat::Raw => {
+            let preview = raw_service::extract_preview(path)
+                .map_err(|e| DecodeError::Corrupt(e.message))?;
+            let (image, icc_profile) = decode_in_memory(&preview.jpeg).map_err(with_path)?;
+            Ok(DecodedImage { image, orientation: preview.orientation, icc_profile })
+        }
+        SourceFormat::Svg => {
+            let image = render_svg(path, file).map_err(with_path)?;
+            Ok(DecodedImage { image, orientation: 1, icc_profile: None })
+        }
+        SourceFormat::Jxl => {
+            // jxl-oxide renders JPEG XL upright
+            let (image, icc_profile) = decode_jxl(file).map_err(with_path)?;
+            Ok(DecodedImage { image, orientation: 1, icc_profile })
+        }
+        SourceFormat::Raster(format) => {
+            let reader = ImageReader::with_format(BufReader::new(file), format);
+            let (image, icc_profile) = decode_reader(reader).map_err(with_path)?;
+            Ok(DecodedImage { image, orientation: exif_service::get_orientation(path), icc_profile })
+        }
+    }
+}
+
+/// Decode an in-memory raster image, such as an embedded thumbnail or RAW preview, under
+/// the same limits as files. No orientation or color conversion is applied.
+pub fn decode_bytes(bytes: &[u8]) -> Result<DynamicImage, DecodeError> {
+    Ok(decode_in_memory(bytes)?.0)
+}
+
+fn decode_in_memory(bytes: &[u8]) -> Result<(DynamicImage, Option<Vec<u8>>), DecodeError> {
+    let format = image::guess_format(bytes)
+        .map_err(|_| DecodeError::Unsupported("unknown image format".to_string()))?;
+    decode_reader(ImageReader::with_format(Cursor::new(bytes), format))
+}
+
+fn detect_format(path: &Path, header: &[u8]) -> Result<SourceFormat, DecodeError> {
+    // ISO base media files (HEIF, AVIF, CR3) name their kind in the ftyp box
+    if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        let box_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let brands: Vec<&[u8]> = header[8..box_len.clamp(12, header.len())]
+            .chunks_exact(4)
+            .enumerate()
+            // Major brand, minor version, then compatible brands
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, brand)| brand)
+            .collect();
+        if brands.contains(&&b"crx "[..]) {
+            return Ok(SourceFormat::Raw);
+        }
+        if brands.iter().any(|b| matches!(*b, b"avif" | b"avis")) {
+            return if cfg!(feature = "avif") {
+                Ok(SourceFormat::Raster(ImageFormat::Avif))
+            } else {
+                Err(DecodeError::Unsupported("AVIF decoding is not enabled in this build".to_string()))
+            };
+        }
+        if brands.iter().any(|b| matches!(*b, b"heic" | b"heix" | b"hevc" | b"hevx" | b"mif1" | b"msf1")) {
+            return Err(DecodeError::Unsupported("HEIF images cannot be decoded".to_string()));
+        }
+    }
+
+    // JPEG XL: bare codestream or ISO container
+    if header.starts_with(&[0xFF, 0x0A])
+        || header.starts_with(&[0, 0, 0, 0x0C, b'J', b'X', b'L', b' ', 0x0D, 0x0A, 0x87, 0x0A])
+    {
+        return Ok(SourceFormat::Jxl);
+    }
+
+    // Fujifilm RAF, and the TIFF variants of Olympus (ORF) and Panasonic (RW2)
+    if header.starts_with(b"FUJIFILMCCD-RAW")
+        || header.starts_with(b"IIRO")
+        || header.starts_with(b"IIRS")
+        || header.starts_with(b"MMOR")
+        || header.starts_with(b"IIU\0")
+    {
+        return Ok(SourceFormat::Raw);
+    }
+
+    if let Ok(format) = image::guess_format(header) {
+        // Most RAW formats (CR2, NEF, ARW, DNG, ...) are plain TIFF files at this level;
+        // only the extension tells them apart from a TIFF to decode in full
+        if format == ImageFormat::Tiff && fs_service::is_raw_file(path) {
+            return Ok(SourceFormat::Raw);
+        }
+        return Ok(SourceFormat::Raster(format));
+    }
+
+    if looks_like_svg(header) {
+        return Ok(SourceFormat::Svg);
+    }
+
+    Err(DecodeError::Unsupported("unknown image format".to_string()))
+}
+
+/// An XML document (optionally after a BOM and whitespace) with an `<svg` element in its
+/// first few KB.
+fn looks_like_svg(header: &[u8]) -> bool {
+    let text = header.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(header);
+    let start = text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(text.len());
+    text[start..].starts_with(b"<") && text.windows(4).any(|w| w == b"<svg")
+}
+
+/// The `image` crate limits matching the configured `DecodeLimits`.
+fn image_limits(limits: &DecodeLimits) -> image::Limits {
+    let mut image_limits = image::Limits::default();
+    image_limits.max_alloc = Some(limits.max_alloc);
+    image_limits
+}
+
+/// Decode with `decoder` once its header passes the limits; the pixel count is checked
+/// before any pixel buffer is allocated. Returns the image with its embedded ICC profile.
+fn decode_limited(mut decoder: impl ImageDecoder) -> Result<(DynamicImage, Option<Vec<u8>>), DecodeError> {
+    let limits = limits();
+    let (width, height) = decoder.dimensions();
+    if width as u64 * height as u64 > limits.max_pixels {
+        return Err(DecodeError::TooLarge(format!(
+            "{}x{} exceeds the limit of {} pixels",
+            width, height, limits.max_pixels
+        )));
+    }
+    decoder.set_limits(image_limits(&limits))?;
+    // A broken profile shouldn't cost the image, it just displays unconverted
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    Ok((DynamicImage::from_decoder(decoder)?, icc_profile))
+}
+
+fn decode_reader<R: std::io::BufRead + Seek>(
+    mut reader: ImageReader<R>,
+) -> Result<(DynamicImage, Option<Vec<u8>>), DecodeError> {
+    // The reader applies its own limits while opening the decoder, so hand it ours
+    reader.limits(image_limits(&limits()));
+    decode_limited(reader.into_decoder()?)
+}
+
+fn decode_jxl(file: File) -> Result<(DynamicImage, Option<Vec<u8>>), DecodeError> {
+    let decoder = JxlDecoder::new(BufReader::new(file))
+        .map_err(|e| DecodeError::Corrupt(e.to_string()))?;
+    decode_limited(decoder)
+}
+
+/// System fonts for SVG text, loaded once.
+fn font_database() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+fn render_svg(path: &Path, mut file: File) -> Result<DynamicImage, DecodeError> {
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    let options = usvg::Options {
+        // Relative references (e.g. linked images) resolve next to the file
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb: font_database(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_data(&data, &options).map_err(|e| DecodeError::Corrupt(e.to_string()))?;
+
+    let size = tree.size();
+    let scale = SVG_RENDER_SIZE / size.width().max(size.height());
+    let width = ((size.width() * scale).round() as u32).max(1);
+    let height = ((size.height() * scale).round() as u32).max(1);
+    let invalid_size = || DecodeError::Corrupt(format!("invalid size {}x{}", size.width(), size.height()));
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or_else(invalid_size)?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // Opaque after the white fill, so the premultiplied RGBA is plain RGBA
+    let rgb: Vec<u8> = pixmap.data().chunks_exact(4).flat_map(|px| [px[0], px[1], px[2]]).collect();
+    let img = RgbImage::from_raw(width, height, rgb).ok_or_else(invalid_size)?;
+    Ok(DynamicImage::ImageRgb8(img))
+}
@@ -1,20 +1,31 @@
 use crate::error::AppError;
-use crate::models::exif_types::ExifData;
+use crate::models::exif_types::{ExifData, PhotoExif};
+use crate::services::{fs_service, raw_service};
 use exif::{In, Tag};
 use std::fs::File;
 use std::io::{BufReader, Cursor, Read};
 use std::path::Path;
 
-pub fn read_exif(path: &Path) -> Result<ExifData, AppError> {
+fn load_exif(path: &Path) -> Result<exif::Exif, AppError> {
+    if fs_service::is_raw_file(path) {
+        return raw_service::load_exif(path);
+    }
+
     let file = File::open(path).map_err(|e| AppError {
         message: format!("Failed to open file: {}", e),
+        ..Default::default()
     })?;
 
     let mut reader = BufReader::new(file);
     let exif_reader = exif::Reader::new();
-    let exif = exif_reader.read_from_container(&mut reader).map_err(|e| AppError {
+    exif_reader.read_from_container(&mut reader).map_err(|e| AppError {
         message: format!("Failed to read EXIF data: {}", e),
-    })?;
+        ..Default::default()
+    })
+}
+
+pub fn read_exif(path: &Path) -> Result<ExifData, AppError> {
+    let exif = load_exif(path)?;
 
     let mut data = ExifData::default();
 
@@ -92,6 +103,73 @@ pub fn read_exif(path: &Path) -> Result<ExifData, AppError> {
     Ok(data)
 }
 
+/// Read the searchable subset of EXIF as typed values for the `exif` table.
+pub fn read_exif_record(path: &Path) -> Result<PhotoExif, AppError> {
+    let exif = load_exif(path)?;
+
+    let gps = |coord: Tag, reference: Tag| {
+        let field = exif.get_field(coord, In::PRIMARY)?;
+        let reference = exif.get_field(reference, In::PRIMARY)?;
+        parse_gps_coord(&field.value, &reference.display_value().to_string())
+    };
+
+    // Capture time, with the fraction of a second from the matching SubSec tag
+    let ascii = |tag: Tag| match exif.get_field(tag, In::PRIMARY)?.value {
+        exif::Value::Ascii(ref v) => v.first().cloned(),
+        _ => None,
+    };
+    let date = [(Tag::DateTimeOriginal, Tag::SubSecTimeOriginal), (Tag::DateTime, Tag::SubSecTime)]
+        .into_iter()
+        .find_map(|(date_tag, subsec_tag)| {
+            let mut date = exif::DateTime::from_ascii(&ascii(date_tag)?).ok()?;
+            if let Some(subsec) = ascii(subsec_tag) {
+                let _ = date.parse_subsec(&subsec);
+            }
+            Some(date)
+        });
+
+    Ok(PhotoExif {
+        camera_make: field_string(&exif, Tag::Make),
+        camera_model: field_string(&exif, Tag::Model),
+        lens_model: field_string(&exif, Tag::LensModel),
+        date_taken: date.as_ref().map(|d| {
+            format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                d.year, d.month, d.day, d.hour, d.minute, d.second
+            )
+        }),
+        subsec_ms: date.as_ref().and_then(|d| d.nanosecond).map(|ns| ns / 1_000_000),
+        iso: field_f64(&exif, Tag::PhotographicSensitivity).map(|v| v as u32),
+        f_number: field_f64(&exif, Tag::FNumber),
+        exposure_time: field_f64(&exif, Tag::ExposureTime),
+        focal_length: field_f64(&exif, Tag::FocalLength),
+        gps_latitude: gps(Tag::GPSLatitude, Tag::GPSLatitudeRef),
+        gps_longitude: gps(Tag::GPSLongitude, Tag::GPSLongitudeRef),
+        software: field_string(&exif, Tag::Software),
+        orientation: field_f64(&exif, Tag::Orientation).map(|v| v as u32),
+    })
+}
+
+fn field_string(exif: &exif::Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    let value = field.display_value().to_string().trim_matches('"').trim().to_string();
+    if value.is_empty() { None } else { Some(value) }
+}
+
+/// First component of a numeric field as f64 (integers and rationals alike).
+fn field_f64(exif: &exif::Exif, tag: Tag) -> Option<f64> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    match field.value {
+        exif::Value::Byte(ref v) => v.first().map(|&x| x as f64),
+        exif::Value::Short(ref v) => v.first().map(|&x| x as f64),
+        exif::Value::Long(ref v) => v.first().map(|&x| x as f64),
+        exif::Value::Rational(ref v) => v.first().map(|r| r.to_f64()),
+        exif::Value::SRational(ref v) => v.first().map(|r| r.to_f64()),
+        _ => None,
+    }
+    .filter(|v| v.is_finite())
+}
+
 fn parse_gps_coord(value: &exif::Value, reference: &str) -> Option<f64> {
     if let exif::Value::Rational(ref rationals) = value {
         if rationals.len() >= 3 {
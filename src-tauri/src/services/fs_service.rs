@@ -1,18 +1,145 @@
 use crate::error::AppError;
 use crate::models::fs_types::{DirEntry, DriveInfo};
-use std::path::Path;
+use crate::services::decode_service;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
 
 const IMAGE_EXTENSIONS: &[&str] = &[
-    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif", "ico", "svg",
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "tif", "ico", "svg", "heic", "heif", "jxl",
 ];
 
+/// Formats imported only by builds that can decode them: AVIF needs the `avif` feature.
+const OPTIONAL_EXTENSIONS: &[(&str, bool)] = &[("avif", cfg!(feature = "avif"))];
+
+/// Camera RAW formats. Imported as photos; a same-named JPEG becomes their primary.
+const RAW_EXTENSIONS: &[&str] = &[
+    "arw", "cr2", "cr3", "dng", "nef", "nrw", "orf", "pef", "raf", "rw2", "srw",
+];
+
+/// Metadata sidecars, never imported but kept with their image on delete, move and copy.
+const SIDECAR_EXTENSIONS: &[&str] = &["xmp"];
+
+/// Default maximum folder depth for recursive scans (relative to the scan root).
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+/// Bytes hashed from each end of a file by `content_fingerprint`.
+const FINGERPRINT_CHUNK: u64 = 1024 * 1024;
+
+pub fn lowercase_extension(path: &Path) -> Option<String> {
+    path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase())
+}
+
 pub fn is_image_file(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+    lowercase_extension(path)
+        .map(|ext| image_extensions().any(|known| known == ext))
+        .unwrap_or(false)
+}
+
+/// Camera RAW file, which the `image` crate cannot decode; see `raw_service`.
+pub fn is_raw_file(path: &Path) -> bool {
+    lowercase_extension(path)
+        .map(|ext| RAW_EXTENSIONS.contains(&ext.as_str()))
         .unwrap_or(false)
 }
 
+/// Every extension the scanner imports, RAW formats last.
+pub fn image_extensions() -> impl Iterator<Item = &'static str> {
+    let optional = OPTIONAL_EXTENSIONS.iter().filter(|(_, enabled)| *enabled).map(|(ext, _)| ext);
+    IMAGE_EXTENSIONS.iter().chain(optional).chain(RAW_EXTENSIONS).copied()
+}
+
+/// Image extensions this build leaves out of scans because it cannot decode them.
+pub fn skipped_extensions() -> impl Iterator<Item = &'static str> {
+    OPTIONAL_EXTENSIONS.iter().filter(|(_, enabled)| !*enabled).map(|(ext, _)| *ext)
+}
+
+/// Preference of a format as the primary of a RAW+JPEG group (lower wins), or `None`
+/// when the format is never paired. Goes by what can be shown: the JPEG, then the RAW
+/// through its embedded preview, then HEIC, which this build may not decode.
+fn pairing_rank(path: &Path) -> Option<u8> {
+    match lowercase_extension(path)?.as_str() {
+        "jpg" | "jpeg" => Some(0),
+        ext if RAW_EXTENSIONS.contains(&ext) => Some(1),
+        "heic" | "heif" => Some(2),
+        _ => None,
+    }
+}
+
+/// Folder and lowercase basename, which the files of a RAW+JPEG group share.
+fn pairing_key(path: &Path) -> Option<(PathBuf, String)> {
+    Some((path.parent()?.to_path_buf(), path.file_stem()?.to_string_lossy().to_lowercase()))
+}
+
+/// Drop the files this build cannot decode (HEIC) unless a same-named JPEG or RAW file
+/// sits next to them: those are kept as its companion, anything else would only ever be
+/// a broken tile.
+fn drop_undecodable(mut images: Vec<(PathBuf, u64, i64)>) -> Vec<(PathBuf, u64, i64)> {
+    let decodable = |path: &Path| {
+        lowercase_extension(path).is_some_and(|ext| decode_service::can_decode(&ext))
+    };
+    let shown: std::collections::HashSet<(PathBuf, String)> = images
+        .iter()
+        .filter(|(path, _, _)| pairing_rank(path).is_some() && decodable(path))
+        .filter_map(|(path, _, _)| pairing_key(path))
+        .collect();
+    images.retain(|(path, _, _)| decodable(path) || pairing_key(path).is_some_and(|key| shown.contains(&key)));
+    images
+}
+
+/// Group JPEG, HEIC and RAW files that share a folder and basename (ignoring case), e.g.
+/// `IMG_0001.CR2` and `IMG_0001.JPG`. Returns (primary, companions) for every group of two
+/// or more; the primary is the JPEG, else the RAW, so the group shows as something the
+/// app can display.
+pub fn pair_companions(paths: &[String]) -> Vec<(String, Vec<String>)> {
+    let mut groups: std::collections::BTreeMap<(PathBuf, String), Vec<(u8, &String)>> =
+        std::collections::BTreeMap::new();
+    for path_str in paths {
+        let path = Path::new(path_str);
+        let (Some(rank), Some(key)) = (pairing_rank(path), pairing_key(path)) else {
+            continue;
+        };
+        groups.entry(key).or_default().push((rank, path_str));
+    }
+
+    groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|mut members| {
+            members.sort();
+            let primary = members[0].1.clone();
+            let companions = members[1..].iter().map(|(_, path)| (*path).clone()).collect();
+            (primary, companions)
+        })
+        .collect()
+}
+
+/// XMP sidecars of `path` next to it: `IMG_0001.xmp` as well as `IMG_0001.CR2.xmp`, in any case.
+pub fn sidecar_files(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem), Some(name)) = (path.parent(), path.file_stem(), path.file_name()) else {
+        return Vec::new();
+    };
+    let stem = stem.to_string_lossy().to_lowercase();
+    let name = name.to_string_lossy().to_lowercase();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut sidecars: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            let is_sidecar = lowercase_extension(candidate)
+                .map(|ext| SIDECAR_EXTENSIONS.contains(&ext.as_str()))
+                .unwrap_or(false);
+            let candidate_stem = candidate.file_stem().map(|s| s.to_string_lossy().to_lowercase());
+            is_sidecar
+                && candidate.is_file()
+                && candidate_stem.is_some_and(|s| s == stem || s == name)
+        })
+        .collect();
+    sidecars.sort();
+    sidecars
+}
+
 #[cfg(windows)]
 pub fn list_drives() -> Result<Vec<DriveInfo>, AppError> {
     use windows::Win32::Storage::FileSystem::GetLogicalDriveStringsW;
@@ -62,6 +189,7 @@ pub fn list_directory(path: &str) -> Result<Vec<DirEntry>, AppError> {
 
     let read_dir = std::fs::read_dir(dir_path).map_err(|e| AppError {
         message: format!("Cannot read directory {}: {}", path, e),
+        ..Default::default()
     })?;
 
     for entry in read_dir {
@@ -97,17 +225,72 @@ pub fn list_directory(path: &str) -> Result<Vec<DirEntry>, AppError> {
     Ok(entries)
 }
 
-pub fn list_image_files(path: &str) -> Result<Vec<std::path::PathBuf>, AppError> {
+pub fn list_image_files(path: &str) -> Result<Vec<PathBuf>, AppError> {
     Ok(list_image_files_with_meta(path)?
         .into_iter()
         .map(|(p, _, _)| p)
         .collect())
 }
 
+/// List image files either directly inside `path` or in its whole subtree.
+pub fn list_image_files_scoped(
+    path: &str,
+    recursive: bool,
+    max_depth: Option<usize>,
+) -> Result<Vec<PathBuf>, AppError> {
+    let files = if recursive {
+        list_image_files_recursive(path, max_depth.unwrap_or(DEFAULT_MAX_DEPTH), |_, _| {})?
+    } else {
+        list_image_files_with_meta(path)?
+    };
+    Ok(files.into_iter().map(|(p, _, _)| p).collect())
+}
+
+/// Extract (size, modified unix seconds) from file metadata.
+fn size_and_modified(meta: &std::fs::Metadata) -> (u64, i64) {
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0) as i64;
+    (meta.len(), modified)
+}
+
+/// Fast content fingerprint: BLAKE3 over the file size and its first and last MiB, so a
+/// file can be recognized after a rename or move without reading all of it.
+pub fn content_fingerprint(path: &Path, size: u64) -> std::io::Result<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&size.to_le_bytes());
+
+    let mut chunk = Vec::with_capacity(FINGERPRINT_CHUNK as usize);
+    (&mut file).take(FINGERPRINT_CHUNK).read_to_end(&mut chunk)?;
+    hasher.update(&chunk);
+    if size > FINGERPRINT_CHUNK {
+        // Small files overlap the head; start the tail after it
+        file.seek(SeekFrom::Start(size.saturating_sub(FINGERPRINT_CHUNK).max(FINGERPRINT_CHUNK)))?;
+        chunk.clear();
+        file.take(FINGERPRINT_CHUNK).read_to_end(&mut chunk)?;
+        hasher.update(&chunk);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// BLAKE3 hash of the whole file, streamed so large files are not loaded into memory.
+pub fn content_hash(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
 /// List image files with metadata (size, modified timestamp).
 /// Uses DirEntry::file_type() and DirEntry::metadata() which are free on Windows
 /// (no extra syscall — data comes from FindNextFile).
-pub fn list_image_files_with_meta(path: &str) -> Result<Vec<(std::path::PathBuf, u64, i64)>, AppError> {
+pub fn list_image_files_with_meta(path: &str) -> Result<Vec<(PathBuf, u64, i64)>, AppError> {
     let dir_path = Path::new(path);
     if !dir_path.exists() {
         return Err(format!("Path does not exist: {}", path).into());
@@ -117,6 +300,7 @@ pub fn list_image_files_with_meta(path: &str) -> Result<Vec<(std::path::PathBuf,
 
     let read_dir = std::fs::read_dir(dir_path).map_err(|e| AppError {
         message: format!("Cannot read directory {}: {}", path, e),
+        ..Default::default()
     })?;
 
     for entry in read_dir {
@@ -144,14 +328,7 @@ pub fn list_image_files_with_meta(path: &str) -> Result<Vec<(std::path::PathBuf,
             Err(_) => continue,
         };
 
-        let size = meta.len();
-        let modified = meta
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs())
-            .unwrap_or(0) as i64;
-
+        let (size, modified) = size_and_modified(&meta);
         images.push((path, size, modified));
     }
 
@@ -169,7 +346,90 @@ pub fn list_image_files_with_meta(path: &str) -> Result<Vec<(std::path::PathBuf,
             )
     });
 
-    Ok(images)
+    Ok(drop_undecodable(images))
+}
+
+/// Recursively list image files below `path` with metadata (size, modified timestamp).
+///
+/// Symlinks are followed, but walkdir tracks the ancestor chain and reports a loop
+/// as an error instead of descending again, so looping links are skipped. Hidden
+/// folders are not entered, matching `list_directory`. `on_dir` is called whenever the
+/// walk finishes a folder with (folder, images found directly in it) so callers can
+/// report progress per subtree.
+pub fn list_image_files_recursive(
+    path: &str,
+    max_depth: usize,
+    mut on_dir: impl FnMut(&Path, usize),
+) -> Result<Vec<(PathBuf, u64, i64)>, AppError> {
+    let dir_path = Path::new(path);
+    if !dir_path.exists() {
+        return Err(format!("Path does not exist: {}", path).into());
+    }
+
+    let mut images = Vec::new();
+    // Folder currently being walked and how many images it contained so far.
+    // Files are sorted before sub-folders, so a folder is finished as soon as
+    // the walk yields the next folder.
+    let mut current_dir: Option<(PathBuf, usize)> = None;
+
+    let walker = WalkDir::new(dir_path)
+        .follow_links(true)
+        .max_depth(max_depth.max(1))
+        .sort_by(|a, b| a.file_type().is_dir().cmp(&b.file_type().is_dir()))
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || !e.file_type().is_dir()
+                || !e.file_name().to_string_lossy().starts_with('.')
+        });
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => {
+                if e.loop_ancestor().is_some() {
+                    eprintln!("Skipping symlink loop: {}", e);
+                }
+                continue;
+            }
+        };
+
+        if entry.file_type().is_dir() {
+            if let Some((dir, count)) = current_dir.take() {
+                on_dir(&dir, count);
+            }
+            current_dir = Some((entry.path().to_path_buf(), 0));
+            continue;
+        }
+
+        if !entry.file_type().is_file() || !is_image_file(entry.path()) {
+            continue;
+        }
+
+        let meta = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        if let Some((_, count)) = current_dir.as_mut() {
+            *count += 1;
+        }
+
+        let (size, modified) = size_and_modified(&meta);
+        images.push((entry.into_path(), size, modified));
+    }
+
+    if let Some((dir, count)) = current_dir.take() {
+        on_dir(&dir, count);
+    }
+
+    images.sort_by(|a, b| {
+        a.0.to_string_lossy()
+            .to_lowercase()
+            .cmp(&b.0.to_string_lossy().to_lowercase())
+    });
+
+    Ok(drop_undecodable(images))
 }
 
 pub fn path_exists(path: &str) -> bool {
@@ -209,6 +469,7 @@ pub fn autocomplete_path(partial: &str) -> Result<Vec<String>, AppError> {
 
     let read_dir = std::fs::read_dir(parent).map_err(|e| AppError {
         message: format!("Cannot read directory {}: {}", parent.display(), e),
+        ..Default::default()
     })?;
 
     let mut matches = Vec::new();
@@ -239,3 +500,4 @@ pub fn autocomplete_path(partial: &str) -> Result<Vec<String>, AppError> {
     
     Ok(matches)
 }
+
@@ -0,0 +1,72 @@
+use image::DynamicImage;
+use qcms::{DataType, Intent, Profile, Transform};
+use std::sync::OnceLock;
+
+/// Signature opening each APP2 segment that carries a piece of an ICC profile.
+const JPEG_ICC_SIGNATURE: &[u8] = b"ICC_PROFILE\0";
+
+/// sRGB as the target of every conversion, with its output tables computed once.
+fn srgb() -> &'static Profile {
+    static SRGB: OnceLock<Box<Profile>> = OnceLock::new();
+    SRGB.get_or_init(|| {
+        let mut profile = Profile::new_sRGB();
+        profile.precache_output_transform();
+        profile
+    })
+}
+
+/// Convert `img` from the color space described by the ICC profile `icc` to sRGB, so it
+/// displays correctly once re-encoded without a profile. The result is 8-bit RGB(A).
+/// Profiles that can't be parsed or don't describe RGB data leave the image unchanged.
+pub fn to_srgb(img: DynamicImage, icc: &[u8]) -> DynamicImage {
+    let Some(profile) = Profile::new_from_slice(icc, false) else {
+        return img;
+    };
+    let data_type = if img.color().has_alpha() { DataType::RGBA8 } else { DataType::RGB8 };
+    let Some(transform) = Transform::new(&profile, srgb(), data_type, Intent::Perceptual) else {
+        return img;
+    };
+    if img.color().has_alpha() {
+        let mut rgba = img.into_rgba8();
+        transform.apply(&mut rgba);
+        DynamicImage::ImageRgba8(rgba)
+    } else {
+        let mut rgb = img.into_rgb8();
+        transform.apply(&mut rgb);
+        DynamicImage::ImageRgb8(rgb)
+    }
+}
+
+/// The ICC profile embedded in a JPEG, reassembled from its APP2 segments. `data` only needs
+/// to reach the start of the scan, so the header read for EXIF is usually enough.
+pub fn jpeg_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    // (sequence number, chunk); the profile may be split over several segments
+    let mut chunks: Vec<(u8, &[u8])> = Vec::new();
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        // Start of scan: no more metadata segments follow
+        if marker == 0xDA {
+            break;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xE2 {
+            if let Some(rest) = segment.strip_prefix(JPEG_ICC_SIGNATURE) {
+                // Sequence number and total count precede the chunk
+                if rest.len() > 2 {
+                    chunks.push((rest[0], &rest[2..]));
+                }
+            }
+        }
+        pos += 2 + len;
+    }
+    if chunks.is_empty() {
+        return None;
+    }
+    chunks.sort_by_key(|(seq, _)| *seq);
+    Some(chunks.into_iter().flat_map(|(_, chunk)| chunk.iter().copied()).collect())
+}
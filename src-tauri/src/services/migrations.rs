@@ -0,0 +1,520 @@
+use crate::error::AppError;
+use crate::services::db::parent_folder;
+use rusqlite::{params, Connection, Transaction};
+use std::path::Path;
+
+/// A single schema upgrade step. Steps run in `version` order, each in its own
+/// transaction, and bump `PRAGMA user_version` to their version on success.
+struct Migration {
+    version: i32,
+    description: &'static str,
+    /// Destructive steps drop or rewrite data; the database is backed up before they run.
+    destructive: bool,
+    up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// Ordered list of all schema migrations. Append new steps at the end and never
+/// edit a step that has shipped — existing libraries have already applied it.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        destructive: false,
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "photos.folder column for exact folder scoping",
+        destructive: false,
+        up: add_photo_folder,
+    },
+    Migration {
+        version: 3,
+        description: "exif table",
+        destructive: false,
+        up: add_exif_table,
+    },
+    Migration {
+        version: 4,
+        description: "photos_fts full-text index",
+        destructive: false,
+        up: add_photo_fts,
+    },
+    Migration {
+        version: 5,
+        description: "tags.source column for tag provenance",
+        destructive: true,
+        up: add_tag_source,
+    },
+    Migration {
+        version: 6,
+        description: "rating, color label and flag columns on photos",
+        destructive: false,
+        up: add_photo_culling,
+    },
+    Migration {
+        version: 7,
+        description: "albums",
+        destructive: false,
+        up: add_albums,
+    },
+    Migration {
+        version: 8,
+        description: "saved queries",
+        destructive: false,
+        up: add_saved_queries,
+    },
+    Migration {
+        version: 9,
+        description: "content fingerprints and removed-photo tombstones",
+        destructive: false,
+        up: add_photo_fingerprint,
+    },
+    Migration {
+        version: 10,
+        description: "full content hashes for duplicate detection",
+        destructive: false,
+        up: add_photo_content_hash,
+    },
+    Migration {
+        version: 11,
+        description: "perceptual hashes for near-duplicate detection",
+        destructive: false,
+        up: add_perceptual_hash,
+    },
+    Migration {
+        version: 12,
+        description: "burst stacks and sub-second capture times",
+        destructive: false,
+        up: add_stacks,
+    },
+    Migration {
+        version: 13,
+        description: "RAW+JPEG pairs",
+        destructive: false,
+        up: add_photo_pairs,
+    },
+    Migration {
+        version: 14,
+        description: "on-disk thumbnail cache index and settings",
+        destructive: false,
+        up: add_thumbnail_cache,
+    },
+];
+
+/// Schema version this build of the app writes.
+pub fn current_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Bring the database at `db_path` up to `current_version()`.
+/// Refuses to touch databases written by a newer app version.
+pub fn run(conn: &mut Connection, db_path: &Path) -> Result<(), AppError> {
+    let db_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let app_version = current_version();
+
+    if db_version > app_version {
+        return Err(AppError {
+            message: format!(
+                "Library database {} uses schema version {}, but this version of PhotoLense only supports up to {}. Please update the app.",
+                db_path.display(),
+                db_version,
+                app_version
+            ),
+            ..Default::default()
+        });
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > db_version).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    // Fresh databases have nothing to lose, so only back up existing libraries
+    let has_data: bool = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'photos'")
+        .and_then(|mut stmt| stmt.exists([]))?;
+    if has_data && pending.iter().any(|m| m.destructive) {
+        backup(conn, db_path, db_version)?;
+    }
+
+    for migration in pending {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx).map_err(|e| AppError {
+            message: format!(
+                "Migration to schema version {} ({}) failed: {}",
+                migration.version, migration.description, e
+            ),
+            ..Default::default()
+        })?;
+        // user_version is stored in the database header and commits with the transaction
+        tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Write a consistent copy of the database next to it, e.g. `library.db.v2.bak`.
+fn backup(conn: &Connection, db_path: &Path, version: i32) -> Result<(), AppError> {
+    let mut backup_path = db_path.as_os_str().to_owned();
+    backup_path.push(format!(".v{}.bak", version));
+    let backup_path = std::path::PathBuf::from(backup_path);
+
+    if backup_path.exists() {
+        std::fs::remove_file(&backup_path)?;
+    }
+    // VACUUM INTO produces a compact snapshot and works while the WAL is active
+    conn.execute("VACUUM INTO ?1", params![backup_path.to_string_lossy()])
+        .map_err(|e| AppError {
+            message: format!("Failed to back up database to {}: {}", backup_path.display(), e),
+            ..Default::default()
+        })?;
+    Ok(())
+}
+
+/// Turn on foreign key enforcement, so deleting a photo also deletes its tags, EXIF and
+/// thumbnail rows (ON DELETE CASCADE). Earlier builds never enforced the keys and left such
+/// rows behind, which would now fail every update that touches them, so rows pointing at
+/// missing records are first cleaned up the way enforcement would have: deleted, or their
+/// reference cleared where the key is ON DELETE SET NULL.
+pub fn enable_foreign_keys(conn: &Connection) -> Result<(), AppError> {
+    let violations: Vec<(String, Option<i64>, i64)> = conn
+        .prepare("PRAGMA foreign_key_check")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(3)?)))?
+                .collect()
+        })?;
+    for (table, rowid, key) in &violations {
+        let Some(rowid) = rowid else {
+            continue;
+        };
+        let (column, on_delete): (String, String) = conn.query_row(
+            "SELECT \"from\", on_delete FROM pragma_foreign_key_list(?1) WHERE id = ?2",
+            params![table, key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let sql = if on_delete == "SET NULL" {
+            format!("UPDATE \"{}\" SET \"{}\" = NULL WHERE rowid = ?1", table, column)
+        } else {
+            format!("DELETE FROM \"{}\" WHERE rowid = ?1", table)
+        };
+        conn.execute(&sql, params![rowid])?;
+    }
+    if !violations.is_empty() {
+        eprintln!("[db] Removed {} rows referencing missing records", violations.len());
+    }
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    Ok(())
+}
+
+/// Version 1: the tables every release before versioned migrations created.
+/// Uses IF NOT EXISTS so libraries from those releases (user_version 0) pass through unchanged.
+fn initial_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS photos (
+            id INTEGER PRIMARY KEY,
+            path TEXT NOT NULL UNIQUE,
+            size INTEGER,
+            modified INTEGER,
+            width INTEGER,
+            height INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY,
+            photo_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            confidence REAL,
+            FOREIGN KEY(photo_id) REFERENCES photos(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS thumbnails (
+            photo_id INTEGER PRIMARY KEY,
+            data BLOB NOT NULL,
+            FOREIGN KEY(photo_id) REFERENCES photos(id) ON DELETE CASCADE
+        );
+
+        -- Index for faster path lookups
+        CREATE INDEX IF NOT EXISTS idx_photos_path ON photos(path);
+
+        -- Vec metadata table to track current model type and embedding dimension
+        CREATE TABLE IF NOT EXISTS vec_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+}
+
+/// Version 2: parent folder of every photo, backfilled from `path`.
+fn add_photo_folder(tx: &Transaction) -> rusqlite::Result<()> {
+    // Builds between folder scoping and versioned migrations created the column unversioned
+    let has_folder = tx
+        .prepare("SELECT 1 FROM pragma_table_info('photos') WHERE name = 'folder'")?
+        .exists([])?;
+    if !has_folder {
+        tx.execute_batch("ALTER TABLE photos ADD COLUMN folder TEXT NOT NULL DEFAULT ''")?;
+    }
+
+    let paths: Vec<(i64, String)> = tx
+        .prepare("SELECT id, path FROM photos")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    {
+        let mut stmt = tx.prepare("UPDATE photos SET folder = ?1 WHERE id = ?2")?;
+        for (id, path) in &paths {
+            stmt.execute(params![parent_folder(path), id])?;
+        }
+    }
+
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS idx_photos_folder ON photos(folder)")
+}
+
+/// Version 3: searchable EXIF fields, one row per photo, filled at import time.
+fn add_exif_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE exif (
+            photo_id INTEGER PRIMARY KEY,
+            camera_make TEXT,
+            camera_model TEXT,
+            lens_model TEXT,
+            date_taken TEXT,
+            iso INTEGER,
+            f_number REAL,
+            exposure_time REAL,
+            focal_length REAL,
+            gps_latitude REAL,
+            gps_longitude REAL,
+            software TEXT,
+            orientation INTEGER,
+            FOREIGN KEY(photo_id) REFERENCES photos(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX idx_exif_date_taken ON exif(date_taken);
+        CREATE INDEX idx_exif_camera_model ON exif(camera_model);
+        CREATE INDEX idx_exif_iso ON exif(iso);",
+    )
+}
+
+/// Statements that rebuild the `photos_fts` row of the photo with id `{id}`.
+/// The file name is the part of `path` after `folder` and its separator. Location is left
+/// out: the library stores only GPS coordinates, no place names, and `has:gps` covers them.
+fn fts_refresh_sql(id: &str) -> String {
+    format!(
+        "DELETE FROM photos_fts WHERE rowid = {id};
+        INSERT INTO photos_fts (rowid, name, tags, metadata, folder)
+            SELECT p.id,
+                   substr(p.path, length(p.folder) + 2),
+                   (SELECT group_concat(t.tag, ' ') FROM tags t WHERE t.photo_id = p.id),
+                   (SELECT concat_ws(' ', e.camera_make, e.camera_model, e.lens_model, e.software)
+                      FROM exif e WHERE e.photo_id = p.id),
+                   p.folder
+            FROM photos p WHERE p.id = {id};",
+        id = id
+    )
+}
+
+/// Version 4: FTS5 index over file name, tags, EXIF text and folder, one row per photo
+/// (rowid = photo id). Triggers keep it in sync with every write to the source tables.
+fn add_photo_fts(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE photos_fts USING fts5(
+            name, tags, metadata, folder,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );",
+    )?;
+
+    let triggers = [
+        ("photos_fts_photo_insert", "AFTER INSERT ON photos", "NEW.id"),
+        ("photos_fts_photo_update", "AFTER UPDATE OF path, folder ON photos", "NEW.id"),
+        ("photos_fts_tag_insert", "AFTER INSERT ON tags", "NEW.photo_id"),
+        ("photos_fts_tag_update", "AFTER UPDATE ON tags", "NEW.photo_id"),
+        ("photos_fts_tag_delete", "AFTER DELETE ON tags", "OLD.photo_id"),
+        ("photos_fts_exif_insert", "AFTER INSERT ON exif", "NEW.photo_id"),
+        ("photos_fts_exif_update", "AFTER UPDATE ON exif", "NEW.photo_id"),
+        ("photos_fts_exif_delete", "AFTER DELETE ON exif", "OLD.photo_id"),
+    ];
+    for (name, event, id) in triggers {
+        tx.execute_batch(&format!(
+            "CREATE TRIGGER {} {} BEGIN {} END;",
+            name,
+            event,
+            fts_refresh_sql(id)
+        ))?;
+    }
+    tx.execute_batch(
+        "CREATE TRIGGER photos_fts_photo_delete AFTER DELETE ON photos BEGIN
+            DELETE FROM photos_fts WHERE rowid = OLD.id;
+        END;",
+    )?;
+
+    // Index the existing library
+    tx.execute_batch(
+        "INSERT INTO photos_fts (rowid, name, tags, metadata, folder)
+            SELECT p.id,
+                   substr(p.path, length(p.folder) + 2),
+                   (SELECT group_concat(t.tag, ' ') FROM tags t WHERE t.photo_id = p.id),
+                   (SELECT concat_ws(' ', e.camera_make, e.camera_model, e.lens_model, e.software)
+                      FROM exif e WHERE e.photo_id = p.id),
+                   p.folder
+            FROM photos p;",
+    )
+}
+
+/// Version 5: where each tag came from — `manual`, or the classifier model that predicted it.
+/// Tags written before this version were all predicted by some model; they keep the legacy
+/// source `model` until the next classification replaces them.
+fn add_tag_source(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE tags ADD COLUMN source TEXT NOT NULL DEFAULT 'model';
+
+        DELETE FROM tags WHERE id NOT IN (
+            SELECT MIN(id) FROM tags GROUP BY photo_id, tag, source
+        );
+
+        CREATE UNIQUE INDEX idx_tags_photo_tag_source ON tags(photo_id, tag, source);
+        CREATE INDEX idx_tags_tag ON tags(tag);",
+    )
+}
+
+/// Version 6: culling decisions — star rating (0-5), color label and pick/reject flag
+/// (1 pick, 0 none, -1 reject). They live on the photo row so moves and renames keep them.
+fn add_photo_culling(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE photos ADD COLUMN rating INTEGER NOT NULL DEFAULT 0 CHECK (rating BETWEEN 0 AND 5);
+        ALTER TABLE photos ADD COLUMN color_label TEXT;
+        ALTER TABLE photos ADD COLUMN flag INTEGER NOT NULL DEFAULT 0 CHECK (flag IN (-1, 0, 1));
+
+        CREATE INDEX idx_photos_rating ON photos(rating);
+        CREATE INDEX idx_photos_flag ON photos(flag);",
+    )
+}
+
+/// Version 7: albums, named and ordered collections of photos that can span folders.
+/// Members reference photo ids, so moves that keep the photo row keep the membership.
+fn add_albums(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE albums (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created INTEGER NOT NULL
+        );
+
+        CREATE TABLE album_photos (
+            album_id INTEGER NOT NULL,
+            photo_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (album_id, photo_id),
+            FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE,
+            FOREIGN KEY(photo_id) REFERENCES photos(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX idx_album_photos_position ON album_photos(album_id, position);
+        CREATE INDEX idx_album_photos_photo ON album_photos(photo_id);",
+    )
+}
+
+/// Version 8: saved queries. `query` holds the `PhotoQueryParams` as JSON; pinned queries
+/// are listed as smart albums with the count from their last run.
+fn add_saved_queries(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE saved_queries (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            query TEXT NOT NULL,
+            pinned INTEGER NOT NULL DEFAULT 0,
+            cached_count INTEGER,
+            created INTEGER NOT NULL
+        );",
+    )
+}
+
+/// Version 9: content fingerprint per photo, and `removed_at` for photos whose file
+/// disappeared. Removed rows keep their tags, thumbnail and embedding for a while, so a file
+/// that reappears under another path with the same fingerprint is re-linked to them.
+fn add_photo_fingerprint(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE photos ADD COLUMN fingerprint TEXT;
+        ALTER TABLE photos ADD COLUMN removed_at INTEGER;
+
+        CREATE INDEX idx_photos_fingerprint ON photos(fingerprint);",
+    )
+}
+
+/// Version 10: BLAKE3 hash of the whole file, computed on demand by the duplicate finder and
+/// cleared whenever the file changes. Only photos sharing a size are ever hashed.
+fn add_photo_content_hash(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE photos ADD COLUMN content_hash TEXT;
+
+        CREATE INDEX idx_photos_size ON photos(size);",
+    )
+}
+
+/// Version 11: 64-bit perceptual hash (dHash) of the photo's thumbnail, stored as a signed
+/// integer. Computed in the background after import and cleared when the file changes.
+fn add_perceptual_hash(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE photos ADD COLUMN perceptual_hash INTEGER;")
+}
+
+/// Version 12: stacks, which collapse a burst or series into one cover photo. A photo belongs
+/// to at most one stack; a stack whose cover is gone falls back to another member.
+/// Also the fraction of a second of the capture time, to order and split burst frames.
+fn add_stacks(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE stacks (
+            id INTEGER PRIMARY KEY,
+            cover_id INTEGER REFERENCES photos(id) ON DELETE SET NULL,
+            created INTEGER NOT NULL
+        );
+
+        ALTER TABLE photos ADD COLUMN stack_id INTEGER REFERENCES stacks(id) ON DELETE SET NULL;
+        CREATE INDEX idx_photos_stack ON photos(stack_id);
+
+        ALTER TABLE exif ADD COLUMN subsec_ms INTEGER;",
+    )
+}
+
+/// Version 13: `primary_id` links a companion file (the RAW of a RAW+JPEG pair) to the
+/// photo shown for the pair. Companions are hidden from listings and become standalone
+/// photos again when their primary is deleted.
+fn add_photo_pairs(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE photos ADD COLUMN primary_id INTEGER REFERENCES photos(id) ON DELETE SET NULL;
+        CREATE INDEX idx_photos_primary ON photos(primary_id);",
+    )
+}
+
+/// Version 14: thumbnails move from BLOBs to content-addressed files on disk. `thumbnail_cache`
+/// indexes the files by hash with their size and last access for LRU eviction, and
+/// `photo_thumbnails` maps photos to them (identical thumbnails share a file). The old table
+/// is kept as `legacy_thumbnails` until the app has moved its rows to disk; the trigger keeps
+/// it from handing out thumbnails of files modified in the meantime. `settings` holds app
+/// preferences such as the cache size limit.
+fn add_thumbnail_cache(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE thumbnail_cache (
+            hash TEXT PRIMARY KEY,
+            size INTEGER NOT NULL,
+            accessed INTEGER NOT NULL
+        );
+        CREATE INDEX idx_thumbnail_cache_accessed ON thumbnail_cache(accessed);
+
+        CREATE TABLE photo_thumbnails (
+            photo_id INTEGER PRIMARY KEY REFERENCES photos(id) ON DELETE CASCADE,
+            hash TEXT NOT NULL
+        );
+        CREATE INDEX idx_photo_thumbnails_hash ON photo_thumbnails(hash);
+
+        CREATE TABLE settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        ALTER TABLE thumbnails RENAME TO legacy_thumbnails;
+        CREATE TRIGGER legacy_thumbnails_stale AFTER UPDATE OF modified ON photos
+        BEGIN
+            DELETE FROM legacy_thumbnails WHERE photo_id = NEW.id;
+        END;",
+    )
+}
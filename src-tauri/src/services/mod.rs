@@ -2,6 +2,13 @@ pub mod classifier;
 pub mod exif_service;
 pub mod fs_service;
 pub mod thumbnail_service;
+pub mod thumbnail_cache;
 pub mod db;
+pub mod decode_service;
+pub mod icc_service;
+pub mod migrations;
+pub mod search_query;
 pub mod watcher;
 pub mod color_service;
+pub mod phash_service;
+pub mod raw_service;
@@ -0,0 +1,28 @@
+use crate::error::AppError;
+use crate::services::decode_service;
+use image::imageops::FilterType;
+
+/// Default Hamming distance (out of 64 bits) up to which two photos count as near-duplicates.
+pub const DEFAULT_NEAR_DUPLICATE_DISTANCE: u32 = 8;
+
+/// 64-bit difference hash (dHash) of an encoded image, normally the cached thumbnail.
+/// The image is reduced to 9x8 grayscale pixels and each bit records whether a pixel is
+/// brighter than its right neighbour, so resizing, recompression and small edits flip few bits.
+pub fn dhash(image_bytes: &[u8]) -> Result<u64, AppError> {
+    let img = decode_service::decode_bytes(image_bytes)?;
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | (left > right) as u64;
+        }
+    }
+    Ok(hash)
+}
+
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
@@ -0,0 +1,673 @@
+use crate::error::AppError;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+
+/// The largest JPEG preview embedded in a RAW file, with the orientation it should be shown in.
+pub struct RawPreview {
+    pub jpeg: Vec<u8>,
+    /// EXIF orientation (1-8) of the RAW file; previews are stored unrotated
+    pub orientation: u32,
+}
+
+/// Container layouts of the supported RAW formats.
+enum Container {
+    /// CR2, NEF, ARW, DNG, PEF, SRW, and ORF / RW2 with their own magic numbers
+    Tiff,
+    /// Canon CR3 (ISO base media file format)
+    Bmff,
+    /// Fujifilm RAF
+    Raf,
+}
+
+const TIFF_MAGICS: &[u16] = &[
+    42,     // TIFF
+    0x4F52, // Olympus "RO"
+    0x5352, // Olympus "RS"
+    0x0055, // Panasonic "U"
+];
+
+const RAF_SIGNATURE: &[u8] = b"FUJIFILMCCD-RAW";
+
+/// CR3 box holding the CMT* metadata boxes and the thumbnail.
+const CANON_METADATA_UUID: [u8; 16] = [
+    0x85, 0xc0, 0xb6, 0x87, 0x82, 0x0f, 0x11, 0xe0, 0x81, 0x11, 0xf4, 0xce, 0x46, 0x2b, 0x6a, 0x48,
+];
+/// CR3 box holding the PRVW preview, after 8 bytes of its own header.
+const CANON_PREVIEW_UUID: [u8; 16] = [
+    0xea, 0xf4, 0x2b, 0x5e, 0x1c, 0x98, 0x4b, 0x88, 0xb9, 0xfb, 0xb7, 0xdc, 0x40, 0x6e, 0x4d, 0x16,
+];
+
+// TIFF tags used to find previews
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_MAKER_NOTE: u16 = 0x927C;
+const TAG_INTEROP_IFD: u16 = 0xA005;
+/// Panasonic RW2: a complete JPEG (with its own EXIF) stored as a tag value
+const TAG_PANASONIC_JPEG: u16 = 0x002E;
+/// Olympus maker note: camera settings IFD with the preview's start and length
+const TAG_OLYMPUS_CAMERA_SETTINGS: u16 = 0x2010;
+const TAG_OLYMPUS_PREVIEW_START: u16 = 0x0101;
+const TAG_OLYMPUS_PREVIEW_LENGTH: u16 = 0x0102;
+
+/// IFDs visited per file, against loops and crafted files.
+const MAX_IFDS: usize = 32;
+
+/// Largest embedded preview read into memory.
+const MAX_PREVIEW_BYTES: usize = 64 * 1024 * 1024;
+/// Largest metadata block (CR3 CMT box, single EXIF value) read into memory.
+const MAX_METADATA_BYTES: usize = 1024 * 1024;
+/// Bytes read from the start of a RAF preview to find its EXIF segment.
+const PREVIEW_EXIF_BYTES: usize = 128 * 1024;
+/// Bytes searched for the start of the JPEG in a CR3 PRVW / THMB box.
+const PREVIEW_HEADER_BYTES: usize = 64;
+
+/// Tags left out when IFDs are copied for the EXIF reader: pointers to other IFDs, image
+/// data and previews, and maker notes.
+const NOT_COPIED: &[u16] = &[
+    TAG_STRIP_OFFSETS,
+    TAG_STRIP_BYTE_COUNTS,
+    TAG_SUB_IFDS,
+    TAG_JPEG_OFFSET,
+    TAG_JPEG_LENGTH,
+    TAG_EXIF_IFD,
+    TAG_GPS_IFD,
+    TAG_MAKER_NOTE,
+    TAG_INTEROP_IFD,
+    TAG_PANASONIC_JPEG,
+];
+
+/// Random access to the bytes RAW structures are parsed from: the file itself, read piece
+/// by piece, or a block of it already in memory.
+trait Source {
+    /// `len` bytes at `at`, or `None` if they are not all there.
+    fn read(&self, at: usize, len: usize) -> Option<Cow<'_, [u8]>>;
+    fn size(&self) -> usize;
+}
+
+impl Source for [u8] {
+    fn read(&self, at: usize, len: usize) -> Option<Cow<'_, [u8]>> {
+        self.get(at..at.checked_add(len)?).map(Cow::Borrowed)
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+/// A RAW file read with seeks and bounded reads, so only its headers, IFDs and the chosen
+/// preview are loaded rather than the whole (often 50+ MB) file.
+struct RawFile {
+    file: File,
+    size: usize,
+}
+
+impl RawFile {
+    fn open(path: &Path) -> Result<Self, AppError> {
+        let open = || -> std::io::Result<Self> {
+            let file = File::open(path)?;
+            let size = file.metadata()?.len() as usize;
+            Ok(Self { file, size })
+        };
+        open().map_err(|e| AppError {
+            message: format!("Failed to read {}: {}", path.display(), e),
+            ..Default::default()
+        })
+    }
+}
+
+impl Source for RawFile {
+    fn read(&self, at: usize, len: usize) -> Option<Cow<'_, [u8]>> {
+        if at.checked_add(len)? > self.size {
+            return None;
+        }
+        let mut buf = vec![0; len];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(at as u64)).ok()?;
+        file.read_exact(&mut buf).ok()?;
+        Some(Cow::Owned(buf))
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// A metadata block of the file, e.g. a CR3 CMT box, as a buffer of its own.
+fn read_block<S: Source + ?Sized>(data: &S, range: Range<usize>) -> Option<Vec<u8>> {
+    if range.len() > MAX_METADATA_BYTES {
+        return None;
+    }
+    data.read(range.start, range.len()).map(Cow::into_owned)
+}
+
+fn container<S: Source + ?Sized>(data: &S) -> Option<Container> {
+    let header = data.read(0, data.size().min(16))?;
+    if header.starts_with(RAF_SIGNATURE) {
+        return Some(Container::Raf);
+    }
+    if header.get(4..8) == Some(b"ftyp") {
+        return Some(Container::Bmff);
+    }
+    let tiff = Tiff::new(data, 0)?;
+    TIFF_MAGICS.contains(&tiff.u16(2)?).then_some(Container::Tiff)
+}
+
+fn unsupported(path: &Path) -> AppError {
+    format!("No embedded preview found in {}", path.display()).into()
+}
+
+/// Extract the largest embedded JPEG preview of the RAW file at `path`. Only the structures
+/// leading to the previews and the chosen preview itself are read.
+pub fn extract_preview(path: &Path) -> Result<RawPreview, AppError> {
+    let file = RawFile::open(path)?;
+    let mut previews = Vec::new();
+    let mut orientation = None;
+    match container(&file).ok_or_else(|| unsupported(path))? {
+        Container::Tiff => {
+            let tiff = Tiff::new(&file, 0).ok_or_else(|| unsupported(path))?;
+            orientation = tiff.orientation();
+            tiff.collect_previews(&mut previews);
+        }
+        Container::Bmff => {
+            let boxes = Cr3Boxes::scan(&file);
+            let cmt1 = boxes.cmt1.and_then(|cmt1| read_block(&file, cmt1));
+            orientation = cmt1.and_then(|cmt1| Tiff::new(&cmt1[..], 0)?.orientation());
+            previews = boxes.previews;
+        }
+        Container::Raf => previews.extend(raf_preview(&file)),
+    }
+
+    let range = previews
+        .into_iter()
+        .filter_map(|range| {
+            let (width, height) = jpeg_dimensions(&file, range.clone())?;
+            Some((width as u64 * height as u64, range))
+        })
+        .max_by_key(|(area, _)| *area)
+        .map(|(_, range)| range)
+        .ok_or_else(|| unsupported(path))?;
+    if range.len() > MAX_PREVIEW_BYTES {
+        return Err(format!("Embedded preview of {} is too large", path.display()).into());
+    }
+    let jpeg = file
+        .read(range.start, range.len())
+        .ok_or_else(|| unsupported(path))?
+        .into_owned();
+
+    // Fall back to the preview's own EXIF (RAF and RW2 previews carry one)
+    let orientation = orientation.or_else(|| jpeg_orientation(&jpeg)).unwrap_or(1);
+    Ok(RawPreview { jpeg, orientation })
+}
+
+/// Read the EXIF of the RAW file at `path`. TIFF-based formats have IFD0 with its EXIF and
+/// GPS IFDs copied into a small TIFF of their own, CR3 its CMT1 and CMT2 boxes joined the
+/// same way, and RAF the start of its preview read.
+pub fn load_exif(path: &Path) -> Result<exif::Exif, AppError> {
+    let file = RawFile::open(path)?;
+    let result = match container(&file).ok_or_else(|| unsupported(path))? {
+        Container::Tiff => {
+            let tiff = Tiff::new(&file, 0).ok_or_else(|| unsupported(path))?;
+            let ifd0 = tiff.first_ifd().ok_or_else(|| unsupported(path))?;
+            let (entries, _) = tiff.ifd(ifd0).ok_or_else(|| unsupported(path))?;
+            let pointer = |tag: u16| {
+                let offset = tiff.uint(entries.iter().find(|e| e.tag == tag)?)?;
+                Some((&tiff, offset as usize))
+            };
+            let (exif, gps) = (pointer(TAG_EXIF_IFD), pointer(TAG_GPS_IFD));
+            let raw = exif_tiff((&tiff, ifd0), exif, gps).ok_or_else(|| unsupported(path))?;
+            exif::Reader::new().read_raw(raw)
+        }
+        Container::Bmff => {
+            let boxes = Cr3Boxes::scan(&file);
+            let cmt1 = boxes
+                .cmt1
+                .and_then(|cmt1| read_block(&file, cmt1))
+                .ok_or_else(|| unsupported(path))?;
+            let cmt2 = boxes.cmt2.and_then(|cmt2| read_block(&file, cmt2));
+            let ifd0 = Tiff::new(&cmt1[..], 0).ok_or_else(|| unsupported(path))?;
+            let exif = cmt2.as_deref().and_then(|cmt2| Tiff::new(cmt2, 0));
+            let raw = exif_tiff(
+                (&ifd0, ifd0.first_ifd().ok_or_else(|| unsupported(path))?),
+                exif.as_ref().and_then(|exif| Some((exif, exif.first_ifd()?))),
+                None,
+            )
+            .ok_or_else(|| unsupported(path))?;
+            exif::Reader::new().read_raw(raw)
+        }
+        Container::Raf => {
+            // The EXIF segment comes first in the preview
+            let head = raf_preview(&file)
+                .and_then(|range| file.read(range.start, range.len().min(PREVIEW_EXIF_BYTES)))
+                .ok_or_else(|| unsupported(path))?;
+            exif::Reader::new().read_from_container(&mut Cursor::new(&head[..]))
+        }
+    };
+    result.map_err(|e| AppError {
+        message: format!("Failed to read EXIF data: {}", e),
+        ..Default::default()
+    })
+}
+
+/// RAF header: big-endian offset and length of the preview JPEG at bytes 84 and 88.
+fn raf_preview<S: Source + ?Sized>(data: &S) -> Option<Range<usize>> {
+    let header = data.read(84, 8)?;
+    let offset = u32::from_be_bytes(header[0..4].try_into().ok()?) as usize;
+    let length = u32::from_be_bytes(header[4..8].try_into().ok()?) as usize;
+    Some(offset..offset.checked_add(length)?)
+}
+
+/// Width and height of a baseline or progressive JPEG at `jpeg`, read marker by marker.
+/// `None` for anything else, including the lossless JPEG that some RAW formats use for the
+/// sensor data itself.
+fn jpeg_dimensions<S: Source + ?Sized>(data: &S, jpeg: Range<usize>) -> Option<(u32, u32)> {
+    let read = |at: usize, len: usize| data.read(at, len).filter(|_| at + len <= jpeg.end);
+    if *read(jpeg.start, 2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    let mut at = jpeg.start + 2;
+    loop {
+        let header = read(at, 4)?;
+        if header[0] != 0xFF {
+            return None;
+        }
+        let marker = header[1];
+        if marker == 0xFF {
+            at += 1;
+            continue;
+        }
+        match marker {
+            0xC0..=0xC2 => {
+                let sof = read(at + 5, 4)?;
+                let height = u16::from_be_bytes([sof[0], sof[1]]) as u32;
+                let width = u16::from_be_bytes([sof[2], sof[3]]) as u32;
+                return (width > 0 && height > 0).then_some((width, height));
+            }
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF | 0xDA => return None,
+            _ => at += 2 + u16::from_be_bytes([header[2], header[3]]) as usize,
+        }
+    }
+}
+
+fn jpeg_orientation(jpeg: &[u8]) -> Option<u32> {
+    let exif = exif::Reader::new().read_from_container(&mut Cursor::new(jpeg)).ok()?;
+    exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?.value.get_uint(0)
+}
+
+/// A TIFF structure in `data` starting at `base`; offsets inside it are relative to `base`.
+struct Tiff<'a, S: Source + ?Sized> {
+    data: &'a S,
+    base: usize,
+    big_endian: bool,
+}
+
+/// One IFD entry; `value` is the raw value field, holding the value or its offset.
+#[derive(Clone, Copy)]
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: [u8; 4],
+}
+
+fn type_size(kind: u16) -> Option<usize> {
+    match kind {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+impl<'a, S: Source + ?Sized> Tiff<'a, S> {
+    /// Byte order from the "II" / "MM" mark at `base`.
+    fn new(data: &'a S, base: usize) -> Option<Self> {
+        let big_endian = match &*data.read(base, 2)? {
+            b"II" => false,
+            b"MM" => true,
+            _ => return None,
+        };
+        Some(Self { data, base, big_endian })
+    }
+
+    fn read(&self, offset: usize, len: usize) -> Option<Cow<'a, [u8]>> {
+        self.data.read(self.base.checked_add(offset)?, len)
+    }
+
+    fn to_u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+    }
+
+    fn to_u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        self.read(offset, 2).map(|bytes| self.to_u16(&bytes))
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        self.read(offset, 4).map(|bytes| self.to_u32(&bytes))
+    }
+
+    fn first_ifd(&self) -> Option<usize> {
+        self.u32(4).map(|offset| offset as usize)
+    }
+
+    /// Entries of the IFD at `offset` and the offset of the next IFD (0 at the end). The
+    /// entry table is read in one go.
+    fn ifd(&self, offset: usize) -> Option<(Vec<Entry>, usize)> {
+        let count = self.u16(offset)? as usize;
+        let table = self.read(offset + 2, count * 12)?;
+        let entries = table
+            .chunks_exact(12)
+            .map(|entry| Entry {
+                tag: self.to_u16(&entry[0..2]),
+                kind: self.to_u16(&entry[2..4]),
+                count: self.to_u32(&entry[4..8]),
+                value: [entry[8], entry[9], entry[10], entry[11]],
+            })
+            .collect();
+        let next = self.u32(offset + 2 + count * 12).unwrap_or(0) as usize;
+        Some((entries, next))
+    }
+
+    /// First value of a SHORT, LONG or IFD entry.
+    fn uint(&self, entry: &Entry) -> Option<u32> {
+        match entry.kind {
+            3 => Some(self.to_u16(&entry.value) as u32),
+            4 | 13 => Some(self.to_u32(&entry.value)),
+            _ => None,
+        }
+    }
+
+    /// All values of a LONG or IFD entry, e.g. the offsets of SubIFDs.
+    fn uints(&self, entry: &Entry) -> Vec<u32> {
+        if entry.count <= 1 {
+            return self.uint(entry).into_iter().collect();
+        }
+        let offset = self.uint(&Entry { kind: 4, ..*entry }).unwrap_or(0) as usize;
+        let count = (entry.count as usize).min(MAX_IFDS);
+        match self.read(offset, count * 4) {
+            Some(values) => values.chunks_exact(4).map(|value| self.to_u32(value)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Absolute byte range of an entry's value.
+    fn value_range(&self, entry: &Entry, field: usize) -> Option<Range<usize>> {
+        let len = type_size(entry.kind)?.checked_mul(entry.count as usize)?;
+        let start = if len <= 4 {
+            field
+        } else {
+            self.base.checked_add(self.uint(&Entry { kind: 4, ..*entry })? as usize)?
+        };
+        Some(start..start.checked_add(len)?)
+    }
+
+    fn orientation(&self) -> Option<u32> {
+        let (entries, _) = self.ifd(self.first_ifd()?)?;
+        let entry = entries.iter().find(|e| e.tag == TAG_ORIENTATION)?;
+        self.uint(entry).filter(|o| (1..=8).contains(o))
+    }
+
+    /// Byte ranges of every JPEG the IFDs point at, following IFD chains, SubIFDs, the EXIF
+    /// IFD and the Olympus maker note.
+    fn collect_previews(&self, previews: &mut Vec<Range<usize>>) {
+        let mut pending: Vec<usize> = self.first_ifd().into_iter().collect();
+        let mut visited = Vec::new();
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || visited.contains(&offset) || visited.len() >= MAX_IFDS {
+                continue;
+            }
+            visited.push(offset);
+            let Some((entries, next)) = self.ifd(offset) else {
+                continue;
+            };
+            pending.push(next);
+
+            let find = |tag: u16| entries.iter().find(|e| e.tag == tag);
+            let uint = |tag: u16| find(tag).and_then(|e| self.uint(e)).map(|v| v as usize);
+            let range = |start: usize, len: usize| {
+                let start = self.base.checked_add(start)?;
+                Some(start..start.checked_add(len)?)
+            };
+
+            if let (Some(start), Some(len)) = (uint(TAG_JPEG_OFFSET), uint(TAG_JPEG_LENGTH)) {
+                previews.extend(range(start, len));
+            }
+            // A JPEG-compressed image stored in a single strip (CR2, DNG)
+            if matches!(uint(TAG_COMPRESSION), Some(6 | 7 | 34892))
+                && find(TAG_STRIP_OFFSETS).is_some_and(|e| e.count == 1)
+            {
+                if let (Some(start), Some(len)) = (uint(TAG_STRIP_OFFSETS), uint(TAG_STRIP_BYTE_COUNTS)) {
+                    previews.extend(range(start, len));
+                }
+            }
+            for (i, entry) in entries.iter().enumerate() {
+                let field = self.base + offset + 2 + i * 12 + 8;
+                match entry.tag {
+                    TAG_PANASONIC_JPEG => previews.extend(self.value_range(entry, field)),
+                    TAG_SUB_IFDS => pending.extend(self.uints(entry).into_iter().map(|o| o as usize)),
+                    TAG_EXIF_IFD => pending.extend(self.uint(entry).map(|o| o as usize)),
+                    TAG_MAKER_NOTE => {
+                        if let Some(note) = self.value_range(entry, field) {
+                            olympus_preview(self.data, note.start, previews);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Olympus maker note ("OLYMPUS\0" + TIFF byte order); offsets are relative to its start.
+fn olympus_preview<S: Source + ?Sized>(data: &S, start: usize, previews: &mut Vec<Range<usize>>) -> Option<()> {
+    if *data.read(start, 8)? != *b"OLYMPUS\0" {
+        return None;
+    }
+    let note = Tiff { base: start, ..Tiff::new(data, start + 8)? };
+    let (entries, _) = note.ifd(12)?;
+    let settings = entries.iter().find(|e| e.tag == TAG_OLYMPUS_CAMERA_SETTINGS)?;
+    let (entries, _) = note.ifd(note.uint(settings)? as usize)?;
+    let uint = |tag: u16| entries.iter().find(|e| e.tag == tag).and_then(|e| note.uint(e));
+    let preview_start = start.checked_add(uint(TAG_OLYMPUS_PREVIEW_START)? as usize)?;
+    let len = uint(TAG_OLYMPUS_PREVIEW_LENGTH)? as usize;
+    previews.push(preview_start..preview_start.checked_add(len)?);
+    Some(())
+}
+
+/// What a CR3 file's boxes hold: candidate JPEGs (PRVW, THMB and the first sample of every
+/// track, the full-size JPEG being one of them) and the CMT1 (IFD0) / CMT2 (EXIF) TIFFs.
+/// Only box headers are read; the media data is skipped over.
+#[derive(Default)]
+struct Cr3Boxes {
+    previews: Vec<Range<usize>>,
+    cmt1: Option<Range<usize>>,
+    cmt2: Option<Range<usize>>,
+}
+
+impl Cr3Boxes {
+    fn scan<S: Source + ?Sized>(data: &S) -> Self {
+        let mut boxes = Self::default();
+        boxes.walk(data, 0..data.size(), 0);
+        boxes
+    }
+
+    fn walk<S: Source + ?Sized>(&mut self, data: &S, range: Range<usize>, depth: u8) {
+        if depth > 8 {
+            return;
+        }
+        let mut at = range.start;
+        while at + 8 <= range.end {
+            let Some(header) = data.read(at, 8) else {
+                return;
+            };
+            let size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = header[4..8].try_into().unwrap();
+            let (header, size) = match size {
+                0 => (8, range.end - at),
+                1 => match data.read(at + 8, 8) {
+                    Some(large) => (16, u64::from_be_bytes((*large).try_into().unwrap()) as usize),
+                    None => return,
+                },
+                size => (8, size),
+            };
+            let end = match at.checked_add(size) {
+                Some(end) if size >= header && end <= range.end => end,
+                _ => return,
+            };
+            let payload = at + header..end;
+
+            match &kind {
+                b"moov" | b"trak" | b"mdia" | b"minf" => self.walk(data, payload, depth + 1),
+                b"stbl" => self.previews.extend(first_sample(data, payload)),
+                b"uuid" => {
+                    let uuid = data.read(payload.start, 16).filter(|_| payload.len() >= 16);
+                    if uuid.as_deref() == Some(&CANON_METADATA_UUID[..]) {
+                        self.walk(data, payload.start + 16..end, depth + 1);
+                    } else if uuid.as_deref() == Some(&CANON_PREVIEW_UUID[..]) {
+                        self.walk(data, payload.start + 24..end, depth + 1);
+                    }
+                }
+                b"PRVW" | b"THMB" => {
+                    // A few size fields precede the JPEG itself
+                    let prefix = data.read(payload.start, payload.len().min(PREVIEW_HEADER_BYTES));
+                    let soi = prefix.and_then(|prefix| prefix.windows(3).position(|w| w == [0xFF, 0xD8, 0xFF]));
+                    if let Some(soi) = soi {
+                        self.previews.push(payload.start + soi..end);
+                    }
+                }
+                b"CMT1" => self.cmt1 = Some(payload),
+                b"CMT2" => self.cmt2 = Some(payload),
+                _ => {}
+            }
+            at = end;
+        }
+    }
+}
+
+/// Byte range of the first sample of a track, from its sample table's `stsz` and `stco` / `co64`.
+fn first_sample<S: Source + ?Sized>(data: &S, stbl: Range<usize>) -> Option<Range<usize>> {
+    let be32 = |at: usize| data.read(at, 4).map(|b| u32::from_be_bytes((*b).try_into().unwrap()) as usize);
+    let mut size = None;
+    let mut offset = None;
+    let mut at = stbl.start;
+    while at + 8 <= stbl.end {
+        let box_size = be32(at)?;
+        if box_size < 8 {
+            return None;
+        }
+        // Full boxes: version and flags, then the fields
+        let fields = at + 12;
+        match &*data.read(at + 4, 4)? {
+            b"stsz" => {
+                let fixed = be32(fields)?;
+                size = Some(if fixed > 0 { fixed } else { be32(fields + 8)? });
+            }
+            b"stco" => offset = Some(be32(fields + 4)?),
+            b"co64" => {
+                let bytes = data.read(fields + 4, 8)?;
+                offset = Some(u64::from_be_bytes((*bytes).try_into().unwrap()) as usize);
+            }
+            _ => {}
+        }
+        at += box_size;
+    }
+    let (offset, size) = (offset?, size?);
+    Some(offset..offset.checked_add(size)?)
+}
+
+/// Copy IFD0 and, when given, its EXIF and GPS IFDs into a TIFF of their own for the EXIF
+/// reader. Out-of-line values move with their entries; image data, previews and maker notes
+/// are left behind, so only IFD-sized reads are made. The IFDs may come from different
+/// TIFFs (CR3 keeps IFD0 and the EXIF IFD in separate boxes) but must share a byte order.
+fn exif_tiff<S: Source + ?Sized>(
+    ifd0: (&Tiff<S>, usize),
+    exif: Option<(&Tiff<S>, usize)>,
+    gps: Option<(&Tiff<S>, usize)>,
+) -> Option<Vec<u8>> {
+    let big_endian = ifd0.0.big_endian;
+    let u32_bytes = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+    let mut out = if big_endian { b"MM\0\x2a\0\0\0\0".to_vec() } else { b"II\x2a\0\0\0\0\0".to_vec() };
+
+    let mut ifd0_entries = copy_entries(&mut out, ifd0.0, ifd0.1)?;
+    for (tag, ifd) in [(TAG_EXIF_IFD, exif), (TAG_GPS_IFD, gps)] {
+        let Some((tiff, offset)) = ifd.filter(|(tiff, _)| tiff.big_endian == big_endian) else {
+            continue;
+        };
+        let Some(mut entries) = copy_entries(&mut out, tiff, offset) else {
+            continue;
+        };
+        entries.sort_by_key(|e| e.tag);
+        let at = write_ifd(&mut out, &entries, big_endian);
+        ifd0_entries.push(Entry { tag, kind: 4, count: 1, value: u32_bytes(at) });
+    }
+    ifd0_entries.sort_by_key(|e| e.tag);
+    let ifd0_at = write_ifd(&mut out, &ifd0_entries, big_endian);
+    out[4..8].copy_from_slice(&u32_bytes(ifd0_at));
+    Some(out)
+}
+
+/// Entries of the IFD at `offset` worth copying, with their out-of-line values appended to
+/// `out` and the entries pointed at them.
+fn copy_entries<S: Source + ?Sized>(out: &mut Vec<u8>, tiff: &Tiff<S>, offset: usize) -> Option<Vec<Entry>> {
+    let (entries, _) = tiff.ifd(offset)?;
+    let u32_bytes = |v: u32| if tiff.big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+    let mut copied = Vec::with_capacity(entries.len());
+    for entry in entries.into_iter().filter(|e| !NOT_COPIED.contains(&e.tag)) {
+        let Some(len) = type_size(entry.kind).and_then(|size| size.checked_mul(entry.count as usize)) else {
+            continue;
+        };
+        if len <= 4 {
+            copied.push(entry);
+            continue;
+        }
+        let value = match tiff.uint(&Entry { kind: 4, ..entry }) {
+            Some(at) if len <= MAX_METADATA_BYTES => tiff.read(at as usize, len),
+            _ => None,
+        };
+        let Some(value) = value else {
+            continue;
+        };
+        if out.len() % 2 == 1 {
+            out.push(0);
+        }
+        let at = out.len() as u32;
+        out.extend_from_slice(&value);
+        copied.push(Entry { value: u32_bytes(at), ..entry });
+    }
+    Some(copied)
+}
+
+/// Append an IFD (word-aligned, no next IFD) and return its offset.
+fn write_ifd(out: &mut Vec<u8>, entries: &[Entry], big_endian: bool) -> u32 {
+    if out.len() % 2 == 1 {
+        out.push(0);
+    }
+    let at = out.len() as u32;
+    let u16_bytes = |v: u16| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+    let u32_bytes = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+    out.extend_from_slice(&u16_bytes(entries.len() as u16));
+    for entry in entries {
+        out.extend_from_slice(&u16_bytes(entry.tag));
+        out.extend_from_slice(&u16_bytes(entry.kind));
+        out.extend_from_slice(&u32_bytes(entry.count));
+        out.extend_from_slice(&entry.value);
+    }
+    out.extend_from_slice(&u32_bytes(0));
+    at
+}
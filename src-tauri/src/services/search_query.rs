@@ -0,0 +1,351 @@
+use crate::error::AppError;
+use crate::models::culling_types::{ColorLabel, PhotoFlag};
+
+/// Parsed search box input, e.g.
+/// `tag:dog -tag:cat iso:>1600 date:2023-06..2023-08 camera:"X-T4" w:>4000 has:gps similar:IMG_0042.jpg`
+/// or `rating:>=4 label:green -flag:reject`.
+/// Every clause must match. Words without a field go to the full-text index.
+#[derive(Debug, Default)]
+pub struct SearchQuery {
+    pub clauses: Vec<Clause>,
+}
+
+/// One whitespace-separated part of the query. A leading `-` negates it.
+#[derive(Debug)]
+pub struct Clause {
+    pub negated: bool,
+    pub term: Term,
+}
+
+#[derive(Debug)]
+pub enum Term {
+    /// Free text, matched as a prefix against file name, tags and metadata.
+    Text(String),
+    Tag(String),
+    Camera(String),
+    Lens(String),
+    Iso(Condition<f64>),
+    FNumber(Condition<f64>),
+    FocalLength(Condition<f64>),
+    Width(Condition<f64>),
+    Height(Condition<f64>),
+    /// File size in bytes.
+    Size(Condition<f64>),
+    /// Date taken. Values are `YYYY`, `YYYY-MM` or `YYYY-MM-DD` and compare as prefixes,
+    /// so `date:2023-06` matches the whole month.
+    Date(Condition<String>),
+    /// Star rating, 0 (unrated) to 5.
+    Rating(Condition<f64>),
+    Label(ColorLabel),
+    Flag(PhotoFlag),
+    Has(Presence),
+    /// File name (or full path) of the reference photo.
+    Similar(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CmpOp {
+    pub fn sql(self) -> &'static str {
+        match self {
+            CmpOp::Eq => "=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+        }
+    }
+}
+
+/// `>1600`, `<=8`, `100` or the inclusive range `100..400`.
+#[derive(Debug)]
+pub enum Condition<T> {
+    Cmp(CmpOp, T),
+    Range(T, T),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Presence {
+    Gps,
+    Tags,
+    Exif,
+    Embedding,
+}
+
+/// Field names recognized before a `:`. Any other `word:` prefix is searched as text.
+const FIELDS: &[&str] = &[
+    "tag", "camera", "lens", "iso", "f", "aperture", "focal", "w", "width", "h", "height", "size", "date",
+    "rating", "stars", "label", "flag", "has", "similar",
+];
+
+/// Parse search box input. Errors carry the character position of the offending token.
+pub fn parse(input: &str) -> Result<SearchQuery, AppError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut query = SearchQuery::default();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if chars[pos].is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        let negated = chars[pos] == '-' && chars.get(pos + 1).is_some_and(|c| !c.is_whitespace());
+        if negated {
+            pos += 1;
+        }
+
+        // A field name is one of `FIELDS` directly followed by ':'
+        let key_start = pos;
+        while pos < chars.len() && chars[pos].is_ascii_alphabetic() {
+            pos += 1;
+        }
+        let key: String = chars[key_start..pos].iter().collect::<String>().to_lowercase();
+        let term = if chars.get(pos) == Some(&':') && FIELDS.contains(&key.as_str()) {
+            pos += 1;
+            let value_start = pos;
+            let value = read_value(&chars, &mut pos)?;
+            if value.is_empty() {
+                return Err(parse_error(format!("Missing value for '{}:'", key), value_start));
+            }
+            parse_field(&key, &value, value_start)?
+        } else {
+            // Plain words, including ones like `note:foo` or `10:30` that only look like a field
+            pos = key_start;
+            let value = read_value(&chars, &mut pos)?;
+            Term::Text(value)
+        };
+
+        if let Term::Text(text) = &term {
+            if text.is_empty() {
+                continue;
+            }
+        }
+        query.clauses.push(Clause { negated, term });
+    }
+
+    Ok(query)
+}
+
+/// Read a bare word or a `"quoted value"` starting at `pos`.
+fn read_value(chars: &[char], pos: &mut usize) -> Result<String, AppError> {
+    if chars.get(*pos) == Some(&'"') {
+        let quote = *pos;
+        *pos += 1;
+        let start = *pos;
+        while *pos < chars.len() && chars[*pos] != '"' {
+            *pos += 1;
+        }
+        if *pos == chars.len() {
+            return Err(parse_error("Unterminated quote".to_string(), quote));
+        }
+        let value = chars[start..*pos].iter().collect();
+        *pos += 1;
+        Ok(value)
+    } else {
+        let start = *pos;
+        while *pos < chars.len() && !chars[*pos].is_whitespace() {
+            *pos += 1;
+        }
+        Ok(chars[start..*pos].iter().collect())
+    }
+}
+
+/// Build the term for `key:value`. `key` is one of `FIELDS`.
+fn parse_field(key: &str, value: &str, value_pos: usize) -> Result<Term, AppError> {
+    let term = match key {
+        "tag" => Term::Tag(value.to_string()),
+        "camera" => Term::Camera(value.to_string()),
+        "lens" => Term::Lens(value.to_string()),
+        "iso" => Term::Iso(parse_condition(value, value_pos, parse_number)?),
+        "f" | "aperture" => Term::FNumber(parse_condition(value, value_pos, parse_number)?),
+        "focal" => Term::FocalLength(parse_condition(value, value_pos, parse_number)?),
+        "w" | "width" => Term::Width(parse_condition(value, value_pos, parse_number)?),
+        "h" | "height" => Term::Height(parse_condition(value, value_pos, parse_number)?),
+        "size" => Term::Size(parse_condition(value, value_pos, parse_size)?),
+        "date" => Term::Date(parse_condition(value, value_pos, parse_date)?),
+        "rating" | "stars" => Term::Rating(parse_condition(value, value_pos, parse_number)?),
+        "label" => Term::Label(ColorLabel::parse(value).ok_or_else(|| {
+            parse_error(
+                format!("Unknown color label '{}' (expected red, yellow, green, blue or purple)", value),
+                value_pos,
+            )
+        })?),
+        "flag" => Term::Flag(PhotoFlag::parse(value).ok_or_else(|| {
+            parse_error(
+                format!("Unknown flag '{}' (expected pick, reject or none)", value),
+                value_pos,
+            )
+        })?),
+        "has" => Term::Has(match value.to_lowercase().as_str() {
+            "gps" => Presence::Gps,
+            "tags" | "tag" => Presence::Tags,
+            "exif" => Presence::Exif,
+            "embedding" => Presence::Embedding,
+            _ => {
+                return Err(parse_error(
+                    format!("Unknown value '{}' for 'has:' (expected gps, tags, exif or embedding)", value),
+                    value_pos,
+                ))
+            }
+        }),
+        "similar" => Term::Similar(value.to_string()),
+        _ => unreachable!("'{}' is not in FIELDS", key),
+    };
+    Ok(term)
+}
+
+/// Parse `>x`, `>=x`, `<x`, `<=x`, `=x`, `x` or `a..b`, using `parse_value` for each operand.
+fn parse_condition<T>(
+    value: &str,
+    pos: usize,
+    parse_value: fn(&str, usize) -> Result<T, AppError>,
+) -> Result<Condition<T>, AppError> {
+    let (op, rest) = if let Some(rest) = value.strip_prefix(">=") {
+        (CmpOp::Ge, rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (CmpOp::Le, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (CmpOp::Gt, rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (CmpOp::Lt, rest)
+    } else if let Some(rest) = value.strip_prefix('=') {
+        (CmpOp::Eq, rest)
+    } else {
+        (CmpOp::Eq, value)
+    };
+    let rest_pos = pos + (value.chars().count() - rest.chars().count());
+
+    if op == CmpOp::Eq {
+        if let Some((from, to)) = rest.split_once("..") {
+            let to_pos = rest_pos + from.chars().count() + 2;
+            return Ok(Condition::Range(parse_value(from, rest_pos)?, parse_value(to, to_pos)?));
+        }
+    }
+    Ok(Condition::Cmp(op, parse_value(rest, rest_pos)?))
+}
+
+fn parse_number(value: &str, pos: usize) -> Result<f64, AppError> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .ok_or_else(|| parse_error(format!("Expected a number, found '{}'", value), pos))
+}
+
+/// Byte count with an optional `kb`/`mb`/`gb` suffix (binary units).
+fn parse_size(value: &str, pos: usize) -> Result<f64, AppError> {
+    let lower = value.to_lowercase();
+    let (number, factor) = [("gb", 1024f64.powi(3)), ("mb", 1024f64.powi(2)), ("kb", 1024.0), ("b", 1.0)]
+        .iter()
+        .find_map(|(suffix, factor)| lower.strip_suffix(suffix).map(|n| (n.to_string(), *factor)))
+        .unwrap_or((lower.clone(), 1.0));
+    parse_number(&number, pos)
+        .map(|n| n * factor)
+        .map_err(|_| parse_error(format!("Expected a size such as 500kb or 4mb, found '{}'", value), pos))
+}
+
+/// `YYYY`, `YYYY-MM` or `YYYY-MM-DD`, kept as text because dates are stored that way.
+fn parse_date(value: &str, pos: usize) -> Result<String, AppError> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let widths = [4, 2, 2];
+    let valid = !parts.is_empty()
+        && parts.len() <= widths.len()
+        && parts
+            .iter()
+            .zip(widths)
+            .all(|(part, width)| part.len() == width && part.chars().all(|c| c.is_ascii_digit()));
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(parse_error(
+            format!("Expected a date as YYYY, YYYY-MM or YYYY-MM-DD, found '{}'", value),
+            pos,
+        ))
+    }
+}
+
+fn parse_error(message: String, position: usize) -> AppError {
+    AppError {
+        message: format!("Invalid search at position {}: {}", position, message),
+        position: Some(position),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_position(input: &str) -> Option<usize> {
+        parse(input).expect_err("query should not parse").position
+    }
+
+    #[test]
+    fn parses_fields_and_conditions() {
+        let query = parse("tag:dog iso:>1600 date:2023-06..2023-08 size:4mb has:gps").unwrap();
+        let terms: Vec<&Term> = query.clauses.iter().map(|c| &c.term).collect();
+        assert!(matches!(terms[0], Term::Tag(tag) if tag == "dog"));
+        assert!(matches!(terms[1], Term::Iso(Condition::Cmp(CmpOp::Gt, n)) if *n == 1600.0));
+        assert!(matches!(terms[2], Term::Date(Condition::Range(from, to)) if from == "2023-06" && to == "2023-08"));
+        assert!(matches!(terms[3], Term::Size(Condition::Cmp(CmpOp::Eq, n)) if *n == 4.0 * 1024.0 * 1024.0));
+        assert!(matches!(terms[4], Term::Has(Presence::Gps)));
+    }
+
+    #[test]
+    fn field_names_are_case_insensitive() {
+        let query = parse("Rating:>=4 LABEL:green").unwrap();
+        assert!(matches!(query.clauses[0].term, Term::Rating(Condition::Cmp(CmpOp::Ge, n)) if n == 4.0));
+        assert!(matches!(query.clauses[1].term, Term::Label(ColorLabel::Green)));
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces() {
+        let query = parse(r#"camera:"X-T4 Pro" "golden hour""#).unwrap();
+        assert_eq!(query.clauses.len(), 2);
+        assert!(matches!(&query.clauses[0].term, Term::Camera(camera) if camera == "X-T4 Pro"));
+        assert!(matches!(&query.clauses[1].term, Term::Text(text) if text == "golden hour"));
+    }
+
+    #[test]
+    fn leading_dash_negates() {
+        let query = parse("-tag:cat -beach - sunset").unwrap();
+        let negated: Vec<bool> = query.clauses.iter().map(|c| c.negated).collect();
+        assert_eq!(negated, [true, true, false, false]);
+        assert!(matches!(&query.clauses[1].term, Term::Text(text) if text == "beach"));
+        assert!(matches!(&query.clauses[2].term, Term::Text(text) if text == "-"));
+    }
+
+    #[test]
+    fn unknown_prefixes_are_text() {
+        let query = parse("note:foo 10:30 -http://example.com").unwrap();
+        let texts: Vec<&str> = query
+            .clauses
+            .iter()
+            .map(|c| match &c.term {
+                Term::Text(text) => text.as_str(),
+                other => panic!("expected text, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(texts, ["note:foo", "10:30", "http://example.com"]);
+        assert!(query.clauses[2].negated);
+    }
+
+    #[test]
+    fn errors_report_the_offending_position() {
+        assert_eq!(error_position("iso:abc"), Some(4));
+        assert_eq!(error_position("dog tag:"), Some(8));
+        assert_eq!(error_position(r#"tag:"dog"#), Some(4));
+        assert_eq!(error_position("beach rating:>x"), Some(14));
+        assert_eq!(error_position("iso:100..x"), Some(9));
+        assert_eq!(error_position("label:pink"), Some(6));
+        assert_eq!(error_position("date:2023-6"), Some(5));
+    }
+}
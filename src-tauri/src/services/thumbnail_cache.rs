@@ -0,0 +1,222 @@
+use crate::error::AppError;
+use crate::models::thumbnail_types::ThumbnailCacheInfo;
+use crate::services::db::Database;
+use crate::services::thumbnail_service;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Settings key of the configured cache size.
+const MAX_BYTES_SETTING: &str = "thumbnail_cache_max_bytes";
+
+/// 2 GB holds the thumbnails of roughly 100,000 photos.
+pub const DEFAULT_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Eviction frees space down to this share of the cap, so it runs once per batch of new
+/// thumbnails rather than on every one once the cache is full.
+const EVICT_TO_PERCENT: u64 = 90;
+
+/// Thumbnails moved out of the database per transaction.
+const LEGACY_BATCH: usize = 500;
+
+/// Thumbnails as JPEG files under `<app data>/thumbnails`, named by the BLAKE3 hash of their
+/// bytes, so photos with identical thumbnails share one file. The database maps photos to
+/// hashes and keeps each file's size and last use; the least recently used files are
+/// deleted once the cache outgrows its size cap.
+#[derive(Clone)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    db: Database,
+    max_bytes: Arc<AtomicU64>,
+    /// Total size of the cached files, kept alongside the index to spare a query per insert
+    total_bytes: Arc<AtomicU64>,
+    /// Thumbnails stored as BLOBs by older versions are still being moved to disk
+    legacy_pending: Arc<AtomicBool>,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf, db: Database) -> Result<Self, AppError> {
+        let max_bytes = db
+            .get_setting(MAX_BYTES_SETTING)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BYTES);
+        let (_, total_bytes) = db.thumbnail_cache_size()?;
+        let legacy_pending = db.has_legacy_thumbnails()?;
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            db,
+            max_bytes: Arc::new(AtomicU64::new(max_bytes)),
+            total_bytes: Arc::new(AtomicU64::new(total_bytes)),
+            legacy_pending: Arc::new(AtomicBool::new(legacy_pending)),
+        })
+    }
+
+    /// The cached thumbnail of a photo, if any. Entries whose file has gone missing are
+    /// forgotten, so the thumbnail gets generated again.
+    pub fn get(&self, photo_id: i64) -> Option<Vec<u8>> {
+        if let Ok(Some(hash)) = self.db.use_thumbnail(photo_id) {
+            match std::fs::read(self.file_path(&hash)) {
+                Ok(bytes) => return Some(bytes),
+                Err(_) => {
+                    let _ = self.db.remove_photo_thumbnail(photo_id);
+                }
+            }
+        }
+        // Not moved to disk yet by `migrate_legacy`: move this one now
+        if self.legacy_pending.load(Ordering::Relaxed) {
+            if let Ok(Some(bytes)) = self.db.get_legacy_thumbnail(photo_id) {
+                if self.put(photo_id, &bytes).is_ok() {
+                    let _ = self.db.delete_legacy_thumbnails(&[photo_id]);
+                }
+                return Some(bytes);
+            }
+        }
+        None
+    }
+
+    /// The cached thumbnail of a photo, generated from the file at `path` and cached on a miss.
+    pub fn get_or_create(&self, photo_id: i64, path: &Path) -> Result<Vec<u8>, AppError> {
+        if let Some(bytes) = self.get(photo_id) {
+            return Ok(bytes);
+        }
+        let bytes = thumbnail_service::generate_thumbnail_bytes(path)?;
+        // A thumbnail that couldn't be cached is still worth showing
+        if let Err(e) = self.put(photo_id, &bytes) {
+            eprintln!("Thumbnail cache: failed to store thumbnail of {}: {}", path.display(), e.message);
+        }
+        Ok(bytes)
+    }
+
+    /// Store `bytes` as the thumbnail of a photo, evicting old thumbnails if the cache has
+    /// grown past its cap.
+    pub fn put(&self, photo_id: i64, bytes: &[u8]) -> Result<(), AppError> {
+        let hash = blake3::hash(bytes).to_hex().to_string();
+        let path = self.file_path(&hash);
+        if !path.exists() {
+            let parent = path.parent().unwrap_or(&self.dir);
+            std::fs::create_dir_all(parent)?;
+            // Written under a unique name and renamed, so readers never see a partial file
+            let tmp = tempfile_path(parent, &hash);
+            std::fs::write(&tmp, bytes)?;
+            if let Err(e) = std::fs::rename(&tmp, &path) {
+                let _ = std::fs::remove_file(&tmp);
+                return Err(e.into());
+            }
+        }
+
+        let added = self.db.add_thumbnail(photo_id, &hash, bytes.len() as u64)?;
+        if added {
+            let total = self.total_bytes.fetch_add(bytes.len() as u64, Ordering::Relaxed) + bytes.len() as u64;
+            if total > self.max_bytes.load(Ordering::Relaxed) {
+                self.evict()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete the least recently used thumbnails until the cache is below its cap.
+    fn evict(&self) -> Result<(), AppError> {
+        let target = self.max_bytes.load(Ordering::Relaxed) / 100 * EVICT_TO_PERCENT;
+        let evicted = self.db.evict_thumbnails(target)?;
+        for (hash, _) in &evicted {
+            let _ = std::fs::remove_file(self.file_path(hash));
+        }
+        self.sync_total();
+        Ok(())
+    }
+
+    /// Reload the total size from the index, which other threads may have changed meanwhile.
+    fn sync_total(&self) {
+        if let Ok((_, total)) = self.db.thumbnail_cache_size() {
+            self.total_bytes.store(total, Ordering::Relaxed);
+        }
+    }
+
+    pub fn info(&self) -> Result<ThumbnailCacheInfo, AppError> {
+        let (files, size_bytes) = self.db.thumbnail_cache_size()?;
+        Ok(ThumbnailCacheInfo {
+            files,
+            size_bytes,
+            max_bytes: self.max_bytes.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Change and persist the size cap, evicting right away if the cache is over the new one.
+    pub fn set_max_bytes(&self, max_bytes: u64) -> Result<(), AppError> {
+        self.db.set_setting(MAX_BYTES_SETTING, &max_bytes.to_string())?;
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
+        if self.total_bytes.load(Ordering::Relaxed) > max_bytes {
+            self.evict()?;
+        }
+        Ok(())
+    }
+
+    /// Delete every cached thumbnail, including any not yet moved out of the database.
+    /// They are generated again as photos are shown.
+    pub fn clear(&self) -> Result<(), AppError> {
+        self.db.clear_thumbnail_cache()?;
+        if self.legacy_pending.swap(false, Ordering::Relaxed) {
+            self.db.drop_legacy_thumbnails()?;
+        }
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)?;
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        self.sync_total();
+        Ok(())
+    }
+
+    /// Move thumbnails stored as BLOBs by older versions into the cache, then drop their
+    /// table and compact the database. Meant for a background thread at startup; photos
+    /// shown before their turn are moved by `get`. An interrupted run resumes next start.
+    pub fn migrate_legacy(&self) {
+        if !self.legacy_pending.load(Ordering::Relaxed) {
+            return;
+        }
+        loop {
+            let batch = match self.db.get_legacy_thumbnails(LEGACY_BATCH) {
+                Ok(batch) => batch,
+                Err(e) => {
+                    // Dropped by `clear` meanwhile, or a real error to retry next start
+                    if self.legacy_pending.load(Ordering::Relaxed) {
+                        eprintln!("Thumbnail cache: failed to read stored thumbnails: {}", e);
+                    }
+                    return;
+                }
+            };
+            if batch.is_empty() {
+                break;
+            }
+            let mut ids = Vec::with_capacity(batch.len());
+            for (photo_id, bytes) in batch {
+                // One that can't be moved is dropped; it gets generated again when shown
+                if let Err(e) = self.put(photo_id, &bytes) {
+                    eprintln!("Thumbnail cache: failed to move thumbnail of photo {}: {}", photo_id, e.message);
+                }
+                ids.push(photo_id);
+            }
+            if let Err(e) = self.db.delete_legacy_thumbnails(&ids) {
+                eprintln!("Thumbnail cache: failed to remove stored thumbnails: {}", e);
+                return;
+            }
+        }
+        if self.legacy_pending.swap(false, Ordering::Relaxed) {
+            if let Err(e) = self.db.drop_legacy_thumbnails() {
+                eprintln!("Thumbnail cache: failed to drop stored thumbnails: {}", e);
+            }
+        }
+    }
+
+    /// `<dir>/ab/abcdef....jpg`; the two-character fan-out keeps directories small.
+    fn file_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(&hash[..2]).join(format!("{}.jpg", hash))
+    }
+}
+
+/// A name in `dir` no other writer uses, for writing a file before renaming it into place.
+fn tempfile_path(dir: &Path, hash: &str) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!("{}.{}.{}.tmp", hash, std::process::id(), n))
+}
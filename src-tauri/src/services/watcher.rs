@@ -5,6 +5,7 @@ use tauri::{AppHandle, Emitter};
 struct WatcherState {
     _watcher: RecommendedWatcher,
     path: String,
+    recursive: bool,
 }
 
 pub struct FolderWatcher {
@@ -18,12 +19,12 @@ impl FolderWatcher {
         }
     }
 
-    pub fn watch_folder(&self, path: &str, app: AppHandle) {
+    pub fn watch_folder(&self, path: &str, recursive: bool, app: AppHandle) {
         let mut state = self.state.lock().unwrap();
 
-        // Already watching this path
+        // Already watching this path in the same mode
         if let Some(ref s) = *state {
-            if s.path == path {
+            if s.path == path && s.recursive == recursive {
                 return;
             }
         }
@@ -64,13 +65,19 @@ impl FolderWatcher {
 
         match watcher {
             Ok(mut w) => {
-                if let Err(e) = w.watch(&watch_path, RecursiveMode::NonRecursive) {
+                let mode = if recursive {
+                    RecursiveMode::Recursive
+                } else {
+                    RecursiveMode::NonRecursive
+                };
+                if let Err(e) = w.watch(&watch_path, mode) {
                     eprintln!("Failed to watch folder {}: {}", path, e);
                     return;
                 }
                 *state = Some(WatcherState {
                     _watcher: w,
                     path: path.to_string(),
+                    recursive,
                 });
             }
             Err(e) => {
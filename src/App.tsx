@@ -5,6 +5,7 @@ import { FileTree } from "./components/FileTree/FileTree";
 import { PhotoPanel } from "./components/PhotoPanel/PhotoPanel";
 import { ClassifyDialog } from "./components/ClassifyDialog/ClassifyDialog";
 import { ContextMenu } from "./components/common/ContextMenu";
+import { DuplicatesDialog } from "./components/DuplicatesDialog/DuplicatesDialog";
 import { NotificationStack } from "./components/NotificationStack/NotificationStack";
 import { StatusBar } from "./components/StatusBar/StatusBar";
 import "./styles/variables.css";
@@ -35,6 +36,7 @@ function App() {
             )}
             <StatusBar />
             <ClassifyDialog />
+            <DuplicatesDialog />
             <ContextMenu />
             <NotificationStack />
           </div>
@@ -1,12 +1,29 @@
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, convertFileSrc, invoke } from "@tauri-apps/api/core";
 import type {
+  Album,
   DriveInfo,
   DirEntry,
+  DecodeLimits,
+  DuplicateReport,
+  NearDuplicateGroup,
   PhotoEntry,
   ExifData,
+  ExifFilter,
+  FormatSupport,
   ModelStatus,
   ClassifyProgress,
+  ColorLabel,
+  CullingFilter,
   ModelType,
+  PageCursor,
+  PhotoFlag,
+  PhotoPage,
+  PhotoQuery,
+  PhotoTag,
+  SavedQuery,
+  TagFilterState,
+  TagSummary,
+  ThumbnailCacheInfo,
 } from "../types";
 
 export async function listDrives(): Promise<DriveInfo[]> {
@@ -25,12 +42,72 @@ export async function listDirectory(path: string): Promise<DirEntry[]> {
   return invoke<DirEntry[]>("list_directory", { path });
 }
 
-export async function listPhotos(path: string): Promise<void> {
-  return invoke<void>("list_photos", { path });
+export async function listPhotos(path: string, recursive = false, maxDepth?: number): Promise<void> {
+  return invoke<void>("list_photos", { path, recursive, maxDepth: maxDepth ?? null });
 }
 
-export async function getAllTags(folder: string): Promise<string[]> {
-  return invoke<string[]>("get_all_tags", { folder });
+export async function getAllTags(folder: string, recursive = false): Promise<string[]> {
+  return invoke<string[]>("get_all_tags", { folder, recursive });
+}
+
+// Backend shape of PhotoQuery (snake_case, tag filter split by match mode)
+function toQueryParams(query: PhotoQuery) {
+  const tagFilter = query.tagFilter;
+  return {
+    folder: query.folder,
+    search: query.search,
+    sort_by: query.sortBy,
+    sort_order: query.sortOrder,
+    tag_filter: tagFilter && {
+      include_all: tagFilter.mode === "all" ? tagFilter.include : [],
+      include_any: tagFilter.mode === "any" ? tagFilter.include : [],
+      exclude: tagFilter.exclude,
+    },
+    exif_filter: query.exifFilter ?? null,
+    culling_filter: query.cullingFilter ?? null,
+    recursive: query.recursive ?? false,
+    album_id: query.albumId ?? null,
+    similarity: query.similarity ? {
+      reference_path: query.similarity.referencePath,
+      // 0–100 in the UI, 0.0–1.0 in the backend
+      threshold: query.similarity.threshold / 100,
+    } : null,
+    collapse_stacks: query.collapseStacks ?? false,
+  };
+}
+
+type QueryParams = ReturnType<typeof toQueryParams>;
+
+// Inverse of toQueryParams, for queries stored by the backend
+function fromQueryParams(params: QueryParams): PhotoQuery {
+  const tagFilter = params.tag_filter;
+  const anyMode = !!tagFilter && tagFilter.include_any.length > 0;
+  return {
+    folder: params.folder,
+    search: params.search,
+    sortBy: params.sort_by,
+    sortOrder: params.sort_order,
+    tagFilter: tagFilter && {
+      include: anyMode ? tagFilter.include_any : tagFilter.include_all,
+      exclude: tagFilter.exclude,
+      mode: anyMode ? "any" : "all",
+    },
+    exifFilter: params.exif_filter,
+    cullingFilter: params.culling_filter,
+    recursive: params.recursive,
+    albumId: params.album_id,
+    similarity: params.similarity && {
+      referencePath: params.similarity.reference_path,
+      threshold: Math.round(params.similarity.threshold * 100),
+    },
+    collapseStacks: params.collapse_stacks,
+  };
+}
+
+type RawSavedQuery = Omit<SavedQuery, "query"> & { query: QueryParams };
+
+function fromRawSavedQuery(raw: RawSavedQuery): SavedQuery {
+  return { ...raw, query: fromQueryParams(raw.query) };
 }
 
 export async function queryPhotos(
@@ -38,44 +115,74 @@ export async function queryPhotos(
   search: string | null,
   sortBy: string,
   sortOrder: string,
-  filterTags: string[] | null
+  tagFilter: TagFilterState | null,
+  recursive = false,
+  exifFilter: ExifFilter | null = null,
+  cullingFilter: CullingFilter | null = null,
+  collapseStacks = false
 ): Promise<PhotoEntry[]> {
   return invoke<PhotoEntry[]>("query_photos", {
-    folder,
-    search,
-    sortBy,
-    sortOrder,
-    filterTags,
+    query: toQueryParams({ folder, search, sortBy, sortOrder, tagFilter, recursive, exifFilter, cullingFilter, collapseStacks }),
+  });
+}
+
+export async function queryPhotosPage(
+  query: PhotoQuery,
+  cursor: PageCursor | null = null,
+  limit?: number
+): Promise<PhotoPage> {
+  return invoke<PhotoPage>("query_photos_page", {
+    query: toQueryParams(query),
+    cursor,
+    limit: limit ?? null,
+  });
+}
+
+/** Streams the query result page by page; resolves with the total once the last page was sent. */
+export async function streamPhotos(
+  query: PhotoQuery,
+  onPage: (page: PhotoPage) => void,
+  pageSize?: number
+): Promise<number> {
+  const channel = new Channel<PhotoPage>();
+  channel.onmessage = onPage;
+  return invoke<number>("stream_photos", {
+    query: toQueryParams(query),
+    pageSize: pageSize ?? null,
+    onPage: channel,
   });
 }
 
 export async function findSimilarPhotos(
   folder: string,
   referencePath: string,
-  threshold: number
+  threshold: number,
+  recursive = false
 ): Promise<PhotoEntry[]> {
   // threshold comes in as 0–100 (percentage), convert to 0.0–1.0 for backend
   return invoke<PhotoEntry[]>("find_similar_photos", {
     folder,
     referencePath,
     threshold: threshold / 100,
+    recursive,
   });
 }
 
-export async function getThumbnailsBatch(paths: string[]): Promise<Record<string, string>> {
-  return invoke<Record<string, string>>("get_thumbnails_batch", { paths });
-}
-
-export async function getThumbnail(path: string): Promise<string> {
-  return invoke<string>("get_thumbnail", { path });
-}
+// Images are served by the backend's photolense:// protocol rather than through IPC
+const IMAGE_SCHEME = "photolense";
 
-export async function getFullImage(path: string): Promise<string> {
-  return invoke<string>("get_full_image", { path });
+/** URL of a photo's thumbnail; generated and cached by the backend on first load. */
+export function thumbnailUrl(photoId: number): string {
+  return convertFileSrc(`thumb/${photoId}`, IMAGE_SCHEME);
 }
 
-export async function getImageBytes(path: string): Promise<Uint8Array> {
-  return invoke<Uint8Array>("get_image_bytes", { path });
+/**
+ * URL of the photo itself (a scaled-down JPEG for files over 32 MB), or with `max` a JPEG
+ * scaled down to fit max×max.
+ */
+export function fullImageUrl(photoId: number, max?: number): string {
+  const url = convertFileSrc(`full/${photoId}`, IMAGE_SCHEME);
+  return max ? `${url}?max=${max}` : url;
 }
 
 export async function readExif(path: string): Promise<ExifData> {
@@ -86,6 +193,33 @@ export async function getHistogram(path: string): Promise<string> {
   return invoke<string>("get_histogram", { path });
 }
 
+/** Extensions the app imports, with whether this build can decode (and so preview) them. */
+export async function getImageFormats(): Promise<FormatSupport[]> {
+  return invoke<FormatSupport[]>("get_image_formats");
+}
+
+export async function getDecodeLimits(): Promise<DecodeLimits> {
+  return invoke<DecodeLimits>("get_decode_limits");
+}
+
+export async function setDecodeLimits(limits: DecodeLimits): Promise<void> {
+  return invoke<void>("set_decode_limits", { limits });
+}
+
+export async function getThumbnailCacheInfo(): Promise<ThumbnailCacheInfo> {
+  return invoke<ThumbnailCacheInfo>("get_thumbnail_cache_info");
+}
+
+/** Cap the on-disk thumbnail cache; least recently used thumbnails are evicted beyond it. */
+export async function setThumbnailCacheLimit(maxBytes: number): Promise<void> {
+  return invoke<void>("set_thumbnail_cache_limit", { maxBytes });
+}
+
+/** Delete all cached thumbnails on disk; they are regenerated as photos are shown. */
+export async function clearThumbnailCache(): Promise<void> {
+  return invoke<void>("clear_thumbnail_cache");
+}
+
 export async function getModelStatus(): Promise<ModelStatus> {
   return invoke<ModelStatus>("get_model_status");
 }
@@ -124,12 +258,43 @@ export async function copyFiles(
   return invoke<void>("copy_files", { paths, destination });
 }
 
-export async function triggerIndexing(folder: string): Promise<string> {
-  return invoke<string>("trigger_indexing", { folder });
+// Stacks: bursts and series collapsed into one cover photo
+export async function detectStacks(folder: string, recursive = false): Promise<number> {
+  return invoke<number>("detect_stacks", { folder, recursive });
+}
+
+export async function getStackPhotos(stackId: number): Promise<PhotoEntry[]> {
+  return invoke<PhotoEntry[]>("get_stack_photos", { stackId });
 }
 
-export async function getIndexingStatus(folder: string): Promise<{ total: number; indexed: number }> {
-  return invoke<{ total: number; indexed: number }>("get_indexing_status", { folder });
+export async function setStackCover(path: string): Promise<void> {
+  return invoke<void>("set_stack_cover", { path });
+}
+
+export async function unstackPhotos(paths: string[]): Promise<number> {
+  return invoke<number>("unstack_photos", { paths });
+}
+
+/** Groups photos with identical content; a null folder searches the whole library. */
+export async function findDuplicates(folder: string | null, recursive = true): Promise<DuplicateReport> {
+  return invoke<DuplicateReport>("find_duplicates", { folder, recursive });
+}
+
+/** Clusters look-alike photos whose perceptual hashes differ in at most `threshold` bits. */
+export async function findNearDuplicates(
+  folder: string | null,
+  recursive = true,
+  threshold?: number
+): Promise<NearDuplicateGroup[]> {
+  return invoke<NearDuplicateGroup[]>("find_near_duplicates", { folder, recursive, threshold: threshold ?? null });
+}
+
+export async function triggerIndexing(folder: string, recursive = false): Promise<string> {
+  return invoke<string>("trigger_indexing", { folder, recursive });
+}
+
+export async function getIndexingStatus(folder: string, recursive = false): Promise<{ total: number; indexed: number }> {
+  return invoke<{ total: number; indexed: number }>("get_indexing_status", { folder, recursive });
 }
 
 export async function classifyImages(
@@ -150,8 +315,119 @@ export async function classifyImages(
   });
 }
 
-export async function deleteAllTags(folder: string): Promise<void> {
-  return invoke<void>("delete_all_tags", { folder });
+export async function deleteAllTags(folder: string, recursive = false): Promise<void> {
+  return invoke<void>("delete_all_tags", { folder, recursive });
+}
+
+export async function getPhotoTags(path: string): Promise<PhotoTag[]> {
+  return invoke<PhotoTag[]>("get_photo_tags", { path });
+}
+
+export async function addManualTag(paths: string[], tag: string): Promise<number> {
+  return invoke<number>("add_manual_tag", { paths, tag });
+}
+
+export async function removeManualTag(paths: string[], tag: string): Promise<number> {
+  return invoke<number>("remove_manual_tag", { paths, tag });
+}
+
+// Albums
+export async function listAlbums(): Promise<Album[]> {
+  return invoke<Album[]>("list_albums");
+}
+
+export async function createAlbum(name: string): Promise<Album> {
+  return invoke<Album>("create_album", { name });
+}
+
+export async function renameAlbum(albumId: number, name: string): Promise<Album> {
+  return invoke<Album>("rename_album", { albumId, name });
+}
+
+export async function deleteAlbum(albumId: number): Promise<void> {
+  return invoke<void>("delete_album", { albumId });
+}
+
+export async function addToAlbum(albumId: number, paths: string[]): Promise<number> {
+  return invoke<number>("add_to_album", { albumId, paths });
+}
+
+export async function removeFromAlbum(albumId: number, paths: string[]): Promise<number> {
+  return invoke<number>("remove_from_album", { albumId, paths });
+}
+
+export async function reorderAlbum(albumId: number, paths: string[]): Promise<void> {
+  return invoke<void>("reorder_album", { albumId, paths });
+}
+
+export async function queryAlbumPhotos(albumId: number, query: PhotoQuery): Promise<PhotoEntry[]> {
+  return invoke<PhotoEntry[]>("query_album_photos", { albumId, query: toQueryParams(query) });
+}
+
+// Saved queries ("smart albums" when pinned)
+export async function listSavedQueries(): Promise<SavedQuery[]> {
+  return (await invoke<RawSavedQuery[]>("list_saved_queries")).map(fromRawSavedQuery);
+}
+
+export async function saveQuery(name: string, query: PhotoQuery, pinned = false): Promise<SavedQuery> {
+  return fromRawSavedQuery(await invoke<RawSavedQuery>("save_query", { name, query: toQueryParams(query), pinned }));
+}
+
+export async function updateSavedQuery(
+  savedQueryId: number,
+  changes: { name?: string; query?: PhotoQuery }
+): Promise<SavedQuery> {
+  return fromRawSavedQuery(await invoke<RawSavedQuery>("update_saved_query", {
+    savedQueryId,
+    name: changes.name ?? null,
+    query: changes.query ? toQueryParams(changes.query) : null,
+  }));
+}
+
+export async function deleteSavedQuery(savedQueryId: number): Promise<void> {
+  return invoke<void>("delete_saved_query", { savedQueryId });
+}
+
+export async function pinSavedQuery(savedQueryId: number, pinned: boolean): Promise<SavedQuery> {
+  return fromRawSavedQuery(await invoke<RawSavedQuery>("pin_saved_query", { savedQueryId, pinned }));
+}
+
+export async function runSavedQuery(savedQueryId: number): Promise<PhotoEntry[]> {
+  return invoke<PhotoEntry[]>("run_saved_query", { savedQueryId });
+}
+
+export async function refreshSavedQueryCounts(): Promise<SavedQuery[]> {
+  return (await invoke<RawSavedQuery[]>("refresh_saved_query_counts")).map(fromRawSavedQuery);
+}
+
+// Culling: each applies to every photo in `paths` and resolves with the number updated
+export async function setRating(paths: string[], rating: number): Promise<number> {
+  return invoke<number>("set_rating", { paths, rating });
+}
+
+export async function setColorLabel(paths: string[], label: ColorLabel | null): Promise<number> {
+  return invoke<number>("set_color_label", { paths, label });
+}
+
+export async function setFlag(paths: string[], flag: PhotoFlag): Promise<number> {
+  return invoke<number>("set_flag", { paths, flag });
+}
+
+// Tag management: a null folder means the whole library
+export async function listTags(folder: string | null, recursive = false): Promise<TagSummary[]> {
+  return invoke<TagSummary[]>("list_tags", { folder, recursive });
+}
+
+export async function renameTag(oldName: string, newName: string): Promise<number> {
+  return invoke<number>("rename_tag", { oldName, newName });
+}
+
+export async function mergeTags(tags: string[], into: string): Promise<number> {
+  return invoke<number>("merge_tags", { tags, into });
+}
+
+export async function deleteTag(tag: string, folder: string | null, recursive = false): Promise<number> {
+  return invoke<number>("delete_tag", { tag, folder, recursive });
 }
 
 export interface GroupingConfig {
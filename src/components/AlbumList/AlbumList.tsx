@@ -0,0 +1,169 @@
+import { useEffect, useState } from "react";
+import { BookImage, PinOff, Plus, Sparkles, Trash2 } from "lucide-react";
+import { createAlbum, deleteAlbum, listAlbums, pinSavedQuery, refreshSavedQueryCounts, renameAlbum } from "../../api/commands";
+import { useAppDispatch, useAppState } from "../../hooks/useAppState";
+import type { Album, SavedQuery } from "../../types";
+import "./AlbumList.css";
+
+export function AlbumList() {
+  const state = useAppState();
+  const dispatch = useAppDispatch();
+  // null: not editing; "new": naming a new album; number: renaming that album
+  const [editing, setEditing] = useState<"new" | number | null>(null);
+  const [name, setName] = useState("");
+
+  const refresh = async () => {
+    try {
+      dispatch({ type: "SET_ALBUMS", albums: await listAlbums() });
+    } catch (e: any) {
+      dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+    }
+  };
+
+  const refreshSmartAlbums = async () => {
+    try {
+      dispatch({ type: "SET_SAVED_QUERIES", queries: await refreshSavedQueryCounts() });
+    } catch (e: any) {
+      dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+    }
+  };
+
+  useEffect(() => {
+    refresh();
+    refreshSmartAlbums();
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  const handleUnpin = async (saved: SavedQuery) => {
+    try {
+      await pinSavedQuery(saved.id, false);
+      await refreshSmartAlbums();
+    } catch (e: any) {
+      dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+    }
+  };
+
+  const smartAlbums = state.savedQueries.filter((q) => q.pinned);
+
+  const startEditing = (target: "new" | Album) => {
+    setEditing(target === "new" ? "new" : target.id);
+    setName(target === "new" ? "" : target.name);
+  };
+
+  const commit = async () => {
+    const target = editing;
+    const trimmed = name.trim();
+    setEditing(null);
+    if (!trimmed || target === null) return;
+    try {
+      if (target === "new") {
+        const album = await createAlbum(trimmed);
+        await refresh();
+        dispatch({ type: "SET_SELECTED_ALBUM", album });
+      } else {
+        await renameAlbum(target, trimmed);
+        await refresh();
+      }
+    } catch (e: any) {
+      dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+    }
+  };
+
+  const handleDelete = async (album: Album) => {
+    if (!(await window.confirm(`Delete the album "${album.name}"? The photos are kept.`))) return;
+    try {
+      await deleteAlbum(album.id);
+      if (state.selectedAlbum?.id === album.id) {
+        dispatch({ type: "SET_SELECTED_ALBUM", album: null });
+      }
+      await refresh();
+    } catch (e: any) {
+      dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+    }
+  };
+
+  const nameInput = (
+    <input
+      className="album-name-input"
+      autoFocus
+      value={name}
+      placeholder="Album name"
+      onChange={(e) => setName(e.target.value)}
+      onKeyDown={(e) => {
+        if (e.key === "Enter") commit();
+        if (e.key === "Escape") setEditing(null);
+      }}
+      onBlur={commit}
+    />
+  );
+
+  return (
+    <div className="album-list">
+      <div className="album-list-header">
+        <span>Albums</span>
+        <button onClick={() => startEditing("new")} title="New album" type="button">
+          <Plus size={14} />
+        </button>
+      </div>
+      <div className="album-list-content">
+        {editing === "new" && <div className="album-item">{nameInput}</div>}
+        {state.albums.map((album) => (
+          <div
+            key={album.id}
+            className={`album-item ${state.selectedAlbum?.id === album.id ? "selected" : ""}`}
+            onClick={() => dispatch({ type: "SET_SELECTED_ALBUM", album })}
+            onDoubleClick={() => startEditing(album)}
+            title="Double-click to rename"
+          >
+            <BookImage size={14} className="album-icon" />
+            {editing === album.id ? nameInput : (
+              <>
+                <span className="album-name">{album.name}</span>
+                <span className="album-count">{album.photo_count}</span>
+                <button
+                  className="album-delete"
+                  onClick={(e) => { e.stopPropagation(); handleDelete(album); }}
+                  title="Delete album"
+                  type="button"
+                >
+                  <Trash2 size={12} />
+                </button>
+              </>
+            )}
+          </div>
+        ))}
+        {state.albums.length === 0 && editing !== "new" && (
+          <div className="album-empty">No albums yet</div>
+        )}
+      </div>
+      {smartAlbums.length > 0 && (
+        <>
+          <div className="album-list-header">
+            <span>Smart Albums</span>
+          </div>
+          <div className="album-list-content">
+            {smartAlbums.map((saved) => (
+              <div
+                key={saved.id}
+                className={`album-item ${state.activeSavedQuery?.id === saved.id ? "selected" : ""}`}
+                onClick={() => dispatch({ type: "APPLY_SAVED_QUERY", saved })}
+              >
+                <Sparkles size={14} className="album-icon" />
+                <span className="album-name">{saved.name}</span>
+                {saved.cached_count !== null && <span className="album-count">{saved.cached_count}</span>}
+                <button
+                  className="album-delete"
+                  onClick={(e) => { e.stopPropagation(); handleUnpin(saved); }}
+                  title="Remove from smart albums"
+                  type="button"
+                >
+                  <PinOff size={12} />
+                </button>
+              </div>
+            ))}
+          </div>
+        </>
+      )}
+    </div>
+  );
+}
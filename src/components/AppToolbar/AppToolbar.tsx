@@ -1,4 +1,5 @@
 import { useEffect, useRef, useState } from "react";
+import { Layers } from "lucide-react";
 import { useAppDispatch, useAppState } from "../../hooks/useAppState";
 import { getAllTags, groupByColor } from "../../api/commands";
 import type { SortBy, ViewMode } from "../../types";
@@ -6,10 +7,12 @@ import "./AppToolbar.css";
 import { SearchBox } from "../SearchBox/SearchBox.tsx";
 import { SimilaritySlider } from "../SimilaritySlider/SimilaritySlider.tsx";
 import { TagFilterDropdown } from "../TagFilterDropdown/TagFilterDropdown.tsx";
+import { SavedQueryMenu } from "../SavedQueryMenu/SavedQueryMenu.tsx";
 import { NotificationBell } from "../NotificationBell/NotificationBell.tsx";
 import { ColorGroupDialog } from "../ColorGroupDialog/ColorGroupDialog.tsx";
 import { PathInput } from "./PathInput";
 import { useFileTree } from "../../hooks/useFileTree";
+import { usePhotos } from "../../hooks/usePhotos";
 
 export function AppToolbar() {
   const state = useAppState();
@@ -21,6 +24,7 @@ export function AppToolbar() {
   const [grouping, setGrouping] = useState(false);
   const [showColorDialog, setShowColorDialog] = useState(false);
   const { expandToFolder } = useFileTree();
+  const { loadPhotos } = usePhotos();
 
   // Sync local input when the global state is reset (e.g. folder change)
   useEffect(() => {
@@ -48,11 +52,11 @@ export function AppToolbar() {
   // Fetch available tags when the folder changes or photos change (e.g. after classification or import)
   useEffect(() => {
     if (state.selectedFolder) {
-      getAllTags(state.selectedFolder).then(setAvailableTags).catch(console.error);
+      getAllTags(state.selectedFolder, state.includeSubfolders).then(setAvailableTags).catch(console.error);
     } else {
       setAvailableTags([]);
     }
-  }, [state.selectedFolder, state.photos]);
+  }, [state.selectedFolder, state.includeSubfolders, state.photos]);
 
   const onSearchChange = (value: string) => {
     setSearch(value);
@@ -62,15 +66,13 @@ export function AppToolbar() {
     }, 300);
   };
 
-  const toggleTag = (tag: string) => {
-    const current = state.filterTags;
-    let next: string[];
-    if (current.includes(tag)) {
-      next = current.filter(t => t !== tag);
-    } else {
-      next = [...current, tag];
+  // Switching to subfolders needs a recursive import, not just a re-query
+  const toggleSubfolders = () => {
+    const next = !state.includeSubfolders;
+    dispatch({type: "TOGGLE_INCLUDE_SUBFOLDERS"});
+    if (state.selectedFolder) {
+      loadPhotos(state.selectedFolder, { clearCache: false, silent: true, recursive: next });
     }
-    dispatch({type: "SET_FILTER_TAGS", tags: next});
   };
 
   const handleGroupClick = () => {
@@ -134,10 +136,12 @@ export function AppToolbar() {
       <div className="toolbar-right">
         <TagFilterDropdown
           availableTags={availableTags}
-          selectedTags={state.filterTags}
-          onToggleTag={toggleTag}
+          tagFilter={state.tagFilter}
+          onChange={(filter) => dispatch({type: "SET_TAG_FILTER", filter})}
         />
 
+        <SavedQueryMenu/>
+
         <div className="view-toggle">
           <button
             className={state.viewMode === "grid" ? "active" : ""}
@@ -174,6 +178,15 @@ export function AppToolbar() {
           <option value="name">Name</option>
           <option value="size">Size</option>
           <option value="date">Date</option>
+          <option value="date_taken">Date taken</option>
+          <option value="camera">Camera</option>
+          <option value="iso">ISO</option>
+          <option value="rating">Rating</option>
+          <option value="label">Color label</option>
+          <option value="flag">Pick / reject</option>
+          {state.selectedAlbum && <option value="position">Album order</option>}
+          {state.similaritySearch && <option value="similarity">Similarity</option>}
+          {state.searchQuery.trim() && <option value="relevance">Relevance</option>}
         </select>
 
         <button
@@ -242,6 +255,26 @@ export function AppToolbar() {
           </>
         )}
 
+        <button
+          className={`subfolders-btn ${state.includeSubfolders ? "active" : ""}`}
+          onClick={toggleSubfolders}
+          title={state.includeSubfolders ? "Show this folder only" : "Include subfolders"}
+        >
+          <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2">
+            <path d="M3 6a2 2 0 0 1 2-2h4l2 2h8a2 2 0 0 1 2 2v2"/>
+            <path d="M7 12h12a2 2 0 0 1 2 2v4a2 2 0 0 1-2 2H9a2 2 0 0 1-2-2z"/>
+            <path d="M3 10v8a2 2 0 0 0 2 2h2"/>
+          </svg>
+        </button>
+
+        <button
+          className={`stacks-btn ${state.collapseStacks ? "active" : ""}`}
+          onClick={() => dispatch({type: "TOGGLE_COLLAPSE_STACKS"})}
+          title={state.collapseStacks ? "Show every photo of a stack" : "Collapse stacks to their cover"}
+        >
+          <Layers size={16}/>
+        </button>
+
         <button
           className={`overlay-btn ${state.showOverlay ? "active" : ""}`}
           onClick={() => dispatch({type: "TOGGLE_OVERLAY"})}
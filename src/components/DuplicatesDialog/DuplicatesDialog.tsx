@@ -0,0 +1,234 @@
+import { useCallback, useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { open } from "@tauri-apps/plugin-dialog";
+import { deleteFiles, findDuplicates, findNearDuplicates, moveFiles } from "../../api/commands";
+import { useAppDispatch, useAppState } from "../../hooks/useAppState";
+import "./DuplicatesDialog.css";
+
+function formatSize(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`;
+}
+
+type Scope = "folder" | "library";
+// Identical files by content hash, or look-alikes by perceptual hash
+type Mode = "exact" | "similar";
+
+// Groups of both modes, shown the same way; the first path is the suggested keeper
+interface Group {
+  key: string;
+  label: string;
+  paths: string[];
+}
+
+const DEFAULT_THRESHOLD = 8;
+
+export function DuplicatesDialog() {
+  const { duplicatesDialog } = useAppState();
+  if (!duplicatesDialog) return null;
+  // Remount per opening so the scope and results start fresh
+  return <DuplicatesDialogContent key={duplicatesDialog.folderPath ?? ""} folder={duplicatesDialog.folderPath} />;
+}
+
+function DuplicatesDialogContent({ folder }: { folder: string | null }) {
+  const dispatch = useAppDispatch();
+  const [scope, setScope] = useState<Scope>(folder ? "folder" : "library");
+  const [mode, setMode] = useState<Mode>("exact");
+  const [threshold, setThreshold] = useState(DEFAULT_THRESHOLD);
+  const [groups, setGroups] = useState<Group[] | null>(null);
+  const [summary, setSummary] = useState("");
+  // Path to keep per group key; defaults to the suggested first path
+  const [keep, setKeep] = useState<Record<string, string>>({});
+  const [progress, setProgress] = useState<{ current: number; total: number } | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const scopeFolder = scope === "folder" ? folder : null;
+
+  const scan = useCallback(async () => {
+    setBusy(true);
+    setGroups(null);
+    setKeep({});
+    setError(null);
+    try {
+      if (mode === "exact") {
+        const report = await findDuplicates(scopeFolder, true);
+        setGroups(report.groups.map((g) => ({
+          key: g.hash,
+          label: `${g.paths.length} copies, ${formatSize(g.size)} each`,
+          paths: g.paths,
+        })));
+        setSummary(`${report.groups.length} groups, ${formatSize(report.wasted_bytes)} wasted`);
+      } else {
+        const found = await findNearDuplicates(scopeFolder, true, threshold);
+        setGroups(found.map((g) => ({
+          key: g.paths[0],
+          label: `${g.paths.length} photos, up to ${g.max_distance} bits apart`,
+          paths: g.paths,
+        })));
+        setSummary(`${found.length} groups of look-alike photos`);
+      }
+    } catch (e: any) {
+      setError(e?.message ?? String(e));
+    } finally {
+      setProgress(null);
+      setBusy(false);
+    }
+  }, [scopeFolder, mode, threshold]);
+
+  useEffect(() => {
+    scan();
+  }, [scan]);
+
+  useEffect(() => {
+    const unlisten = listen<{ current: number; total: number; done?: boolean }>("duplicate-progress", (event) => {
+      setProgress(event.payload.done ? null : { current: event.payload.current, total: event.payload.total });
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  const close = () => dispatch({ type: "SET_DUPLICATES_DIALOG", state: null });
+
+  // Every photo except the one kept in its group
+  const extraPaths = (groups ?? []).flatMap((g) => {
+    const kept = keep[g.key] ?? g.paths[0];
+    return g.paths.filter((p) => p !== kept);
+  });
+
+  const resolve = async (action: (paths: string[]) => Promise<void>) => {
+    setBusy(true);
+    try {
+      await action(extraPaths);
+      dispatch({ type: "REMOVE_PHOTOS", paths: extraPaths });
+    } catch (e: any) {
+      setError(e?.message ?? String(e));
+    } finally {
+      setBusy(false);
+    }
+    scan();
+  };
+
+  const handleDelete = async () => {
+    const what = mode === "exact" ? "duplicate files and keep one copy of each" : "look-alike photos and keep one per group";
+    if (!(await window.confirm(`Delete ${extraPaths.length} ${what}?`))) return;
+    resolve(deleteFiles);
+  };
+
+  const handleMove = async () => {
+    const dest = await open({ directory: true, title: "Move duplicates to..." });
+    if (!dest) return;
+    resolve((paths) => moveFiles(paths, dest));
+  };
+
+  return (
+    <div className="duplicates-overlay">
+      <div className="duplicates-dialog">
+        <h3>Find Duplicates</h3>
+
+        <div className="duplicates-scope">
+          <button
+            className={scope === "folder" ? "active" : ""}
+            onClick={() => setScope("folder")}
+            disabled={!folder || busy}
+            title={folder ?? undefined}
+            type="button"
+          >
+            This folder and subfolders
+          </button>
+          <button
+            className={scope === "library" ? "active" : ""}
+            onClick={() => setScope("library")}
+            disabled={busy}
+            type="button"
+          >
+            Whole library
+          </button>
+        </div>
+
+        <div className="duplicates-scope">
+          <button
+            className={mode === "exact" ? "active" : ""}
+            onClick={() => setMode("exact")}
+            disabled={busy}
+            type="button"
+          >
+            Identical files
+          </button>
+          <button
+            className={mode === "similar" ? "active" : ""}
+            onClick={() => setMode("similar")}
+            disabled={busy}
+            title="Resized, recompressed or lightly edited copies"
+            type="button"
+          >
+            Look-alikes
+          </button>
+          {mode === "similar" && (
+            <label className="duplicates-threshold" title="Maximum Hamming distance of the perceptual hashes (0-64)">
+              Tolerance
+              <input
+                type="number"
+                min={0}
+                max={64}
+                value={threshold}
+                onChange={(e) => setThreshold(Math.max(0, Math.min(64, Number(e.target.value) || 0)))}
+                disabled={busy}
+              />
+            </label>
+          )}
+        </div>
+
+        {error && <div className="duplicates-error">{error}</div>}
+
+        {busy && (
+          <div className="duplicates-status">
+            {progress ? `Hashing ${progress.current} / ${progress.total}...` : "Searching..."}
+          </div>
+        )}
+
+        {groups && !busy && (
+          <div className="duplicates-status">
+            {groups.length === 0 ? "No duplicates found." : summary}
+          </div>
+        )}
+
+        {groups && groups.length > 0 && (
+          <div className="duplicates-list">
+            {groups.map((g) => (
+              <div className="duplicates-group" key={g.key}>
+                <div className="duplicates-group-header">{g.label}</div>
+                {g.paths.map((path) => (
+                  <label className="duplicates-path" key={path} title={path}>
+                    <input
+                      type="radio"
+                      name={g.key}
+                      checked={(keep[g.key] ?? g.paths[0]) === path}
+                      onChange={() => setKeep({ ...keep, [g.key]: path })}
+                    />
+                    <span>{path}</span>
+                  </label>
+                ))}
+              </div>
+            ))}
+          </div>
+        )}
+
+        <div className="dialog-actions">
+          <button className="cancel-btn" onClick={close} type="button">
+            Close
+          </button>
+          <button className="duplicates-action" onClick={handleMove} disabled={busy || extraPaths.length === 0} type="button">
+            Move others to...
+          </button>
+          <button className="duplicates-action danger" onClick={handleDelete} disabled={busy || extraPaths.length === 0} type="button">
+            Delete others
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -4,6 +4,7 @@ import { useAppState, useAppDispatch } from "../../hooks/useAppState";
 import { usePhotos } from "../../hooks/usePhotos";
 import { useFileTree } from "../../hooks/useFileTree";
 import { TreeNode } from "./TreeNode";
+import { AlbumList } from "../AlbumList/AlbumList";
 import type { TreeNodeData } from "../../types";
 import "./FileTree.css";
 
@@ -144,6 +145,7 @@ export function FileTree() {
           />
         ))}
       </div>
+      <AlbumList />
     </div>
   );
 }
@@ -1,6 +1,7 @@
 import type { ExifData, PhotoEntry } from "../../types";
 import { FileInfo } from "./FileInfo";
 import { Histogram } from "./Histogram";
+import { TagEditor } from "./TagEditor";
 import "./PhotoPanel.css";
 
 interface ExifPanelProps {
@@ -60,6 +61,8 @@ export function ExifPanel({ exif, histogramData, selectedPhoto }: ExifPanelProps
 
       <FileInfo photo={selectedPhoto} />
 
+      <TagEditor photo={selectedPhoto} />
+
       <Histogram data={histogramData} />
 
       {exif ? (
@@ -3,7 +3,7 @@ import { useNotifications } from "../../hooks/useNotifications";
 import { getIndexingStatus, triggerIndexing } from "../../api/commands";
 
 export function FindSimilarButton({ disabled }: { disabled?: boolean }) {
-  const { selectedPaths, photos, indexingState, selectedFolder } = useAppState();
+  const { selectedPaths, photos, indexingState, selectedFolder, includeSubfolders } = useAppState();
   const dispatch = useAppDispatch();
   const { actions } = useNotifications();
 
@@ -34,11 +34,11 @@ export function FindSimilarButton({ disabled }: { disabled?: boolean }) {
     }
 
     try {
-      const status = await getIndexingStatus(selectedFolder);
+      const status = await getIndexingStatus(selectedFolder, includeSubfolders);
       if (status.indexed < status.total) {
         // Not fully indexed — trigger indexing and notify
         try {
-          await triggerIndexing(selectedFolder);
+          await triggerIndexing(selectedFolder, includeSubfolders);
           actions.addNotification({
             id: "similarity-status",
             type: "warning",
@@ -1,158 +1,100 @@
 import { memo, useEffect, useRef, useState } from "react";
-import { getThumbnail, getThumbnailsBatch } from "../../api/commands";
+import { getImageFormats, thumbnailUrl } from "../../api/commands";
 
 interface LazyThumbnailProps {
+  photoId: number;
   path: string;
   alt: string;
   className?: string;
 }
 
-// Simple in-memory cache so thumbnails survive re-renders and scrolling back
-const thumbnailCache = new Map<string, string>();
-
-// --- Batched thumbnail loading ---
-// Requests arriving within the same frame are coalesced into a single IPC call
-// that fetches all cached thumbnails in one DB query. Uncached thumbnails fall
-// back to individual generation with a concurrency limiter.
-
-const BATCH_DELAY_MS = 10;
-type Subscriber = { resolve: (v: string) => void; reject: (e: unknown) => void };
-let pendingBatch = new Map<string, Subscriber[]>();
-let batchTimer: ReturnType<typeof setTimeout> | null = null;
-
-// Concurrency limiter for individual (uncached) thumbnail generation
-const MAX_CONCURRENT = 6;
-let activeRequests = 0;
-const slotQueue: Array<() => void> = [];
-
-function acquireSlot(): Promise<void> {
-  return new Promise<void>((resolve) => {
-    if (activeRequests < MAX_CONCURRENT) {
-      activeRequests++;
-      resolve();
-    } else {
-      slotQueue.push(() => { activeRequests++; resolve(); });
-    }
-  });
-}
+// Thumbnails already shown, so tiles scrolled back into view render them right away
+// instead of waiting for the intersection observer; the webview caches the images
+const loadedThumbnails = new Set<number>();
 
-function releaseSlot() {
-  activeRequests--;
-  const next = slotQueue.shift();
-  if (next) next();
-}
+// Decodable flag per extension, fetched once to explain tiles without a thumbnail
+let formatSupport: Promise<Map<string, boolean>> | null = null;
 
-function loadUncached(path: string, subscribers: Subscriber[]) {
-  acquireSlot().then(() => {
-    getThumbnail(path)
-      .then((data) => {
-        thumbnailCache.set(path, data);
-        for (const s of subscribers) s.resolve(data);
-      })
-      .catch((err) => {
-        for (const s of subscribers) s.reject(err);
-      })
-      .finally(releaseSlot);
-  });
-}
-
-async function flushBatch() {
-  batchTimer = null;
-  const batch = pendingBatch;
-  pendingBatch = new Map();
-
-  const paths = Array.from(batch.keys());
-  if (paths.length === 0) return;
-
-  try {
-    // Single IPC call fetches all cached thumbnails from DB
-    const results = await getThumbnailsBatch(paths);
-
-    for (const [path, subscribers] of batch) {
-      const data = results[path];
-      if (data) {
-        thumbnailCache.set(path, data);
-        for (const s of subscribers) s.resolve(data);
-      } else {
-        // Not cached in DB — fall back to individual generation
-        loadUncached(path, subscribers);
-      }
-    }
-  } catch {
-    // Batch call failed — fall back to individual loading for all
-    for (const [path, subscribers] of batch) {
-      loadUncached(path, subscribers);
-    }
+function decodableFormats(): Promise<Map<string, boolean>> {
+  if (!formatSupport) {
+    formatSupport = getImageFormats()
+      .then((formats) => new Map(formats.map((f) => [f.extension, f.decodable])))
+      .catch(() => new Map());
   }
+  return formatSupport;
 }
 
-function requestThumbnail(path: string): Promise<string> {
-  // In-memory cache hit — instant
-  const cached = thumbnailCache.get(path);
-  if (cached) return Promise.resolve(cached);
-
-  return new Promise<string>((resolve, reject) => {
-    let subs = pendingBatch.get(path);
-    if (!subs) {
-      subs = [];
-      pendingBatch.set(path, subs);
-    }
-    subs.push({ resolve, reject });
-
-    if (batchTimer === null) {
-      batchTimer = setTimeout(flushBatch, BATCH_DELAY_MS);
-    }
-  });
+function extensionOf(path: string): string {
+  const dot = path.lastIndexOf(".");
+  return dot >= 0 ? path.slice(dot + 1).toLowerCase() : "";
 }
 
 // --- Component ---
 
-export const LazyThumbnail = memo(function LazyThumbnail({ path, alt, className }: LazyThumbnailProps) {
+export const LazyThumbnail = memo(function LazyThumbnail({ photoId, path, alt, className }: LazyThumbnailProps) {
   const ref = useRef<HTMLDivElement>(null);
-  const [src, setSrc] = useState<string | null>(() => thumbnailCache.get(path) ?? null);
-  const [loading, setLoading] = useState(false);
+  const [visible, setVisible] = useState(() => loadedThumbnails.has(photoId));
+  const [loaded, setLoaded] = useState(() => loadedThumbnails.has(photoId));
+  // Set when no thumbnail could be made: the tooltip for the format label
+  const [failure, setFailure] = useState<string | null>(null);
 
   useEffect(() => {
-    if (thumbnailCache.has(path)) {
-      setSrc(thumbnailCache.get(path)!);
+    setFailure(null);
+    if (loadedThumbnails.has(photoId)) {
+      setVisible(true);
+      setLoaded(true);
       return;
     }
+    setVisible(false);
+    setLoaded(false);
 
     const el = ref.current;
     if (!el) return;
 
-    let cancelled = false;
-
     const observer = new IntersectionObserver(
       (entries) => {
         if (entries[0].isIntersecting) {
           observer.disconnect();
-          setLoading(true);
-          requestThumbnail(path)
-            .then((data) => { if (!cancelled) setSrc(data); })
-            .catch(() => {})
-            .finally(() => { if (!cancelled) setLoading(false); });
+          setVisible(true);
         }
       },
       { rootMargin: "200px" }
     );
 
     observer.observe(el);
-    return () => { cancelled = true; observer.disconnect(); };
-  }, [path]);
-
-  if (src) {
-    return <img src={src} alt={alt} className={className} />;
+    return () => observer.disconnect();
+  }, [photoId]);
+
+  const handleLoad = () => {
+    loadedThumbnails.add(photoId);
+    setLoaded(true);
+  };
+
+  const handleError = async () => {
+    const ext = extensionOf(path);
+    const decodable = (await decodableFormats()).get(ext) ?? true;
+    setFailure(decodable ? "No preview available" : `.${ext} files can't be decoded by this build`);
+  };
+
+  if (loaded) {
+    return <img src={thumbnailUrl(photoId)} alt={alt} className={className} />;
   }
 
   return (
-    <div ref={ref} className={`lazy-thumb-placeholder ${className ?? ""}`}>
-      {loading && <div className="thumb-spinner" />}
+    <div ref={ref} className={`lazy-thumb-placeholder ${className ?? ""}`} title={failure ?? undefined}>
+      {visible && !failure && (
+        <>
+          <div className="thumb-spinner" />
+          {/* Loads hidden; the tile swaps to a plain <img> once it arrives */}
+          <img src={thumbnailUrl(photoId)} alt="" hidden onLoad={handleLoad} onError={handleError} />
+        </>
+      )}
+      {failure && <span className="thumb-format">{extensionOf(path).toUpperCase()}</span>}
     </div>
   );
 });
 
 /** Call this when navigating to a new folder to free memory */
 export function clearThumbnailCache() {
-  thumbnailCache.clear();
+  loadedThumbnails.clear();
 }
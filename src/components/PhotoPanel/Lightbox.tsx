@@ -1,5 +1,5 @@
 import { useEffect, useCallback, useState } from "react";
-import { getImageBytes } from "../../api/commands";
+import { fullImageUrl } from "../../api/commands";
 import type { PhotoEntry } from "../../types";
 
 interface LightboxProps {
@@ -10,7 +10,6 @@ interface LightboxProps {
 }
 
 export function Lightbox({ photos, index, onClose, onIndexChange }: LightboxProps) {
-  const [imageSrc, setImageSrc] = useState<string | null>(null);
   const [loading, setLoading] = useState(true);
 
   const photo = photos[index];
@@ -44,28 +43,7 @@ export function Lightbox({ photos, index, onClose, onIndexChange }: LightboxProp
 
   useEffect(() => {
     setLoading(true);
-    let cancelled = false;
-    let objectUrl: string | null = null;
-    
-    // Fetch raw image bytes
-    // WebView2 (Chromium) automatically respects EXIF orientation in <img> tags.
-    // We don't need to manually rotate via CSS.
-    getImageBytes(photo.path)
-      .then((bytes) => {
-        if (cancelled) return;
-        const blob = new Blob([new Uint8Array(bytes)], { type: 'image/jpeg' });
-        objectUrl = URL.createObjectURL(blob);
-        setImageSrc(objectUrl);
-      })
-      .catch((e) => {
-        if (!cancelled) console.error("Failed to load image", e);
-      });
-
-    return () => { 
-      cancelled = true; 
-      if (objectUrl) URL.revokeObjectURL(objectUrl);
-    };
-  }, [photo.path]);
+  }, [photo.id]);
 
   return (
     <div className="lightbox-overlay" onClick={onClose}>
@@ -74,19 +52,24 @@ export function Lightbox({ photos, index, onClose, onIndexChange }: LightboxProp
         onClick={(e) => e.stopPropagation()}
         style={{ position: 'relative', minWidth: '200px', minHeight: '200px' }}
       >
-        {imageSrc && (
-          <img 
-            src={imageSrc} 
-            alt={photo.name} 
-            className="lightbox-image" 
-            style={{ 
-              opacity: loading ? 0 : 1, // Hide until loaded to prevent jump
-              transition: "opacity 0.2s ease-in"
-            }}
-            onLoad={() => setLoading(false)}
-            onError={() => setLoading(false)}
-          />
-        )}
+        {/* The original file, loaded straight from the backend's image protocol.
+            WebView2 (Chromium) automatically respects EXIF orientation in <img> tags,
+            so we don't need to manually rotate via CSS. */}
+        <img 
+          key={photo.id}
+          src={fullImageUrl(photo.id)} 
+          alt={photo.name} 
+          className="lightbox-image" 
+          style={{ 
+            opacity: loading ? 0 : 1, // Hide until loaded to prevent jump
+            transition: "opacity 0.2s ease-in"
+          }}
+          onLoad={() => setLoading(false)}
+          onError={() => {
+            console.error("Failed to load image", photo.path);
+            setLoading(false);
+          }}
+        />
         {loading && (
           <div 
             className="lightbox-loading" 
@@ -1,5 +1,6 @@
-import { memo, useMemo, useCallback, useRef, type MouseEvent, type Dispatch } from "react";
+import { memo, useMemo, useCallback, useRef, type DragEvent, type MouseEvent, type Dispatch } from "react";
 import type { AppAction, PhotoEntry } from "../../types";
+import { Layers } from "lucide-react";
 import { LazyThumbnail } from "./LazyThumbnail";
 import "./PhotoGrid.css";
 
@@ -9,6 +10,16 @@ function formatSize(bytes: number): string {
   return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
 }
 
+function fileName(path: string): string {
+  return path.split(/[\\/]/).pop() ?? path;
+}
+
+function fileExtension(path: string): string {
+  const name = fileName(path);
+  const dot = name.lastIndexOf(".");
+  return dot >= 0 ? name.slice(dot + 1).toUpperCase() : name;
+}
+
 interface PhotoGridItemProps {
   photo: PhotoEntry;
   index: number;
@@ -19,17 +30,22 @@ interface PhotoGridItemProps {
   onItemClick: (photo: PhotoEntry, index: number, e: MouseEvent) => void;
   onItemDoubleClick: (index: number) => void;
   onCheckClick: (photo: PhotoEntry, e: MouseEvent) => void;
+  onDropOn?: (path: string, e: DragEvent) => void;
 }
 
 const PhotoGridItem = memo(function PhotoGridItem({
   photo, index, isSelected, isMultiSelected, isFocused, showOverlay,
-  onItemClick, onItemDoubleClick, onCheckClick,
+  onItemClick, onItemDoubleClick, onCheckClick, onDropOn,
 }: PhotoGridItemProps) {
   return (
     <div
-      className={`photo-grid-item ${isSelected ? "selected" : ""} ${isMultiSelected ? "multi-selected" : ""} ${isFocused ? "focused" : ""}`}
+      className={`photo-grid-item ${isSelected ? "selected" : ""} ${isMultiSelected ? "multi-selected" : ""} ${isFocused ? "focused" : ""} ${photo.flag === "reject" ? "rejected" : ""}`}
       onClick={(e) => onItemClick(photo, index, e)}
       onDoubleClick={() => onItemDoubleClick(index)}
+      draggable={!!onDropOn}
+      onDragStart={onDropOn && ((e) => e.dataTransfer.setData("text/plain", photo.path))}
+      onDragOver={onDropOn && ((e) => e.preventDefault())}
+      onDrop={onDropOn && ((e) => onDropOn(photo.path, e))}
     >
       <div
         className={`select-check ${isMultiSelected ? "checked" : ""}`}
@@ -42,7 +58,30 @@ const PhotoGridItem = memo(function PhotoGridItem({
         )}
       </div>
       <div className="photo-thumb-container">
-        <LazyThumbnail path={photo.path} alt={photo.name}/>
+        <LazyThumbnail photoId={photo.id} path={photo.path} alt={photo.name}/>
+        {(photo.rating > 0 || photo.color_label || photo.flag !== "none") && (
+          <div className="photo-culling">
+            {photo.color_label && <span className={`photo-label ${photo.color_label}`} title={photo.color_label}/>}
+            {photo.rating > 0 && <span className="photo-rating">{"★".repeat(photo.rating)}</span>}
+            {photo.flag === "pick" && <span className="photo-flag pick" title="Pick">⚑</span>}
+            {photo.flag === "reject" && <span className="photo-flag reject" title="Reject">✕</span>}
+          </div>
+        )}
+        {(photo.stack_size > 1 || photo.companions.length > 0) && (
+          <div className="photo-badges">
+            {photo.stack_size > 1 && (
+              <span className="photo-stack" title={`Stack of ${photo.stack_size} photos`}>
+                <Layers size={11} />
+                {photo.stack_size}
+              </span>
+            )}
+            {photo.companions.length > 0 && (
+              <span className="photo-pair" title={`Paired with ${photo.companions.map(fileName).join(", ")}`}>
+                +{photo.companions.map(fileExtension).join(" +")}
+              </span>
+            )}
+          </div>
+        )}
         {showOverlay && (
           <div className="photo-overlay">
             {photo.width && photo.height && (
@@ -80,11 +119,13 @@ interface PhotoGridProps {
   onSelect: (photo: PhotoEntry) => void;
   onOpen: (index: number) => void;
   onFocus: (index: number) => void;
+  // Enables drag and drop; called with the dragged path and the path it was dropped on
+  onReorder?: (from: string, to: string) => void;
 }
 
 export const PhotoGrid = memo(function PhotoGrid({
   photos, selectedPhoto, selectedPaths, showOverlay, focusedIndex, startIndex = 0,
-  dispatch, onSelect, onOpen, onFocus,
+  dispatch, onSelect, onOpen, onFocus, onReorder,
 }: PhotoGridProps) {
   const selectedSet = useMemo(() => new Set(selectedPaths), [selectedPaths]);
 
@@ -109,6 +150,12 @@ export const PhotoGrid = memo(function PhotoGrid({
     dispatch({type: "TOGGLE_SELECTION", path: photo.path});
   }, [dispatch]);
 
+  const handleDrop = useCallback((path: string, e: DragEvent) => {
+    e.preventDefault();
+    const from = e.dataTransfer.getData("text/plain");
+    if (from && from !== path) onReorder?.(from, path);
+  }, [onReorder]);
+
   if (photos.length === 0) {
     return (
       <div className="photo-empty">
@@ -138,6 +185,7 @@ export const PhotoGrid = memo(function PhotoGrid({
             onItemClick={handleItemClick}
             onItemDoubleClick={onOpen}
             onCheckClick={handleCheckClick}
+            onDropOn={onReorder && handleDrop}
           />
         );
       })}
@@ -53,7 +53,7 @@ const PhotoListItem = memo(function PhotoListItem({
         </div>
       </span>
       <span className="list-col-thumb">
-        <LazyThumbnail path={photo.path} alt={photo.name}/>
+        <LazyThumbnail photoId={photo.id} path={photo.path} alt={photo.name}/>
       </span>
       <span className="list-col-name" title={photo.name}>
         {photo.name}
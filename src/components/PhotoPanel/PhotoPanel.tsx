@@ -8,7 +8,12 @@ import { Lightbox } from "./Lightbox";
 import { SelectionActionBar } from "./SelectionActionBar";
 import { GroupIndex } from "./GroupIndex";
 import "./PhotoPanel.css";
-import type { PhotoEntry } from "../../types";
+import { reorderAlbum, setColorLabel, setFlag, setRating } from "../../api/commands";
+import type { ColorLabel, PhotoEntry, PhotoFlag } from "../../types";
+
+// Lightroom-style culling shortcuts: 0-5 rate, 6-9 label, P/X/U flag
+const LABEL_KEYS: Record<string, ColorLabel> = { "6": "red", "7": "yellow", "8": "green", "9": "blue" };
+const FLAG_KEYS: Record<string, PhotoFlag> = { p: "pick", x: "reject", u: "none" };
 
 const PALETTE_ORDER = [
   "Red", "Orange", "Yellow", "Green", "Cyan", "Blue", "Purple", "Pink", "Brown", "Black", "Grey", "White", "Unknown"
@@ -91,6 +96,62 @@ export function PhotoPanel() {
   const selectedPathsRef = useRef(state.selectedPaths);
   selectedPathsRef.current = state.selectedPaths;
 
+  // Albums can be rearranged by dragging while they show their full contents in album order
+  const album = state.selectedAlbum;
+  const canReorder = !!album && state.sortBy === "position" && state.sortOrder === "asc"
+    && !state.searchQuery && !state.colorGroups
+    && state.tagFilter.include.length === 0 && state.tagFilter.exclude.length === 0;
+
+  // Moves the dragged photo (or the whole selection, if it is part of it) in front of the drop target
+  const handleReorder = useCallback(async (from: string, to: string) => {
+    if (!album) return;
+    const photos = photosRef.current;
+    const selected = new Set(selectedPathsRef.current);
+    const moving = selected.has(from) ? photos.filter(p => selected.has(p.path)) : photos.filter(p => p.path === from);
+    if (moving.some(p => p.path === to)) return;
+
+    const rest = photos.filter(p => !moving.includes(p));
+    const at = rest.findIndex(p => p.path === to);
+    const reordered = [...rest.slice(0, at), ...moving, ...rest.slice(at)];
+    try {
+      await reorderAlbum(album.id, reordered.map(p => p.path));
+      dispatch({ type: "SET_PHOTOS", photos: reordered });
+    } catch (e: any) {
+      dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+    }
+  }, [album, dispatch]);
+
+  // Apply a culling change to the selection, or to the focused photo when nothing is selected
+  const applyCulling = useCallback(async (key: string) => {
+    const focused = photosRef.current[focusedIndexRef.current];
+    const paths = selectedPathsRef.current.length > 0
+      ? selectedPathsRef.current
+      : focused ? [focused.path] : [];
+    if (paths.length === 0) return;
+
+    try {
+      if (key >= "0" && key <= "5") {
+        const rating = Number(key);
+        await setRating(paths, rating);
+        dispatch({ type: "UPDATE_PHOTO_CULLING", paths, changes: { rating } });
+      } else if (LABEL_KEYS[key]) {
+        // Pressing the label every target already has clears it
+        const targets = new Set(paths);
+        const allLabeled = photosRef.current
+          .filter(p => targets.has(p.path))
+          .every(p => p.color_label === LABEL_KEYS[key]);
+        const label = allLabeled ? null : LABEL_KEYS[key];
+        await setColorLabel(paths, label);
+        dispatch({ type: "UPDATE_PHOTO_CULLING", paths, changes: { color_label: label } });
+      } else if (FLAG_KEYS[key]) {
+        await setFlag(paths, FLAG_KEYS[key]);
+        dispatch({ type: "UPDATE_PHOTO_CULLING", paths, changes: { flag: FLAG_KEYS[key] } });
+      }
+    } catch (e: any) {
+      dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+    }
+  }, [dispatch]);
+
   // Reset focused index when photos change (but not when just grouping changes if possible? No, order changes)
   useEffect(() => {
     setFocusedIndex(-1);
@@ -156,6 +217,13 @@ export function PhotoPanel() {
       const len = photosRef.current.length;
       if (len === 0) return;
 
+      const key = e.key.toLowerCase();
+      if (!e.ctrlKey && !e.metaKey && !e.altKey && (/^[0-9]$/.test(key) || FLAG_KEYS[key])) {
+        e.preventDefault();
+        applyCulling(key);
+        return;
+      }
+
       if (e.key === "Enter" && cur >= 0) {
         e.preventDefault();
         setLightboxIndex(cur);
@@ -196,9 +264,9 @@ export function PhotoPanel() {
 
     window.addEventListener("keydown", handleKeyDown);
     return () => window.removeEventListener("keydown", handleKeyDown);
-  }, [dispatch, navigateTo]);
+  }, [dispatch, navigateTo, applyCulling]);
 
-  if (!state.selectedFolder) {
+  if (!state.selectedFolder && !state.selectedAlbum) {
     return (
       <div className="photo-panel">
         <div className="photo-welcome">
@@ -282,6 +350,7 @@ export function PhotoPanel() {
                 onSelect={selectPhoto}
                 onOpen={openLightbox}
                 onFocus={setFocusedIndex}
+                onReorder={canReorder ? handleReorder : undefined}
               />
             )
           ) : (
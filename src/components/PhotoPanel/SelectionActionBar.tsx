@@ -2,11 +2,13 @@ import { useState } from "react";
 import { open, ask } from "@tauri-apps/plugin-dialog";
 import { useAppState, useAppDispatch } from "../../hooks/useAppState";
 import { usePhotos } from "../../hooks/usePhotos";
-import { deleteFiles, moveFiles, copyFiles } from "../../api/commands";
+import {
+  deleteFiles, moveFiles, copyFiles, addToAlbum, removeFromAlbum, listAlbums, setStackCover, unstackPhotos,
+} from "../../api/commands";
 import { FindSimilarButton } from "./FindSimilarButton";
 
 export function SelectionActionBar() {
-  const { selectedPaths, selectedFolder } = useAppState();
+  const { selectedPaths, selectedFolder, albums, selectedAlbum, photos } = useAppState();
   const dispatch = useAppDispatch();
   const { loadPhotos } = usePhotos();
   const [busy, setBusy] = useState(false);
@@ -67,6 +69,55 @@ export function SelectionActionBar() {
     }
   };
 
+  // Refresh album counts and covers after membership changes
+  const refreshAlbums = async () => {
+    dispatch({ type: "SET_ALBUMS", albums: await listAlbums() });
+  };
+
+  const handleAddToAlbum = async (albumId: number) => {
+    setBusy(true);
+    try {
+      await addToAlbum(albumId, selectedPaths);
+      await refreshAlbums();
+    } catch (err) {
+      alert(`Add to album failed: ${err}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleRemoveFromAlbum = async () => {
+    if (!selectedAlbum) return;
+    setBusy(true);
+    try {
+      await removeFromAlbum(selectedAlbum.id, selectedPaths);
+      dispatch({ type: "REMOVE_PHOTOS", paths: selectedPaths });
+      await refreshAlbums();
+    } catch (err) {
+      alert(`Remove from album failed: ${err}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const targetAlbums = albums.filter((a) => a.id !== selectedAlbum?.id);
+
+  const selectedSet = new Set(selectedPaths);
+  const stacked = photos.filter((p) => selectedSet.has(p.path) && p.stack_id !== null);
+
+  // Stack changes alter which photos a collapsed view shows, so re-query
+  const runStackAction = async (action: () => Promise<unknown>, label: string) => {
+    setBusy(true);
+    try {
+      await action();
+      if (selectedFolder) loadPhotos(selectedFolder, { clearCache: false, silent: true });
+    } catch (err) {
+      alert(`${label} failed: ${err}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
   return (
     <div className="selection-action-bar">
       <span className="selection-count">
@@ -93,6 +144,42 @@ export function SelectionActionBar() {
           </svg>
           Copy to...
         </button>
+        {targetAlbums.length > 0 && (
+          <select
+            className="selection-btn"
+            value=""
+            onChange={(e) => handleAddToAlbum(Number(e.target.value))}
+            disabled={busy}
+          >
+            <option value="" disabled>Add to album...</option>
+            {targetAlbums.map((album) => (
+              <option key={album.id} value={album.id}>{album.name}</option>
+            ))}
+          </select>
+        )}
+        {selectedAlbum && (
+          <button className="selection-btn" onClick={handleRemoveFromAlbum} disabled={busy}>
+            Remove from album
+          </button>
+        )}
+        {stacked.length === 1 && selectedPaths.length === 1 && (
+          <button
+            className="selection-btn"
+            onClick={() => runStackAction(() => setStackCover(stacked[0].path), "Set stack cover")}
+            disabled={busy}
+          >
+            Set as stack cover
+          </button>
+        )}
+        {stacked.length > 0 && (
+          <button
+            className="selection-btn"
+            onClick={() => runStackAction(() => unstackPhotos(stacked.map((p) => p.path)), "Unstack")}
+            disabled={busy}
+          >
+            Unstack
+          </button>
+        )}
         <FindSimilarButton disabled={busy} />
       </div>
       <button
@@ -0,0 +1,93 @@
+import { useState, useEffect, useCallback } from "react";
+import { X } from "lucide-react";
+import { useAppDispatch } from "../../hooks/useAppState";
+import { getPhotoTags, addManualTag, removeManualTag } from "../../api/commands";
+import type { PhotoEntry, PhotoTag } from "../../types";
+import "./PhotoPanel.css";
+
+interface TagEditorProps {
+  photo: PhotoEntry;
+}
+
+// Distinct names in display order (manual first), as the backend returns them in PhotoEntry.tags
+function tagNames(tags: PhotoTag[]): string[] {
+  return Array.from(new Set(tags.map(t => t.tag)));
+}
+
+export function TagEditor({ photo }: TagEditorProps) {
+  const dispatch = useAppDispatch();
+  const [tags, setTags] = useState<PhotoTag[]>([]);
+  const [input, setInput] = useState("");
+
+  const refresh = useCallback(async () => {
+    try {
+      const next = await getPhotoTags(photo.path);
+      setTags(next);
+      dispatch({ type: "UPDATE_PHOTO_TAGS", updates: [{ path: photo.path, tags: tagNames(next) }] });
+    } catch (e) {
+      console.error("Failed to load tags:", e);
+    }
+  }, [photo.path, dispatch]);
+
+  useEffect(() => {
+    let cancelled = false;
+    getPhotoTags(photo.path)
+      .then(next => { if (!cancelled) setTags(next); })
+      .catch(e => console.error("Failed to load tags:", e));
+    return () => { cancelled = true; };
+  }, [photo.path]);
+
+  const handleAdd = async () => {
+    const tag = input.trim();
+    if (!tag) return;
+    try {
+      await addManualTag([photo.path], tag);
+      setInput("");
+      await refresh();
+    } catch (e) {
+      console.error("Failed to add tag:", e);
+    }
+  };
+
+  const handleRemove = async (tag: string) => {
+    try {
+      await removeManualTag([photo.path], tag);
+      await refresh();
+    } catch (e) {
+      console.error("Failed to remove tag:", e);
+    }
+  };
+
+  return (
+    <div className="tag-editor">
+      <span className="exif-label">Tags</span>
+      <div className="tag-editor-list">
+        {tags.map(t => (
+          <span
+            key={`${t.source}:${t.tag}`}
+            className={`tag-chip ${t.source === "manual" ? "manual" : ""}`}
+            title={t.source === "manual"
+              ? "Added manually"
+              : `${t.source}${t.confidence != null ? ` (${(t.confidence * 100).toFixed(0)}%)` : ""}`}
+          >
+            {t.tag}
+            {t.source === "manual" && (
+              <button className="tag-chip-remove" onClick={() => handleRemove(t.tag)} title="Remove tag">
+                <X size={10} />
+              </button>
+            )}
+          </span>
+        ))}
+        {tags.length === 0 && <span className="tag-editor-empty">No tags</span>}
+      </div>
+      <input
+        className="tag-editor-input"
+        type="text"
+        placeholder="Add tag..."
+        value={input}
+        onChange={e => setInput(e.target.value)}
+        onKeyDown={e => { if (e.key === "Enter") handleAdd(); }}
+      />
+    </div>
+  );
+}
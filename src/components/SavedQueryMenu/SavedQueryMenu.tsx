@@ -0,0 +1,150 @@
+import { useEffect, useRef, useState } from "react";
+import { Bookmark, Pin, PinOff, Trash2 } from "lucide-react";
+import { deleteSavedQuery, pinSavedQuery, refreshSavedQueryCounts, saveQuery } from "../../api/commands";
+import { useAppDispatch, useAppState } from "../../hooks/useAppState";
+import type { PhotoQuery, SavedQuery } from "../../types";
+import "./SavedQueryMenu.css";
+
+export function SavedQueryMenu() {
+  const state = useAppState();
+  const dispatch = useAppDispatch();
+  const [isOpen, setIsOpen] = useState(false);
+  const [name, setName] = useState("");
+  const ref = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    if (!isOpen) return;
+    function handleClickOutside(e: MouseEvent) {
+      if (ref.current && !ref.current.contains(e.target as Node)) {
+        setIsOpen(false);
+      }
+    }
+    document.addEventListener("mousedown", handleClickOutside);
+    return () => document.removeEventListener("mousedown", handleClickOutside);
+  }, [isOpen]);
+
+  const showError = (e: any) => dispatch({ type: "SET_ERROR", error: e?.message ?? String(e) });
+
+  // Counts may be stale after imports or tagging, so recount whenever the menu opens
+  const refresh = async () => {
+    dispatch({ type: "SET_SAVED_QUERIES", queries: await refreshSavedQueryCounts() });
+  };
+
+  useEffect(() => {
+    if (isOpen) refresh().catch(showError);
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [isOpen]);
+
+  // The view as it is on screen; similarity results are ordered closest first
+  const currentQuery = (): PhotoQuery => ({
+    folder: state.selectedFolder ?? "",
+    albumId: state.selectedAlbum?.id ?? null,
+    search: state.searchQuery || null,
+    sortBy: state.similaritySearch ? "similarity" : state.sortBy,
+    sortOrder: state.similaritySearch ? "asc" : state.sortOrder,
+    tagFilter: state.tagFilter,
+    recursive: state.includeSubfolders,
+    similarity: state.similaritySearch,
+    collapseStacks: state.collapseStacks,
+  });
+
+  const canSave = !!state.selectedFolder || !!state.selectedAlbum;
+
+  const handleSave = async () => {
+    const trimmed = name.trim();
+    if (!trimmed || !canSave) return;
+    try {
+      const saved = await saveQuery(trimmed, currentQuery());
+      setName("");
+      await refresh();
+      dispatch({ type: "APPLY_SAVED_QUERY", saved });
+    } catch (e) {
+      showError(e);
+    }
+  };
+
+  const handleApply = (saved: SavedQuery) => {
+    dispatch({ type: "APPLY_SAVED_QUERY", saved });
+    setIsOpen(false);
+  };
+
+  const handleTogglePin = async (saved: SavedQuery) => {
+    try {
+      await pinSavedQuery(saved.id, !saved.pinned);
+      await refresh();
+    } catch (e) {
+      showError(e);
+    }
+  };
+
+  const handleDelete = async (saved: SavedQuery) => {
+    if (!(await window.confirm(`Delete the saved search "${saved.name}"?`))) return;
+    try {
+      await deleteSavedQuery(saved.id);
+      await refresh();
+    } catch (e) {
+      showError(e);
+    }
+  };
+
+  return (
+    <div className="saved-query-menu" ref={ref}>
+      <button
+        className={`saved-query-btn ${state.activeSavedQuery ? "active" : ""}`}
+        onClick={() => setIsOpen(!isOpen)}
+        title="Saved searches"
+      >
+        <Bookmark size={14} />
+        {state.activeSavedQuery ? state.activeSavedQuery.name : "Saved"}
+      </button>
+
+      {isOpen && (
+        <div className="saved-query-dropdown">
+          <div className="saved-query-save">
+            <input
+              value={name}
+              placeholder={canSave ? "Save current view as..." : "Open a folder or album first"}
+              disabled={!canSave}
+              onChange={(e) => setName(e.target.value)}
+              onKeyDown={(e) => {
+                if (e.key === "Enter") handleSave();
+              }}
+            />
+            <button onClick={handleSave} disabled={!canSave || !name.trim()}>Save</button>
+          </div>
+          <div className="saved-query-list">
+            {state.savedQueries.length === 0 && (
+              <div className="saved-query-empty">No saved searches</div>
+            )}
+            {state.savedQueries.map((saved) => (
+              <div
+                key={saved.id}
+                className={`saved-query-item ${state.activeSavedQuery?.id === saved.id ? "selected" : ""}`}
+                onClick={() => handleApply(saved)}
+                title={saved.query.search ?? undefined}
+              >
+                <span className="saved-query-name">{saved.name}</span>
+                {saved.cached_count !== null && <span className="saved-query-count">{saved.cached_count}</span>}
+                <button
+                  onClick={(e) => { e.stopPropagation(); handleTogglePin(saved); }}
+                  title={saved.pinned ? "Remove from smart albums" : "Pin as smart album"}
+                  type="button"
+                >
+                  {saved.pinned ? <PinOff size={12} /> : <Pin size={12} />}
+                </button>
+                <button
+                  className="saved-query-delete"
+                  onClick={(e) => { e.stopPropagation(); handleDelete(saved); }}
+                  title="Delete saved search"
+                  type="button"
+                >
+                  <Trash2 size={12} />
+                </button>
+              </div>
+            ))}
+          </div>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -9,7 +9,7 @@ type SearchBoxProps = {
 };
 
 export const SearchBox = forwardRef<HTMLInputElement, SearchBoxProps>(
-  ({value, onChange, placeholder = "Search photos... (Ctrl+F)"}, ref) => {
+  ({value, onChange, placeholder = "Search photos, or tag:dog iso:>1600 has:gps... (Ctrl+F)"}, ref) => {
     return (
       <div className="search-box">
         <Search color="white" size={12}/>
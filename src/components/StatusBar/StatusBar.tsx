@@ -1,7 +1,7 @@
 import { useMemo } from "react";
 import { useAppState } from "../../hooks/useAppState";
 import "./StatusBar.css";
-import { LucideFolder, LucideHardDrive, LucideImages } from "lucide-react";
+import { LucideBookImage, LucideFolder, LucideHardDrive, LucideImages } from "lucide-react";
 
 function formatSize(bytes: number): string {
   if (bytes < 1024) return `${bytes} B`;
@@ -19,7 +19,7 @@ export function StatusBar() {
     return {count: state.photos.length, size: formatSize(totalSize)};
   }, [state.photos]);
 
-  if (!state.selectedFolder) return null;
+  if (!state.selectedFolder && !state.selectedAlbum) return null;
 
   return (
     <div className="status-bar">
@@ -37,12 +37,21 @@ export function StatusBar() {
           <span className="status-bar-separator"/>
         </>
       )}
-      <span className="status-bar-item status-bar-path" title={state.selectedFolder}>
-        <LucideFolder size={14}/>
-        <span style={{overflow: "hidden", textOverflow: "ellipsis"}}>
-          {state.selectedFolder}
+      {state.selectedAlbum ? (
+        <span className="status-bar-item status-bar-path" title={state.selectedAlbum.name}>
+          <LucideBookImage size={14}/>
+          <span style={{overflow: "hidden", textOverflow: "ellipsis"}}>
+            {state.selectedAlbum.name}
+          </span>
+        </span>
+      ) : (
+        <span className="status-bar-item status-bar-path" title={state.selectedFolder ?? undefined}>
+          <LucideFolder size={14}/>
+          <span style={{overflow: "hidden", textOverflow: "ellipsis"}}>
+            {state.selectedFolder}
+          </span>
         </span>
-      </span>
+      )}
     </div>
   );
 }
@@ -1,22 +1,32 @@
 import { useEffect, useRef, useState } from "react";
-import { TagsIcon, Trash2 } from "lucide-react";
+import { Check, Minus, Settings2, TagsIcon, Trash2 } from "lucide-react";
 import { deleteAllTags } from "../../api/commands";
 import { useAppState } from "../../hooks/useAppState";
 import { usePhotos } from "../../hooks/usePhotos";
+import { TagManagerDialog } from "../TagManagerDialog/TagManagerDialog";
+import type { TagFilterState, TagMatchMode } from "../../types";
 import "./TagFilterDropdown.css";
 
 type TagFilterDropdownProps = {
   availableTags: string[];
-  selectedTags: string[];
-  onToggleTag: (tag: string) => void;
+  tagFilter: TagFilterState;
+  onChange: (filter: TagFilterState) => void;
 };
 
+/** Human-readable form of the filter, e.g. "dog AND beach NOT night". */
+function describeFilter({ include, exclude, mode }: TagFilterState): string {
+  const included = include.join(mode === "all" ? " AND " : " OR ");
+  const excluded = exclude.map(t => `NOT ${t}`).join(" ");
+  return [included, excluded].filter(Boolean).join(" ");
+}
+
 export function TagFilterDropdown({
                                     availableTags,
-                                    selectedTags,
-                                    onToggleTag,
+                                    tagFilter,
+                                    onChange,
                                   }: TagFilterDropdownProps) {
   const [isOpen, setIsOpen] = useState(false);
+  const [showManager, setShowManager] = useState(false);
   const ref = useRef<HTMLDivElement>(null);
   const state = useAppState();
   const { loadPhotos } = usePhotos();
@@ -37,7 +47,7 @@ export function TagFilterDropdown({
     const confirm = await window.confirm("Are you sure you want to delete ALL tags in this folder?");
     if (confirm) {
       try {
-        await deleteAllTags(state.selectedFolder);
+        await deleteAllTags(state.selectedFolder, state.includeSubfolders);
         setIsOpen(false);
         // Refresh photos to update UI
         await loadPhotos(state.selectedFolder, { clearCache: false, silent: true });
@@ -47,40 +57,95 @@ export function TagFilterDropdown({
     }
   };
 
+  // Each click cycles a tag: off -> include -> exclude -> off
+  const cycleTag = (tag: string) => {
+    const include = tagFilter.include.filter(t => t !== tag);
+    const exclude = tagFilter.exclude.filter(t => t !== tag);
+    if (tagFilter.include.includes(tag)) {
+      exclude.push(tag);
+    } else if (!tagFilter.exclude.includes(tag)) {
+      include.push(tag);
+    }
+    onChange({ ...tagFilter, include, exclude });
+  };
+
+  const setMode = (mode: TagMatchMode) => onChange({ ...tagFilter, mode });
+
+  const activeCount = tagFilter.include.length + tagFilter.exclude.length;
+
   if (availableTags.length === 0) return null;
 
   return (
     <div className="tag-filter-dropdown" ref={ref}>
       <button
-        className={`tag-filter-btn ${selectedTags.length > 0 ? "active" : ""}`}
+        className={`tag-filter-btn ${activeCount > 0 ? "active" : ""}`}
         onClick={() => setIsOpen((v) => !v)}
-        title="Filter by tags"
+        title={activeCount > 0 ? describeFilter(tagFilter) : "Filter by tags"}
         type="button"
       >
         <TagsIcon size={14}/>
 
         <span>
-          {selectedTags.length > 0
-            ? `${selectedTags.length} tags`
+          {activeCount > 0
+            ? `${activeCount} tags`
             : "Tags"}
         </span>
       </button>
 
       {isOpen && (
         <div className="tag-menu">
+          <div className="tag-menu-mode">
+            <span>Match</span>
+            <button
+              className={tagFilter.mode === "all" ? "active" : ""}
+              onClick={() => setMode("all")}
+              title="Photos must have every included tag"
+              type="button"
+            >
+              All
+            </button>
+            <button
+              className={tagFilter.mode === "any" ? "active" : ""}
+              onClick={() => setMode("any")}
+              title="Photos need at least one included tag"
+              type="button"
+            >
+              Any
+            </button>
+          </div>
           <div className="tag-list">
-            {availableTags.map((tag) => (
-              <label key={tag} className="tag-menu-item">
-                <input
-                  type="checkbox"
-                  checked={selectedTags.includes(tag)}
-                  onChange={() => onToggleTag(tag)}
-                />
-                <span>{tag}</span>
-              </label>
-            ))}
+            {availableTags.map((tag) => {
+              const tagState = tagFilter.include.includes(tag)
+                ? "include"
+                : tagFilter.exclude.includes(tag) ? "exclude" : "off";
+              return (
+                <button
+                  key={tag}
+                  className={`tag-menu-item ${tagState}`}
+                  onClick={() => cycleTag(tag)}
+                  title={tagState === "off" ? "Include" : tagState === "include" ? "Exclude" : "Clear"}
+                  type="button"
+                >
+                  <span className="tag-state">
+                    {tagState === "include" && <Check size={10}/>}
+                    {tagState === "exclude" && <Minus size={10}/>}
+                  </span>
+                  <span>{tag}</span>
+                </button>
+              );
+            })}
           </div>
+          {activeCount > 0 && (
+            <div className="tag-menu-summary">{describeFilter(tagFilter)}</div>
+          )}
           <div className="tag-menu-footer">
+            <button
+              className="manage-tags-btn"
+              onClick={() => { setIsOpen(false); setShowManager(true); }}
+            >
+              <Settings2 size={12} />
+              <span>Manage Tags...</span>
+            </button>
             <button className="delete-tags-btn" onClick={handleDeleteAll}>
               <Trash2 size={12} />
               <span>Delete All Tags</span>
@@ -88,6 +153,18 @@ export function TagFilterDropdown({
           </div>
         </div>
       )}
+
+      <TagManagerDialog
+        open={showManager}
+        onClose={() => setShowManager(false)}
+        folder={state.selectedFolder}
+        recursive={state.includeSubfolders}
+        onChanged={() => {
+          if (state.selectedFolder) {
+            loadPhotos(state.selectedFolder, { clearCache: false, silent: true });
+          }
+        }}
+      />
     </div>
   );
 }
\ No newline at end of file
@@ -0,0 +1,173 @@
+import { useCallback, useEffect, useState } from "react";
+import { Pencil, Trash2 } from "lucide-react";
+import { deleteTag, listTags, mergeTags, renameTag } from "../../api/commands";
+import type { TagSummary } from "../../types";
+import "./TagManagerDialog.css";
+
+interface TagManagerDialogProps {
+  open: boolean;
+  onClose: () => void;
+  folder: string | null;
+  recursive: boolean;
+  // Called after any change so the caller can refresh photos and the tag filter
+  onChanged: () => void;
+}
+
+type Scope = "folder" | "library";
+
+export function TagManagerDialog({ open, onClose, folder, recursive, onChanged }: TagManagerDialogProps) {
+  const [scope, setScope] = useState<Scope>(folder ? "folder" : "library");
+  const [tags, setTags] = useState<TagSummary[]>([]);
+  const [selected, setSelected] = useState<Set<string>>(new Set());
+  const [editing, setEditing] = useState<string | null>(null);
+  const [editValue, setEditValue] = useState("");
+  const [mergeInto, setMergeInto] = useState("");
+  const [error, setError] = useState<string | null>(null);
+
+  const scopeFolder = scope === "folder" ? folder : null;
+
+  const refresh = useCallback(async () => {
+    try {
+      setTags(await listTags(scopeFolder, recursive));
+      setError(null);
+    } catch (e: any) {
+      setError(e?.message ?? String(e));
+    }
+  }, [scopeFolder, recursive]);
+
+  useEffect(() => {
+    if (!open) return;
+    setSelected(new Set());
+    refresh();
+  }, [open, refresh]);
+
+  if (!open) return null;
+
+  const run = async (action: () => Promise<unknown>) => {
+    try {
+      await action();
+      setSelected(new Set());
+      setEditing(null);
+      setMergeInto("");
+      await refresh();
+      onChanged();
+    } catch (e: any) {
+      setError(e?.message ?? String(e));
+    }
+  };
+
+  const toggleSelected = (tag: string) => {
+    const next = new Set(selected);
+    if (next.has(tag)) next.delete(tag);
+    else next.add(tag);
+    setSelected(next);
+  };
+
+  const handleRename = (tag: string) => {
+    const newName = editValue.trim();
+    if (!newName || newName === tag) {
+      setEditing(null);
+      return;
+    }
+    run(() => renameTag(tag, newName));
+  };
+
+  const handleDelete = async (tag: string) => {
+    const where = scopeFolder ? "this folder" : "the whole library";
+    if (!(await window.confirm(`Delete the tag "${tag}" from ${where}?`))) return;
+    run(() => deleteTag(tag, scopeFolder, recursive));
+  };
+
+  const handleMerge = () => {
+    const into = mergeInto.trim();
+    if (!into || selected.size === 0) return;
+    run(() => mergeTags(Array.from(selected), into));
+  };
+
+  return (
+    <div className="tag-manager-overlay">
+      <div className="tag-manager-dialog">
+        <h3>Manage Tags</h3>
+
+        <div className="tag-manager-scope">
+          <button
+            className={scope === "folder" ? "active" : ""}
+            onClick={() => setScope("folder")}
+            disabled={!folder}
+            type="button"
+          >
+            This folder
+          </button>
+          <button
+            className={scope === "library" ? "active" : ""}
+            onClick={() => setScope("library")}
+            type="button"
+          >
+            Whole library
+          </button>
+        </div>
+
+        {error && <div className="tag-manager-error">{error}</div>}
+
+        <div className="tag-manager-list">
+          {tags.map(t => (
+            <div className="tag-manager-row" key={t.tag}>
+              <input type="checkbox" checked={selected.has(t.tag)} onChange={() => toggleSelected(t.tag)} />
+              {editing === t.tag ? (
+                <input
+                  className="tag-manager-edit"
+                  autoFocus
+                  value={editValue}
+                  onChange={e => setEditValue(e.target.value)}
+                  onKeyDown={e => {
+                    if (e.key === "Enter") handleRename(t.tag);
+                    if (e.key === "Escape") setEditing(null);
+                  }}
+                  onBlur={() => setEditing(null)}
+                />
+              ) : (
+                <span className="tag-manager-name">{t.tag}</span>
+              )}
+              <span className="tag-manager-count" title="Photos">{t.photo_count}</span>
+              <span className="tag-manager-confidence" title={t.manual_count > 0 ? `${t.manual_count} manual` : "Average confidence"}>
+                {t.avg_confidence != null ? `${(t.avg_confidence * 100).toFixed(0)}%` : "manual"}
+              </span>
+              <button
+                className="tag-manager-action"
+                onClick={() => { setEditing(t.tag); setEditValue(t.tag); }}
+                title="Rename everywhere"
+                type="button"
+              >
+                <Pencil size={12} />
+              </button>
+              <button className="tag-manager-action danger" onClick={() => handleDelete(t.tag)} title="Delete" type="button">
+                <Trash2 size={12} />
+              </button>
+            </div>
+          ))}
+          {tags.length === 0 && <div className="tag-manager-empty">No tags</div>}
+        </div>
+
+        <div className="tag-manager-merge">
+          <input
+            type="text"
+            placeholder={selected.size > 1 ? `Merge ${selected.size} tags into...` : "Select tags to merge"}
+            value={mergeInto}
+            onChange={e => setMergeInto(e.target.value)}
+            onKeyDown={e => { if (e.key === "Enter") handleMerge(); }}
+            disabled={selected.size === 0}
+          />
+          <button onClick={handleMerge} disabled={selected.size === 0 || !mergeInto.trim()} type="button">
+            Merge
+          </button>
+        </div>
+
+        <div className="dialog-actions">
+          <button className="cancel-btn" onClick={onClose} type="button">
+            Close
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
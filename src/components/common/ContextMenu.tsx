@@ -1,5 +1,9 @@
 import { useEffect, useRef } from "react";
+import { Copy, Layers } from "lucide-react";
+import { detectStacks } from "../../api/commands";
 import { useAppState, useAppDispatch } from "../../hooks/useAppState";
+import { useNotifications } from "../../hooks/useNotifications";
+import { usePhotos } from "../../hooks/usePhotos";
 import "./ContextMenu.css";
 
 export function ContextMenu() {
@@ -7,6 +11,8 @@ export function ContextMenu() {
   const dispatch = useAppDispatch();
   const menuRef = useRef<HTMLDivElement>(null);
   const menu = state.contextMenu;
+  const { actions: notifActions } = useNotifications();
+  const { loadPhotos } = usePhotos();
 
   useEffect(() => {
     if (!menu) return;
@@ -64,6 +70,28 @@ export function ContextMenu() {
     });
   };
 
+  const handleFindDuplicates = () => {
+    dispatch({ type: "SET_CONTEXT_MENU", menu: null });
+    dispatch({ type: "SET_DUPLICATES_DIALOG", state: { folderPath: menu.folderPath } });
+  };
+
+  const handleDetectStacks = async () => {
+    dispatch({ type: "SET_CONTEXT_MENU", menu: null });
+    const folder = menu.folderPath;
+    try {
+      const count = await detectStacks(folder, state.includeSubfolders);
+      notifActions.addNotification({
+        id: "stack-status",
+        type: "success",
+        message: `Grouped bursts into ${count} stack${count === 1 ? "" : "s"}.`,
+        autoDismissMs: 5000,
+      });
+      if (folder === state.selectedFolder) loadPhotos(folder, { clearCache: false, silent: true });
+    } catch (err) {
+      notifActions.addNotification({ id: "stack-status", type: "warning", message: `Grouping bursts failed: ${err}`, autoDismissMs: 8000 });
+    }
+  };
+
   return (
     <div
       ref={menuRef}
@@ -77,6 +105,14 @@ export function ContextMenu() {
         </svg>
         Classify Images
       </button>
+      <button className="context-menu-item" onClick={handleFindDuplicates}>
+        <Copy size={14} />
+        Find Duplicates
+      </button>
+      <button className="context-menu-item" onClick={handleDetectStacks}>
+        <Layers size={14} />
+        Stack Bursts
+      </button>
     </div>
   );
 }
@@ -1,5 +1,7 @@
 import { createContext, useContext, useReducer, type Dispatch } from "react";
-import type { AppState, AppAction, TreeNodeData, ClassifyDialogState } from "../types";
+import type { AppState, AppAction, TreeNodeData, ClassifyDialogState, PhotoEntry, SortBy, SortOrder, TagFilterState } from "../types";
+
+const EMPTY_TAG_FILTER: TagFilterState = { include: [], exclude: [], mode: "all" };
 
 const initialClassifyDialog: ClassifyDialogState = {
   open: false,
@@ -15,6 +17,10 @@ export const initialState: AppState = {
   treeNodes: [],
   selectedFolder: null,
   photos: [],
+  albums: [],
+  selectedAlbum: null,
+  savedQueries: [],
+  activeSavedQuery: null,
   selectedPhoto: null,
   exifData: null,
   histogramData: null,
@@ -23,13 +29,16 @@ export const initialState: AppState = {
   sortOrder: "asc",
   searchQuery: "",
   selectedPaths: [],
-  filterTags: [],
+  tagFilter: EMPTY_TAG_FILTER,
+  includeSubfolders: localStorage.getItem("includeSubfolders") === "true",
+  collapseStacks: localStorage.getItem("collapseStacks") === "true",
   loading: false,
   error: null,
   showExif: false,
   showOverlay: localStorage.getItem("showOverlay") === "true",
   classifyDialog: initialClassifyDialog,
   contextMenu: null,
+  duplicatesDialog: null,
   similaritySearch: null,
   querying: false,
   indexingState: null,
@@ -91,9 +100,48 @@ export function appReducer(state: AppState, action: AppAction): AppState {
         treeNodes: toggleTreeNode(state.treeNodes, action.path),
       };
     case "SET_SELECTED_FOLDER":
-      return { ...state, selectedFolder: action.path, selectedPhoto: null, exifData: null, histogramData: null, selectedPaths: [], filterTags: [], similaritySearch: null, colorGroups: null };
+      return {
+        ...state, selectedFolder: action.path, selectedAlbum: null, activeSavedQuery: null, selectedPhoto: null, exifData: null, histogramData: null, selectedPaths: [], tagFilter: EMPTY_TAG_FILTER, similaritySearch: null, colorGroups: null,
+        // Album order only exists inside albums
+        sortBy: state.sortBy === "position" ? "name" : state.sortBy,
+      };
+    case "SET_ALBUMS": {
+      // Keep the selected album's name and count in sync
+      const selectedAlbum = state.selectedAlbum && (action.albums.find(a => a.id === state.selectedAlbum!.id) ?? null);
+      return { ...state, albums: action.albums, selectedAlbum };
+    }
+    case "SET_SELECTED_ALBUM":
+      return {
+        ...state, selectedAlbum: action.album, selectedFolder: null, activeSavedQuery: null, photos: [], selectedPhoto: null, exifData: null, histogramData: null, selectedPaths: [], tagFilter: EMPTY_TAG_FILTER, similaritySearch: null, colorGroups: null,
+        sortBy: action.album ? "position" : state.sortBy, sortOrder: "asc",
+      };
+    case "SET_SAVED_QUERIES": {
+      const activeSavedQuery = state.activeSavedQuery && (action.queries.find(q => q.id === state.activeSavedQuery!.id) ?? null);
+      return { ...state, savedQueries: action.queries, activeSavedQuery };
+    }
+    case "APPLY_SAVED_QUERY": {
+      // Restore the whole view the query was saved from
+      const { query } = action.saved;
+      const album = query.albumId != null ? state.albums.find(a => a.id === query.albumId) ?? null : null;
+      return {
+        ...state,
+        activeSavedQuery: action.saved,
+        selectedFolder: album ? null : query.folder,
+        selectedAlbum: album,
+        photos: [], selectedPhoto: null, exifData: null, histogramData: null, selectedPaths: [], colorGroups: null,
+        searchQuery: query.search ?? "",
+        sortBy: query.sortBy as SortBy,
+        sortOrder: query.sortOrder as SortOrder,
+        tagFilter: query.tagFilter ?? EMPTY_TAG_FILTER,
+        includeSubfolders: query.recursive ?? false,
+        similaritySearch: query.similarity ?? null,
+        collapseStacks: query.collapseStacks ?? false,
+      };
+    }
     case "SET_PHOTOS":
       return { ...state, photos: action.photos, selectedPaths: [] };
+    case "APPEND_PHOTOS":
+      return { ...state, photos: [...state.photos, ...action.photos] };
     case "SET_SELECTED_PHOTO":
       return { ...state, selectedPhoto: action.photo };
     case "SET_EXIF":
@@ -107,7 +155,11 @@ export function appReducer(state: AppState, action: AppAction): AppState {
     case "SET_SORT_ORDER":
       return { ...state, sortOrder: action.order };
     case "SET_SEARCH_QUERY":
-      return { ...state, searchQuery: action.query, selectedPaths: [] };
+      return {
+        ...state, searchQuery: action.query, selectedPaths: [],
+        // Relevance only ranks text searches
+        sortBy: state.sortBy === "relevance" && !action.query.trim() ? "name" : state.sortBy,
+      };
     case "SET_LOADING":
       return { ...state, loading: action.loading };
     case "SET_ERROR":
@@ -119,6 +171,16 @@ export function appReducer(state: AppState, action: AppAction): AppState {
       localStorage.setItem("showOverlay", String(next));
       return { ...state, showOverlay: next };
     }
+    case "TOGGLE_INCLUDE_SUBFOLDERS": {
+      const next = !state.includeSubfolders;
+      localStorage.setItem("includeSubfolders", String(next));
+      return { ...state, includeSubfolders: next, selectedPaths: [], selectedPhoto: null, exifData: null, histogramData: null };
+    }
+    case "TOGGLE_COLLAPSE_STACKS": {
+      const next = !state.collapseStacks;
+      localStorage.setItem("collapseStacks", String(next));
+      return { ...state, collapseStacks: next, selectedPaths: [] };
+    }
     case "SET_CLASSIFY_DIALOG":
       return {
         ...state,
@@ -126,6 +188,8 @@ export function appReducer(state: AppState, action: AppAction): AppState {
       };
     case "SET_CONTEXT_MENU":
       return { ...state, contextMenu: action.menu };
+    case "SET_DUPLICATES_DIALOG":
+      return { ...state, duplicatesDialog: action.state };
     case "UPDATE_PHOTO_TAGS": {
       const tagMap = new Map(action.updates.map((u) => [u.path, u.tags]));
       const newPhotos = state.photos.map((p) => {
@@ -135,6 +199,15 @@ export function appReducer(state: AppState, action: AppAction): AppState {
       });
       return { ...state, photos: newPhotos };
     }
+    case "UPDATE_PHOTO_CULLING": {
+      const paths = new Set(action.paths);
+      const update = (p: PhotoEntry) => (paths.has(p.path) ? { ...p, ...action.changes } : p);
+      return {
+        ...state,
+        photos: state.photos.map(update),
+        selectedPhoto: state.selectedPhoto && update(state.selectedPhoto),
+      };
+    }
     case "SET_SELECTION":
       return { ...state, selectedPaths: action.paths };
     case "TOGGLE_SELECTION": {
@@ -158,8 +231,8 @@ export function appReducer(state: AppState, action: AppAction): AppState {
       return { ...state, selectedPaths: state.photos.map((p) => p.path) };
     case "CLEAR_SELECTION":
       return { ...state, selectedPaths: [] };
-    case "SET_FILTER_TAGS":
-      return { ...state, filterTags: action.tags, selectedPaths: [], selectedPhoto: null, exifData: null, histogramData: null };
+    case "SET_TAG_FILTER":
+      return { ...state, tagFilter: action.filter, selectedPaths: [], selectedPhoto: null, exifData: null, histogramData: null };
     case "SET_SIMILARITY_SEARCH":
       return { ...state, similaritySearch: action.search, selectedPaths: [], selectedPhoto: null, exifData: null, histogramData: null };
     case "SET_SIMILARITY_THRESHOLD":
@@ -1,10 +1,10 @@
 import { useCallback, useEffect, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
-import { listPhotos, queryPhotos, findSimilarPhotos, readExif, getHistogram } from "../api/commands";
+import { listPhotos, queryPhotos, streamPhotos, findSimilarPhotos, readExif, getHistogram } from "../api/commands";
 import { clearThumbnailCache } from "../components/PhotoPanel/LazyThumbnail";
 import { useAppDispatch, useAppState } from "./useAppState";
 import { useNotifications } from "./useNotifications";
-import type { PhotoEntry } from "../types";
+import type { AppError, PhotoEntry } from "../types";
 
 export function usePhotos() {
   const state = useAppState();
@@ -18,8 +18,12 @@ export function usePhotos() {
   sortOrderRef.current = state.sortOrder;
   const searchRef = useRef(state.searchQuery);
   searchRef.current = state.searchQuery;
-  const filterTagsRef = useRef(state.filterTags);
-  filterTagsRef.current = state.filterTags;
+  const tagFilterRef = useRef(state.tagFilter);
+  tagFilterRef.current = state.tagFilter;
+  const recursiveRef = useRef(state.includeSubfolders);
+  recursiveRef.current = state.includeSubfolders;
+  const collapseStacksRef = useRef(state.collapseStacks);
+  collapseStacksRef.current = state.collapseStacks;
   const similaritySearchRef = useRef(state.similaritySearch);
   similaritySearchRef.current = state.similaritySearch;
   const notifActionsRef = useRef(notifActions);
@@ -30,7 +34,8 @@ export function usePhotos() {
   const indexThrottleRef = useRef(0);
 
   const loadPhotos = useCallback(
-    async (folderPath: string, opts?: { clearCache?: boolean; silent?: boolean }) => {
+    async (folderPath: string, opts?: { clearCache?: boolean; silent?: boolean; recursive?: boolean }) => {
+      const recursive = opts?.recursive ?? recursiveRef.current;
       if (!opts?.silent) {
         dispatch({ type: "SET_LOADING", loading: true });
       }
@@ -46,7 +51,11 @@ export function usePhotos() {
           searchRef.current || null,
           sortByRef.current,
           sortOrderRef.current,
-          filterTagsRef.current
+          tagFilterRef.current,
+          recursive,
+          null,
+          null,
+          collapseStacksRef.current
         );
         if (cached.length > 0) {
           dispatch({ type: "SET_PHOTOS", photos: cached });
@@ -54,7 +63,7 @@ export function usePhotos() {
         }
 
         // Import phase — sync filesystem to DB (runs after cached results are shown)
-        await listPhotos(folderPath);
+        await listPhotos(folderPath, recursive);
 
         // Re-query for updated results (picks up new/modified/deleted files)
         const photos = await queryPhotos(
@@ -62,7 +71,11 @@ export function usePhotos() {
           searchRef.current || null,
           sortByRef.current,
           sortOrderRef.current,
-          filterTagsRef.current
+          tagFilterRef.current,
+          recursive,
+          null,
+          null,
+          collapseStacksRef.current
         );
         dispatch({ type: "SET_PHOTOS", photos });
       } catch (err) {
@@ -77,41 +90,61 @@ export function usePhotos() {
     [dispatch]
   );
 
-  // Re-query DB when search or sort params change, or similarity search changes
+  // Re-query DB when search or sort params change, similarity search changes, an album is opened
+  // or a saved query is applied
+  const albumId = state.selectedAlbum?.id ?? null;
+  const savedQueryId = state.activeSavedQuery?.id ?? null;
   useEffect(() => {
     const folder = selectedFolderRef.current;
-    if (!folder) return;
+    if (!folder && albumId === null) return;
 
     let cancelled = false;
     dispatch({ type: "SET_QUERYING", querying: true });
     (async () => {
       try {
-        let photos;
-        if (state.similaritySearch) {
-          photos = await findSimilarPhotos(
+        if (state.similaritySearch && folder) {
+          const photos = await findSimilarPhotos(
             folder,
             state.similaritySearch.referencePath,
-            state.similaritySearch.threshold
+            state.similaritySearch.threshold,
+            state.includeSubfolders
           );
+          if (!cancelled) dispatch({ type: "SET_PHOTOS", photos });
         } else {
-          photos = await queryPhotos(
-            folder,
-            state.searchQuery || null,
-            state.sortBy,
-            state.sortOrder,
-            state.filterTags
+          // Stream pages so the grid renders the first photos right away
+          let first = true;
+          await streamPhotos(
+            {
+              folder: folder ?? "",
+              albumId,
+              search: state.searchQuery || null,
+              sortBy: state.sortBy,
+              sortOrder: state.sortOrder,
+              tagFilter: state.tagFilter,
+              recursive: state.includeSubfolders,
+              collapseStacks: state.collapseStacks,
+            },
+            (page) => {
+              if (cancelled) return;
+              dispatch({ type: first ? "SET_PHOTOS" : "APPEND_PHOTOS", photos: page.photos });
+              first = false;
+            }
           );
         }
-        if (!cancelled) dispatch({ type: "SET_PHOTOS", photos });
       } catch (err) {
         console.error("Failed to query photos:", err);
+        // Search syntax errors point at the offending character, so show them to the user
+        const appError = err as AppError;
+        if (!cancelled && typeof appError?.position === "number") {
+          dispatch({ type: "SET_ERROR", error: appError.message });
+        }
       } finally {
         if (!cancelled) dispatch({ type: "SET_QUERYING", querying: false });
       }
     })();
 
     return () => { cancelled = true; };
-  }, [state.searchQuery, state.sortBy, state.sortOrder, state.filterTags, state.similaritySearch, dispatch]);
+  }, [state.searchQuery, state.sortBy, state.sortOrder, state.tagFilter, state.includeSubfolders, state.collapseStacks, state.similaritySearch, albumId, savedQueryId, dispatch]);
 
   // Listen for backend indexing-progress events → drive indexingState + progressive similarity results
   useEffect(() => {
@@ -127,7 +160,7 @@ export function usePhotos() {
           const folder = selectedFolderRef.current;
           if (folder && sim) {
             // Final re-query for similarity search
-            findSimilarPhotos(folder, sim.referencePath, sim.threshold)
+            findSimilarPhotos(folder, sim.referencePath, sim.threshold, recursiveRef.current)
               .then((photos) => {
                 dispatch({ type: "SET_PHOTOS", photos });
                 notifActionsRef.current.replaceNotification("similarity-status", {
@@ -140,7 +173,7 @@ export function usePhotos() {
               .catch(console.error);
           } else if (folder) {
             // Re-query photos so has_embedding flags are updated
-            queryPhotos(folder, searchRef.current || null, sortByRef.current, sortOrderRef.current, filterTagsRef.current)
+            queryPhotos(folder, searchRef.current || null, sortByRef.current, sortOrderRef.current, tagFilterRef.current, recursiveRef.current, null, null, collapseStacksRef.current)
               .then((photos) => dispatch({ type: "SET_PHOTOS", photos }))
               .catch(console.error);
           }
@@ -165,7 +198,7 @@ export function usePhotos() {
           const folder = selectedFolderRef.current;
           if (folder && sim && event.payload.current - lastRetryRef.current >= 10) {
             lastRetryRef.current = event.payload.current;
-            findSimilarPhotos(folder, sim.referencePath, sim.threshold)
+            findSimilarPhotos(folder, sim.referencePath, sim.threshold, recursiveRef.current)
               .then((photos) => {
                 if (photos.length > 0) {
                   dispatch({ type: "SET_PHOTOS", photos });
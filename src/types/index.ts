@@ -10,6 +10,8 @@ export interface DirEntry {
 }
 
 export interface PhotoEntry {
+  // Library id, used in image URLs
+  id: number;
   name: string;
   path: string;
   size: number;
@@ -18,6 +20,25 @@ export interface PhotoEntry {
   width?: number | null;
   height?: number | null;
   has_embedding: boolean;
+  rating: number;
+  color_label: ColorLabel | null;
+  flag: PhotoFlag;
+  // Burst / series stack; stack_size is the number of photos in it, 0 when not stacked
+  stack_id: number | null;
+  stack_size: number;
+  // Other files of a RAW+JPEG pair shown as this photo; deletes, moves and copies include them
+  companions: string[];
+}
+
+export type ColorLabel = "red" | "yellow" | "green" | "blue" | "purple";
+export type PhotoFlag = "none" | "pick" | "reject";
+
+/** Culling conditions for query_photos; empty lists don't filter. */
+export interface CullingFilter {
+  rating_min?: number | null;
+  rating_max?: number | null;
+  color_labels?: ColorLabel[];
+  flags?: PhotoFlag[];
 }
 
 export interface ExifData {
@@ -39,6 +60,127 @@ export interface ExifData {
   white_balance: string | null;
 }
 
+/** Filters and ordering for the query_photos family of commands. */
+export interface PhotoQuery {
+  folder: string;
+  search: string | null;
+  sortBy: string;
+  sortOrder: string;
+  tagFilter: TagFilterState | null;
+  recursive?: boolean;
+  exifFilter?: ExifFilter | null;
+  cullingFilter?: CullingFilter | null;
+  // Scope to this album instead of `folder`
+  albumId?: number | null;
+  similarity?: SimilaritySearch | null;
+  // Show each stack as its cover photo only
+  collapseStacks?: boolean;
+}
+
+/** A query stored in the library; pinned ones are shown as smart albums. */
+export interface SavedQuery {
+  id: number;
+  name: string;
+  query: PhotoQuery;
+  pinned: boolean;
+  // Photos matched when the query last ran, null if it never ran
+  cached_count: number | null;
+  created: number;
+}
+
+/** Photos with identical content; the suggested copy to keep comes first. */
+export interface DuplicateGroup {
+  hash: string;
+  size: number;
+  paths: string[];
+}
+
+export interface DuplicateReport {
+  groups: DuplicateGroup[];
+  // Bytes freed by keeping one copy per group
+  wasted_bytes: number;
+  // Files hashed by this run; hashes from earlier runs are reused
+  hashed: number;
+}
+
+/** Look-alike photos by perceptual hash; the suggested photo to keep comes first. */
+export interface NearDuplicateGroup {
+  paths: string[];
+  // Largest Hamming distance (of 64 bits) from the first photo
+  max_distance: number;
+}
+
+export interface Album {
+  id: number;
+  name: string;
+  created: number;
+  photo_count: number;
+  cover_path: string | null;
+}
+
+/** Keyset pagination cursor returned by the backend; pass it back unchanged. */
+export interface PageCursor {
+  sort_key: unknown[];
+  last_id: number;
+}
+
+export interface PhotoPage {
+  photos: PhotoEntry[];
+  next_cursor: PageCursor | null;
+  /** Matches across all pages; only set on the first page, reuse it for the rest. */
+  total: number | null;
+}
+
+/** Why an image failed to decode. */
+export type DecodeErrorKind = "unsupported" | "corrupt" | "too_large" | "io";
+
+/**
+ * Error returned by backend commands. `position` is set for search query parse errors,
+ * `kind` for image decode errors.
+ */
+export interface AppError {
+  message: string;
+  position?: number;
+  kind?: DecodeErrorKind;
+}
+
+/** Filters on EXIF fields stored at import time; all set fields must match. */
+export interface ExifFilter {
+  camera?: string;
+  lens?: string;
+  iso_min?: number;
+  iso_max?: number;
+  f_number_min?: number;
+  f_number_max?: number;
+  focal_length_min?: number;
+  focal_length_max?: number;
+  /** "YYYY-MM-DD" or a prefix such as "2023-06" */
+  date_from?: string;
+  date_to?: string;
+  has_gps?: boolean;
+}
+
+export interface FormatSupport {
+  extension: string;
+  decodable: boolean;
+  /** False for formats this build skips when scanning, e.g. AVIF without the `avif` feature */
+  imported: boolean;
+}
+
+/** Caps on a single image decode; larger images fail with "Image too large". */
+export interface DecodeLimits {
+  max_pixels: number;
+  // Bytes
+  max_alloc: number;
+}
+
+/** On-disk thumbnail cache usage; sizes in bytes. */
+export interface ThumbnailCacheInfo {
+  files: number;
+  size_bytes: number;
+  max_bytes: number;
+}
+
 export interface ModelStatus {
   downloaded: boolean;
   loading: boolean;
@@ -51,6 +193,20 @@ export interface Prediction {
   confidence: number;
 }
 
+export interface PhotoTag {
+  tag: string;
+  // "manual", or the name of the model that predicted the tag
+  source: string;
+  confidence: number | null;
+}
+
+export interface TagSummary {
+  tag: string;
+  photo_count: number;
+  avg_confidence: number | null;
+  manual_count: number;
+}
+
 export interface ClassifyResult {
   file_name: string;
   file_path: string;
@@ -74,8 +230,16 @@ export interface SimilaritySearch {
 }
 
 export type ViewMode = "grid" | "list";
-export type SortBy = "name" | "size" | "date";
+export type SortBy = "name" | "size" | "date" | "date_taken" | "camera" | "iso" | "rating" | "label" | "flag" | "position" | "similarity" | "relevance";
 export type SortOrder = "asc" | "desc";
+export type TagMatchMode = "all" | "any";
+
+/** Tag filter as set in the dropdown: included tags combined by `mode`, minus excluded tags. */
+export interface TagFilterState {
+  include: string[];
+  exclude: string[];
+  mode: TagMatchMode;
+}
 
 export interface TreeNodeData {
   name: string;
@@ -102,6 +266,12 @@ export interface AppState {
   drives: DriveInfo[];
   treeNodes: TreeNodeData[];
   selectedFolder: string | null;
+  albums: Album[];
+  // Album being browsed; mutually exclusive with selectedFolder
+  selectedAlbum: Album | null;
+  savedQueries: SavedQuery[];
+  // Saved query whose view is shown, until another folder or album is opened
+  activeSavedQuery: SavedQuery | null;
   photos: PhotoEntry[];
   selectedPhoto: PhotoEntry | null;
   exifData: ExifData | null;
@@ -113,11 +283,14 @@ export interface AppState {
   loading: boolean;
   error: string | null;
   selectedPaths: string[];
-  filterTags: string[];
+  tagFilter: TagFilterState;
+  includeSubfolders: boolean;
+  collapseStacks: boolean;
   showExif: boolean;
   showOverlay: boolean;
   classifyDialog: ClassifyDialogState;
   contextMenu: ContextMenuState | null;
+  duplicatesDialog: DuplicatesDialogState | null;
   similaritySearch: SimilaritySearch | null;
   querying: boolean;
   indexingState: IndexingState | null;
@@ -134,6 +307,11 @@ export interface ClassifyDialogState {
   error: string | null;
 }
 
+export interface DuplicatesDialogState {
+  // Folder searched with its subfolders; null searches the whole library
+  folderPath: string | null;
+}
+
 export interface ContextMenuState {
   x: number;
   y: number;
@@ -147,6 +325,7 @@ export type AppAction =
   | { type: "TOGGLE_TREE_NODE"; path: string }
   | { type: "SET_SELECTED_FOLDER"; path: string | null }
   | { type: "SET_PHOTOS"; photos: PhotoEntry[] }
+  | { type: "APPEND_PHOTOS"; photos: PhotoEntry[] }
   | { type: "SET_SELECTED_PHOTO"; photo: PhotoEntry | null }
   | { type: "SET_EXIF"; data: ExifData | null }
   | { type: "SET_HISTOGRAM"; data: string | null }
@@ -158,15 +337,23 @@ export type AppAction =
   | { type: "SET_ERROR"; error: string | null }
   | { type: "TOGGLE_EXIF" }
   | { type: "TOGGLE_OVERLAY" }
+  | { type: "TOGGLE_INCLUDE_SUBFOLDERS" }
+  | { type: "TOGGLE_COLLAPSE_STACKS" }
   | { type: "SET_CLASSIFY_DIALOG"; state: Partial<ClassifyDialogState> }
   | { type: "SET_CONTEXT_MENU"; menu: ContextMenuState | null }
+  | { type: "SET_DUPLICATES_DIALOG"; state: DuplicatesDialogState | null }
   | { type: "UPDATE_PHOTO_TAGS"; updates: { path: string; tags: string[] }[] }
+  | { type: "UPDATE_PHOTO_CULLING"; paths: string[]; changes: Partial<Pick<PhotoEntry, "rating" | "color_label" | "flag">> }
+  | { type: "SET_ALBUMS"; albums: Album[] }
+  | { type: "SET_SELECTED_ALBUM"; album: Album | null }
+  | { type: "SET_SAVED_QUERIES"; queries: SavedQuery[] }
+  | { type: "APPLY_SAVED_QUERY"; saved: SavedQuery }
   | { type: "SET_SELECTION"; paths: string[] }
   | { type: "TOGGLE_SELECTION"; path: string }
   | { type: "RANGE_SELECTION"; from: number; to: number }
   | { type: "SELECT_ALL" }
   | { type: "CLEAR_SELECTION" }
-  | { type: "SET_FILTER_TAGS"; tags: string[] }
+  | { type: "SET_TAG_FILTER"; filter: TagFilterState }
   | { type: "SET_SIMILARITY_SEARCH"; search: SimilaritySearch | null }
   | { type: "SET_SIMILARITY_THRESHOLD"; threshold: number }
   | { type: "SET_QUERYING"; querying: boolean }